@@ -0,0 +1,204 @@
+//! Cross-flag invariants, declared with `invariant("...")` on the container.
+//!
+//! The grammar is a tiny boolean language over the struct's `bool` fields:
+//!
+//! ```text
+//! implies := or ("implies" | "=>") implies | or
+//! or      := and (("or" | "||") and)*
+//! and     := not (("and" | "&&") not)*
+//! not     := ("not" | "!") not | "(" implies ")" | field
+//! ```
+
+use proc_macro2::{Ident, Span};
+
+/// A parsed invariant expression.
+#[derive(Debug)]
+pub(crate) enum Expr {
+    Flag(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Implies(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Every field the expression refers to.
+    pub(crate) fn flags(&self) -> Vec<&str> {
+        match self {
+            Expr::Flag(flag) => vec![flag],
+            Expr::Not(expr) => expr.flags(),
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) | Expr::Implies(lhs, rhs) => {
+                let mut flags = lhs.flags();
+                flags.extend(rhs.flags());
+                flags
+            }
+        }
+    }
+
+    /// The expression evaluated against `self`'s fields.
+    pub(crate) fn to_tokens(&self) -> proc_macro2::TokenStream {
+        match self {
+            Expr::Flag(flag) => {
+                let flag = Ident::new(flag, Span::call_site());
+                quote::quote!(self.#flag)
+            }
+            Expr::Not(expr) => {
+                let expr = expr.to_tokens();
+                quote::quote!(!(#expr))
+            }
+            Expr::And(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.to_tokens(), rhs.to_tokens());
+                quote::quote!((#lhs && #rhs))
+            }
+            Expr::Or(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.to_tokens(), rhs.to_tokens());
+                quote::quote!((#lhs || #rhs))
+            }
+            Expr::Implies(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.to_tokens(), rhs.to_tokens());
+                quote::quote!((!(#lhs) || #rhs))
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Flag(String),
+    Not,
+    And,
+    Or,
+    Implies,
+    Open,
+    Close,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Flag(flag) => write!(f, "`{}`", flag),
+            Token::Not => write!(f, "`not`"),
+            Token::And => write!(f, "`and`"),
+            Token::Or => write!(f, "`or`"),
+            Token::Implies => write!(f, "`implies`"),
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_ascii_alphanumeric() && c != '_' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(match word.as_str() {
+                "not" => Token::Not,
+                "and" => Token::And,
+                "or" => Token::Or,
+                "implies" => Token::Implies,
+                _ => Token::Flag(word),
+            });
+        } else {
+            chars.next();
+            let token = match (c, chars.peek()) {
+                ('(', _) => Token::Open,
+                (')', _) => Token::Close,
+                ('!', _) => Token::Not,
+                ('&', Some('&')) => Token::And,
+                ('|', Some('|')) => Token::Or,
+                ('=', Some('>')) => Token::Implies,
+                _ => return Err(format!("unexpected `{}`", c)),
+            };
+            if matches!(token, Token::And | Token::Or | Token::Implies) {
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, token: Token) -> bool {
+        if self.tokens.get(self.pos) == Some(&token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn implies(&mut self) -> Result<Expr, String> {
+        let lhs = self.or()?;
+        if self.eat(Token::Implies) {
+            return Ok(Expr::Implies(Box::new(lhs), Box::new(self.implies()?)));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.eat(Token::Or) {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.not()?;
+        while self.eat(Token::And) {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.eat(Token::Not) {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.eat(Token::Open) {
+            let expr = self.implies()?;
+            if !self.eat(Token::Close) {
+                return Err("missing `)`".to_string());
+            }
+            return Ok(expr);
+        }
+        match self.tokens.get(self.pos) {
+            Some(Token::Flag(flag)) => {
+                self.pos += 1;
+                Ok(Expr::Flag(flag.clone()))
+            }
+            Some(token) => Err(format!("expected a flag name, found {}", token)),
+            None => Err("expected a flag name, found the end of the invariant".to_string()),
+        }
+    }
+}
+
+/// Parses an invariant like `clowntown_fast implies clowntown_unsafe`.
+pub(crate) fn parse(source: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let expr = parser.implies()?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(format!("unexpected {} after the invariant", token));
+    }
+    Ok(expr)
+}
@@ -0,0 +1,6605 @@
+//! Procedural macros of [`clowncopterize`](https://docs.rs/clowncopterize/).
+//!
+//! Depend on `clowncopterize` rather than on this crate: the generated code relies on its runtime
+//! support.
+
+mod invariant;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use quote::ToTokens;
+
+const CLOWNCOPTERIZE_PREFIX: &str = "clowntown";
+const CLOWNCOPTERIZE_FLAG: &str = "clowncopterize";
+/// Long flags clap generates itself, which an injected flag can't take.
+const CLAP_RESERVED: &[&str] = &["help", "version"];
+/// Help heading of the clowntown flags in `warning_style` mode.
+const CLOWNTOWN_HEADING: &str = "Clowntown";
+/// Bold yellow, and back.
+const WARNING_STYLE: &str = "\x1b[1;33m";
+const WARNING_STYLE_RESET: &str = "\x1b[0m";
+
+// used to parse Named Fields from a TokenStream so we can inject our ew --clowncopterize parameter
+// https://github.com/dtolnay/syn/issues/651#issuecomment-503771863
+struct ParsableNamedField {
+    pub field: syn::Field,
+}
+
+impl syn::parse::Parse for ParsableNamedField {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::parse::Result<Self> {
+        let field = syn::Field::parse_named(input)?;
+
+        Ok(ParsableNamedField { field })
+    }
+}
+
+/// Clowncopterize the underlying clap parser
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+///
+/// // by default, uses `--clowncopterize` flag:
+///
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     /// Optional name to operate on
+///     name: Option<String>,
+///
+///     /// Turn debugging information on
+///     #[arg(long)]
+///     clowntown_this: bool,
+///
+///     /// lists test values
+///     #[arg(long)]
+///     clowntown_that: bool,
+/// }
+///
+///
+/// let cli = Cli::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+///
+/// println!("Cli! {:#?}", cli);
+/// assert!(cli.clowntown_this);
+///
+/// // but can be customized with the `clowncopterizer` attribute:
+///
+/// #[clowncopterize::clowncopterize(clowncopterizer = "i-live-in-clowntown")]
+/// #[derive(Parser, Debug)]
+/// struct CliCustom {
+///     /// Optional name to operate on
+///     name: Option<String>,
+///
+///     /// Turn debugging information on
+///     #[arg(long)]
+///     clowntown_this: bool,
+///
+///     /// lists test values
+///     #[arg(long)]
+///     clowntown_that: bool,
+/// }
+///
+///
+/// let cli = CliCustom::try_parse_from(vec!["prog", "--i-live-in-clowntown"]).unwrap();
+///
+/// println!("Cli! {:#?}", cli);
+/// assert!(cli.clowntown_this);
+/// ```
+///
+/// # Options
+///
+/// Options are comma-separated inside the attribute, each given at most once, except the ones
+/// adding to a list (`external`, `deny_env` and `invariant`). Two options naming the same
+/// injected flag are an error.
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize(clowncopterizer = "danger", shadow = "danger")]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_drop: bool,
+/// }
+/// ```
+///
+/// * `clowncopterizer = "name"`: name of the injected flag (default: `clowncopterize`). Like the
+///   other flag names below, it can be a kebab-case, snake_case or camelCase string, normalized
+///   to a snake_case field name, or a bare identifier: `clowncopterizer = i_live_in_clowntown`.
+///   Names that can't make a field name, e.g. with spaces or a leading digit, and clap's own
+///   `help` and `version`, are compile errors.
+/// * `long = "all-the-clowns"`: the long flag of the injected `clowncopterizer`, when it
+///   shouldn't follow the field name, e.g. for product branding. The field keeps the
+///   `clowncopterizer` name.
+/// * `long_help = "..."`: the `--help` of the injected `clowncopterizer`, shown as is, so it can
+///   take several paragraphs warning about what mass-enabling does, e.g. from
+///   `include_str!("clowncopterize.txt")`. `-h` keeps the one-line help. With `localize`, its key
+///   is the field name followed by `_long_help`.
+/// * `vis = "pub"`: the visibility of the injected fields, e.g. `"pub(crate)"`, or `"inherit"`
+///   for the struct's own, so other crates can build the struct, e.g. in integration tests.
+///   They are private by default.
+/// * `extra_attrs("serde(skip)", "builder(default)")`: attributes to put on the injected fields,
+///   for the struct's other derives, which would otherwise not know what to do with them.
+/// * `inject = "#[arg(long, hide = true)] pub clowncopterize: bool"`: the whole `clowncopterizer`
+///   field, attributes included, for what the other options don't cover; a `{ ... }` block of
+///   the same tokens works too. Its name is the `clowncopterizer`, and its `long`, if any, the
+///   flag the generated code mentions. The clowntown flags are wired to it as usual. It can't be
+///   combined with the options shaping that field: `clowncopterizer`, `long`, `action`,
+///   `repeatable`, `value_parser` and `token_key`.
+/// * `unclowncopterizer` or `unclowncopterizer = "name"`: also inject a flag turning every
+///   clowntown flag off (default name: `un-` followed by the `clowncopterizer` one). Flags set
+///   explicitly or from the environment are only forced off when parsing with the generated
+///   `clowntown_parse` constructors, see below.
+/// * `ack_flag` or `ack_flag = "name"`: also inject a `--clowncopterize-ack` flag (default name:
+///   the `clowncopterizer` one followed by `-ack`) every master flag requires, so mass-enabling
+///   the clowntown flags takes two deliberate actions. Their help mentions the pairing.
+/// * `reason` or `reason = "name"`: also inject a `--clowncopterize-reason <REASON>` flag
+///   (default name: the `clowncopterizer` one followed by `-reason`), an `Option<String>` every
+///   master flag requires, so the transcript of an incident says why clowntown was entered.
+/// * `companions`: inject both the `ack_flag` and `reason` flags, with their default names, each
+///   requiring the other along with being required by the masters. Name them with `ack_flag` and
+///   `reason` instead.
+/// * `until` or `until = "name"`: also inject a `--clowncopterize-until <DEADLINE>` flag
+///   (default name: the `clowncopterizer` one followed by `-until`) taking an RFC 3339 time, like
+///   `2026-01-31T18:00:00Z`, past which parsing fails, so incident commands copied from old
+///   runbooks don't re-enter clowntown weeks later.
+/// * `shadow` or `shadow = "name"`: also inject a `--clowncopterize-shadow` flag (default name:
+///   the `clowncopterizer` one followed by `-shadow`) recording the intent to use the master
+///   without turning any flag on: in the `report`, as the `clowntown.master_shadowed` metric,
+///   and to the `sink`. It measures how often mass-enabling would be used before allowing it.
+/// * `completions` or `completions = "name"`: with the `completions` feature, also inject a
+///   `--clowntown-completions <SHELL>` flag (default name: `clowntown-completions`) printing the
+///   shell completions of the command and exiting, when parsing with the `clowntown_parse`
+///   constructors. Like `--help`, the `try_` ones return them as a `DisplayHelp` error instead.
+///   `clowntown_completions(shell, out)` writes them anywhere else, e.g. from a build script.
+/// * `provenance` or `provenance = "name"`: also inject a `--clowntown-provenance` flag
+///   (default name: `clowntown-provenance`) printing, once parsing and its checks are done, a
+///   JSON document of every clowntown flag's value and where it comes from, and exiting, or as a
+///   `DisplayHelp` error from the `try_` constructors and `clowntown_post_parse`. It lets
+///   orchestration systems check the effective risky configuration of a job before running it.
+///   `clowntown_provenance(&matches)` builds the document anywhere else.
+/// * `warning_style = true`: list the clowntown flags and the injected flags under a
+///   "Clowntown" heading (or their severity one), with the heading and the flags' help in bold
+///   yellow. Clap strips the colors when they are off, e.g. with `NO_COLOR`.
+/// * `after_help = true`: append "The following flags are mass-enabled by --clowncopterize: ..."
+///   to the command's `after_help`, one line per master flag, after the struct's own
+///   `after_help` if any.
+/// * `version_audit = true`: append " (clowntown: N flags, --clowncopterize)" to the
+///   command's `version`, so support can tell from `--version` whether a build has clowntown
+///   flags. The struct needs a `#[command(version)]`, or a `version = ...` that `concat!` takes,
+///   like a literal or `env!(...)`.
+/// * `external(in = "dep::CommonArgs")`: wire the clowntown flags of a flattened `Args` struct
+///   we can't annotate, typically from another crate, to the `clowncopterizer` (and the
+///   `unclowncopterizer`). Its `SetTrue` flags whose id starts with `prefix = "..."` (default:
+///   the first of the struct's prefixes) get the wiring at runtime, in the command returned by
+///   `clowntown_command()`, which the `clowntown_parse` constructors use, as the declared flags
+///   would: with `repeatable`, `select` or `mode`, only `all` turns them on. Repeat it for several
+///   structs.
+/// * `guard = "crate::guards::allow_clowntown"`: a
+///   `fn(&clowncopterize::ClowntownGuardContext) -> Result<(), String>` the `clowntown_parse`
+///   constructors call for every master flag that is on, with the host name and the flags it
+///   turns on. An `Err` refuses the master, like any other clap error, with the reason. As
+///   `guard(fn = "...", env = "APP_ENV")`, the context also carries the value of `APP_ENV`.
+/// * `action = "SetTrue"`, `"Count"` or `"Set"`: the clap action of the injected master flags.
+///   `"SetTrue"` is the default. With `"Count"`, the masters are `u8` fields counting their uses,
+///   e.g. for `-vv`-like thresholds in the program, and any use turns the clowntown flags on.
+///   `"Set"` makes them take a non-empty value, or one checked by `value_parser`.
+/// * `repeatable`: the `clowncopterizer` takes the name of a `group`, and can be repeated to turn
+///   the flags of several groups on, as `--clowncopterize db --clowncopterize net`. `all` turns
+///   every clowntown flag on, including the ones without a group. The field is then a
+///   `Vec<String>` of the requested groups. It can't be combined with `action`, `value_parser` or
+///   `token_key`.
+/// * `select`: the `clowncopterizer` takes `all`, `none` or a comma-separated list of clowntown
+///   flags, by their long name without the dashes, as
+///   `--clowncopterize=clowntown-drop,clowntown-this`. Clap refuses unknown names. `none` turns
+///   nothing on, so a script can pass the flag unconditionally. The `clowntown_parse` constructors,
+///   and any parse of `clowntown_command()` followed by `clowntown_post_parse`, also take the
+///   patterns of `clowntown_select`, as `--clowncopterize='clowntown-db-*'` or
+///   `--clowncopterize=db-dr`; plain clap parsing refuses them, as nothing would resolve them. The
+///   field is then a `Vec<String>` of the given values. It can't be combined with `repeatable`,
+///   `action`, `value_parser` or `token_key`.
+/// * `mode` or `mode = "Name"`: the `clowncopterizer` takes a generated `ValueEnum` (default
+///   name: `ClowncopterizeMode`), as `--clowncopterize=off|dry-run|all`. A bare `--clowncopterize`
+///   is `all`. `dry-run` turns nothing on but records the intent like the `shadow` flag does, in
+///   the `report`, the metrics and to the `sink`. The enum has `is_on()` and `is_dry_run()`. It
+///   can't be combined with `shadow`, `repeatable`, `select`, `action`, `value_parser` or
+///   `token_key`.
+/// * `value_parser = ...`: the master flags take a value, as `--clowncopterize=<VALUE>`, checked
+///   by this clap `TypedValueParser`, e.g.
+///   `clap::builder::PossibleValuesParser::new(["i-am-sure"])` for a confirmation string or
+///   `clap::value_parser!(u8).range(1..=3)`. Clap refuses invalid values like for any other arg; a
+///   valid one turns the clowntown flags on. It can't be combined with `token_key`.
+/// * `token_key = "crate::keys::clowntown_key"`: with the `token` feature, the master flags
+///   take a time-limited token, as `--clowncopterize=<TOKEN>`, checked against the key returned
+///   by a `fn() -> Option<Vec<u8>>` while parsing. Operators get tokens from
+///   `clowncopterize::clowntown_token`; an invalid or expired one, or no key, fails the parse.
+///   `generate_tests` and `#[clowncopterize_test]` skip the master checks, which need a token.
+/// * `sink = "crate::telemetry::SINK"`: a static implementing `clowncopterize::ClowntownSink`,
+///   which the `clowntown_parse` constructors tell about the master flags and clowntown flags in
+///   use, for any metrics backend. `record_clowntown_to(&sink)` reports to another one.
+/// * `env_filter`: the `clowntown_parse` constructors only let the masters turn on the
+///   clowntown flags matching the comma-separated glob patterns of the `CLOWNTOWN_ALLOW`
+///   environment variable, if set, and none matching those of `CLOWNTOWN_BLOCK`, so platform
+///   owners can constrain mass-enabling per environment. Flags set explicitly are left alone,
+///   and a pattern matching no flag is an error.
+/// * `report = "stderr"`: when a master flag is on, the `clowntown_parse` constructors print on
+///   stderr which clowntown flags it forced on, and which it left alone because they were set
+///   explicitly, so the choice shows up in incident transcripts. `clowntown_report(&matches)`
+///   returns the lines. The default is `report = "off"`.
+/// * `sentinel = "/etc/allow-clowntown"`: the `clowntown_parse` constructors refuse the master
+///   flags, like any other clap error, unless that file exists, so machine owners can veto
+///   mass-enabling on their hosts by not creating it.
+/// * `deny_env = "APP_ENV=production"`: the `clowntown_parse` constructors refuse the master
+///   flags, like any other clap error, when the environment variable has that value, or is set
+///   at all with `deny_env = "NAME"`. Repeat it for several variables.
+/// * `deny_release = true`: fail the build when `debug_assertions` are off, unless the
+///   `CLOWNTOWN_ALLOW_RELEASE` environment variable is set when building, so production binaries
+///   don't ship the master flags by accident.
+/// * `policy = "clowntown-policy.toml"`: a TOML file, relative to the crate root, whose
+///   `never_mass_enable` list names clowntown flags (by field name or long flag) no master may
+///   turn on. Wiring one of them is a compile error, so the org policy is enforced by the build.
+/// * `localize = path::to::tr`: translate the help we generate through a
+///   `fn(key: &str, default: &str) -> String`, called when building the command with the English
+///   default. The keys are the field names of the injected flags for their help, and `since`,
+///   `runbook` and `deprecated` for the labels of the notes appended to the `--help` of clowntown
+///   flags.
+/// * `clowntown_help` or `clowntown_help = "name"`: also inject a `--clowntown-help` flag
+///   (default name: `clowntown-help`) printing the help of the clowntown flags and their masters
+///   only, and exiting, when parsing with the `clowntown_parse` constructors. Like `--help`, it
+///   wins over missing arguments, and the `try_` ones return it as a `DisplayHelp` error.
+///   `clowntown_help_command()` returns the filtered command.
+/// * `subcommand` or `subcommand = "name"`: activate the `clowncopterizer` with a subcommand
+///   (default name: the `clowncopterizer` one), as `prog clowncopterize -- <args>`, when parsing
+///   with the `clowntown_parse` constructors, for CLIs whose style guide forbids
+///   behavior-changing global flags. The flag itself is hidden, and `clowntown_command()` lists
+///   the subcommand instead. The master can't take a value then.
+/// * `runtime_max = N`: the `clowntown_parse` constructors fail, like any other clap error, when
+///   more than `N` clowntown flags end up enabled without going through a master flag.
+/// * `warn_above = N`: emit a compile-time warning, without failing the build, when the struct
+///   has more than `N` clowntown flags, nudging the team to retire some. See
+///   `assert_max_flags!` for a hard limit.
+/// * `strip_prefix = true`: expose the clowntown flags without their `clowntown` prefix, e.g.
+///   `--this` for `clowntown_this`, or `strip_prefix = "ct"` to use a shorter one, e.g.
+///   `--ct-this`. The fields keep their names, so the wiring and the generated code don't change.
+///   Flags with an explicit `long = "..."` are left alone.
+/// * `prefix = "name"`: wire the `bool` fields starting with `name` instead of `clowntown`, e.g.
+///   `prefix = "danger"` for `danger_drop_db`. `strip_prefix` then strips that prefix.
+///   `prefixes("clowntown", "danger")` wires the fields starting with any of them.
+/// * `groups(db, net)`: the only groups the fields can join with `#[clowntown(group = "...")]`,
+///   so a typo in a group name is a compile error instead of a new master flag.
+/// * `hidden = true`: hide the injected master flags from the help. They still work, for
+///   runbooks that know about them.
+/// * `exclusive = true`: the injected master flags can't be combined with any other argument, so
+///   turning the clowntown flags on is an all-or-nothing invocation. It can't be combined with
+///   `ack_flag`, `reason` or `companions`, nor used on a struct with a `#[command(subcommand)]`
+///   field.
+/// * `global = true`: make the injected flags `global`, as they are when the struct has a
+///   `#[command(subcommand)]` field, e.g. for subcommands added by a flattened struct.
+/// * `hide_flags = true`: hide the clowntown flags from the help, so only the master flags are
+///   discoverable. They still parse as usual.
+/// * `locked = true`: the clowntown flags require their master, so they can only be used once
+///   clowntown mode is entered explicitly. `#[clowncopterize_test]` then only checks them along
+///   their master.
+/// * `manifest`: export a JSON manifest of the clowntown flags for fleet auditing tools: as the
+///   `CLOWNTOWN_MANIFEST_JSON` const, as a line in the `.clowntown` link section of the binary
+///   (`__DATA,__clowntown` on Apple targets, `.clwntwn` on Windows), and through the generated
+///   `write_clowntown_manifest(dir)`, writing `dir/clowntown-<Struct>.json` for an xtask or the
+///   build script of a dependent crate. `clowntown_manifest()` carries the same metadata, typed.
+/// * `invariant("...")`: a boolean expression over the struct's `bool` fields that must hold
+///   once parsed, like `invariant("clowntown_fast implies clowntown_unsafe")`. It supports `not`,
+///   `and`, `or`, `implies` (or `!`, `&&`, `||`, `=>`) and parentheses, and can be repeated. The
+///   invariants are checked by the generated `validate` method, which the `clowntown_parse`
+///   constructors call.
+/// * `panic_in_tests`: under `cfg(test)`, the `clowntown_parse` constructors panic when a master
+///   flag is on, so unit tests can't depend on every clowntown flag being on by accident.
+/// * `toggle`: the master flips every flag from its declared default (`default_value_t`,
+///   `default_value` or a `SetFalse` action) instead of setting it to true.
+/// * `default_true = "warn"`, `"skip"` or `"invert"`: what to do with a flag whose declared
+///   default is already the value its masters set, like `#[arg(default_value_t = true)]`. The
+///   masters leave it alone, and `"warn"`, the default, says so with a compile-time warning;
+///   `"skip"` doesn't. `"invert"` has them set it to the opposite instead, as `invert` would.
+/// * `verbose`: report, as compile-time warnings, which fields were matched, which were
+///   skipped and why, and the name of the injected flag.
+/// * `generate_tests = true`: emit a `#[cfg(test)]` module checking that the struct parses with
+///   no arguments, that every matched flag defaults to its safe value, and that the injected flag
+///   sets all of them. The struct must parse without any other argument for these tests to pass.
+///
+/// A policy violation fails the build:
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// // never_mass_enable = ["clowntown_drop_prod", "--clowntown-wipe"]
+/// #[clowncopterize::clowncopterize(policy = "../tests/clowntown-policy.toml")]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_wipe: bool,
+/// }
+/// ```
+///
+/// # Docs
+///
+/// `CLOWNTOWN_DOCS_MD` is a markdown table of the clowntown flags, with their help, owner,
+/// introducing version and expiry, ready to embed in a runbook. `clowntown_manifest()` returns
+/// the same metadata as `clowncopterize::ClownFlagInfo` values, for tooling.
+///
+/// The struct and its clowntown fields get the rustdoc aliases `clowntown` and `dangerous`, and
+/// each field its flag as typed, e.g. `clowntown-this`, so searching the API docs finds them.
+///
+/// # Parsing
+///
+/// Structs deriving `Parser` get `clowntown_parse`, `clowntown_try_parse`, `clowntown_parse_from`
+/// and `clowntown_try_parse_from`. They behave like their `clap::Parser` counterparts, then run
+/// the checks and fixups that clap can't express through arg attributes alone.
+///
+/// `clowntown_builder` returns a builder turning on a chosen set of clowntown flags, by field name
+/// or long flag, then either listing their long flags with `build_args` or parsing the struct
+/// with `build`:
+///
+/// ```
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_this: bool,
+///     #[arg(long)]
+///     clowntown_that: bool,
+/// }
+///
+/// let builder = Cli::clowntown_builder().enable("clowntown_this");
+/// assert_eq!(builder.build_args(), ["--clowntown-this"]);
+/// let cli = builder.build().unwrap();
+/// assert!(cli.clowntown_this && !cli.clowntown_that);
+/// ```
+///
+/// `clowntown_combinations` yields every combination of clowntown flags as argument vectors, for
+/// test matrices, and `clowntown_combinations_up_to` the ones enabling at most a given number of
+/// flags.
+///
+/// `merge_from_config` sets the clowntown flags from a `clowncopterize::ClowntownOverrides`, e.g.
+/// carried in a configuration file, below the command line and the master flags in precedence.
+///
+/// `apply_masters` sets the flags wired to the master flags that are on, for structs that don't
+/// come from clap, e.g. deserialized with `clap-serde` or any other serde format. With the `serde`
+/// feature, structs deriving `Deserialize` also get `#[serde(default)]` on their clowntown flags
+/// and injected flags, following the declared defaults, so they can be left out of the data.
+///
+/// `apply_resolver` lets a `clowncopterize::ClowntownResolver`, e.g. backed by a central
+/// feature-flag service, veto or force each clowntown flag, and `apply_resolver_async` does the
+/// same with a `clowncopterize::AsyncClowntownResolver`. `clowntown_parse_with` and
+/// `clowntown_try_parse_from_with` consult one right after parsing.
+///
+/// `reload_clowntown` updates the clowntown flags of a running program from a flag file of
+/// `flag = true|false` lines, and with the `notify` feature, `watch_clowntown` does it every time
+/// the file changes, for daemons that can't restart to flip a risky flag off.
+///
+/// With the `metrics` feature, `record_clowntown_metrics` increments the `clowntown.master_used`
+/// (labeled by `master`) and `clowntown.flag_enabled` (labeled by `flag`) counters of the
+/// `metrics` facade, for each master and clowntown flag that is on. The `clowntown_parse`
+/// constructors call it once parsing succeeds.
+///
+/// With the `prometheus` feature, `register_clowntown_gauges` registers a
+/// `clowntown_flag_enabled{flag="..."}` gauge with a `prometheus::Registry`, so services expose
+/// their risky configuration on `/metrics`, and `update_clowntown_gauges` refreshes it.
+///
+/// With the `otel` feature, `clowntown_otel_attributes` returns the clowntown flags as
+/// OpenTelemetry `KeyValue`s, `clowntown.<field>` set to whether the flag is on, for resource
+/// attributes, and `record_clowntown_span` sets them on a span.
+///
+/// With the `sentry` feature, `record_clowntown_sentry` sets a `clowntown` Sentry context, and
+/// leaves a breadcrumb, listing the master flags in use and the clowntown flags they enabled. It
+/// does nothing unless a master flag is on. The `clowntown_parse` constructors call it.
+///
+/// With the `syslog` feature, `audit_clowntown` writes an audit record to the system log
+/// (`authpriv` facility) when a master flag is on, with the user, a SHA-256 of the arguments,
+/// the masters and the enabled clowntown flags. The `clowntown_parse` constructors call it.
+///
+/// With the `inventory` feature, every clowntown flag is registered in a process-wide
+/// collection, which `clowncopterize::registered_clown_flags` iterates over, whichever crate of
+/// the binary declares it.
+///
+/// With the `figment` feature, the struct is also a `figment::Provider` of its clowntown flags,
+/// keyed by field name. Only the flags set on the command line are provided, so merging it last
+/// lets configuration files turn flags on while the master flags keep the final say:
+///
+/// ```ignore
+/// let figment = Figment::from(Toml::file("app.toml")).merge(&cli);
+/// ```
+///
+/// With the `proptest` feature, structs also deriving `Debug` get a
+/// `proptest::arbitrary::Arbitrary` impl, parsing no argument and turning on a random set of
+/// clowntown flags. The struct must parse without any other argument, and must not be generic.
+///
+/// They also get `with_all_clowntown`, a test constructor parsing the given arguments and then
+/// turning every clowntown flag on, which panics instead of exiting on error.
+///
+/// # Glob selection
+///
+/// Every struct gets `clowntown_select`, turning on the clowntown flags matching glob patterns,
+/// like `'clowntown_db_*'` or `'--clowntown-db-*'`, where `*` matches any run of characters and
+/// `?` exactly one. A pattern without wildcards can also abbreviate a single flag, like `db-dr`
+/// for `--clowntown-db-drop`. The patterns are resolved against `CLOWNTOWN_FLAGS`, and a pattern
+/// matching no flag, or abbreviating several, is an error, so typos don't go unnoticed.
+///
+/// ```
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_db_drop: bool,
+///
+///     #[arg(long)]
+///     clowntown_net: bool,
+/// }
+///
+/// let mut cli = Cli::parse_from(["prog"]);
+/// cli.clowntown_select(&["clowntown_db_*"]).unwrap();
+/// assert!(cli.clowntown_db_drop && !cli.clowntown_net);
+/// ```
+///
+/// # `ClownFlag` fields
+///
+/// Fields of type `clowncopterize::ClownFlag` are clowntown flags whatever their name, so
+/// `drop_database: ClownFlag` gets wired like `clowntown_drop_database: bool` would. They parse
+/// like a `bool` flag; declare `#[arg(default_value = "true")]` for one defaulting to on, or
+/// `#[arg(num_args = 0..=1)]` for one also taking an explicit value, as `--flag=false`. Once
+/// parsed, they dereference to their value and carry their `ClownFlagInfo`, like their `owner`.
+///
+/// The masters only fill in a flag that isn't given, so `bool` flags taking an optional value,
+/// with `num_args = 0..=1` and `default_missing_value = "true"`, keep the one given explicitly.
+///
+/// # Renaming
+///
+/// Injected flags follow the container's `#[command(rename_all = "...")]`, like the clowntown
+/// flags they wire, and so do the long flags the generated code mentions.
+///
+/// # Args structs
+///
+/// `#[derive(Args)]` structs can be clowncopterized too: their master flags end up in the
+/// command that flattens them. Every clowncopterized struct implements
+/// `clowncopterize::HasClowntownFlags` and `clowncopterize::Clowncopterized`, and converts into a
+/// `clowncopterize::ClowntownReport` with `From<&Cli>`; a parent marking a flattened field with
+/// `#[clowntown(nested)]` reports the flags of that field along its own. Both structs injecting
+/// the same master would clash, so give the flattened one its own `clowncopterizer`, or leave the
+/// master to whichever struct owns the clowntown flags.
+///
+/// `apply_clowntown_to(&mut other)` copies the values of the clowntown flags into the flags of the
+/// same name of any `Clowncopterized` type, such as a settings struct implementing it by hand.
+///
+/// # Other derives
+///
+/// The struct keeps its other derives, in their order, and every attribute but the
+/// `#[clowntown(...)]` ones, on the struct and on its fields: the rewrite only appends its own,
+/// or extends the `#[arg(...)]` of the clowntown flags, and appends the injected fields. Structs
+/// deriving `TypedBuilder` get `#[builder(default)]` on the injected fields, so the builder
+/// doesn't ask for them, unless `extra_attrs` has a `builder(...)` of its own.
+///
+/// # Subcommands
+///
+/// When the struct has a `#[command(subcommand)]` field, the injected masters are `global`, and
+/// the `clowntown_parse` constructors pass a master given before the subcommand to it too, so
+/// `prog --clowncopterize run` turns on the clowntown flags of a clowncopterized `run`, as long
+/// as both use the same master. Declared masters get the same treatment when marked `global`.
+///
+/// # Multicall binaries
+///
+/// In a multicall binary, each applet struct gets its own master flags, which only wire the
+/// flags of that applet. Parse the applets through the multicall command, then run each
+/// applet's `clowntown_post_parse` with its matches to resolve the options clap can't, like
+/// `unclowncopterizer`. To share one configuration across the applets, wrap the
+/// attribute in a `macro_rules!`:
+///
+/// ```
+/// # use clap::FromArgMatches;
+/// macro_rules! applet {
+///     ($item:item) => {
+///         #[clowncopterize::clowncopterize(unclowncopterizer)]
+///         $item
+///     };
+/// }
+///
+/// applet! {
+///     #[derive(clap::Args, Debug)]
+///     struct Ls {
+///         #[arg(long)]
+///         clowntown_hidden: bool,
+///     }
+/// }
+///
+/// #[derive(clap::Parser, Debug)]
+/// #[command(multicall = true)]
+/// enum Applet {
+///     Ls(Ls),
+/// }
+///
+/// let matches = <Applet as clap::CommandFactory>::command()
+///     .get_matches_from(["ls", "--clowntown-hidden", "--un-clowncopterize"]);
+/// let Applet::Ls(mut ls) = Applet::from_arg_matches(&matches).unwrap();
+/// let (_, applet_matches) = matches.subcommand().unwrap();
+/// ls.clowntown_post_parse(applet_matches).unwrap();
+/// assert!(!ls.clowntown_hidden);
+/// ```
+///
+/// # Arg groups
+///
+/// When a clowntown flag belongs to an arg group, declared with `#[arg(group = "...")]` or with
+/// an `ArgGroup` in `#[command(group(...))]`, the master flag wiring it joins that group as well.
+/// `--clowncopterize` alone then satisfies a required group, and conflicts with the other
+/// members of an exclusive one.
+///
+/// # Field options
+///
+/// Clowntown flags accept a `#[clowntown(...)]` attribute:
+///
+/// * `master = "name"`: wire the flag to another master flag than the `clowncopterizer` one.
+///   That flag is injected too, unless the struct declares it.
+/// * `invert`: the master sets the flag to false instead of true, for safety switches that
+///   default to true (e.g. declared with `action = ArgAction::SetFalse`). In `toggle` mode, the
+///   master leaves the flag at its default instead of flipping it.
+/// * `never_mass_enable`: no master turns the flag on, and every master conflicts with it, so
+///   the flag always takes explicit, solitary intent. It satisfies a `policy` listing the flag,
+///   and can't be combined with `master` or `group`.
+/// * `group = "name"`: also wire the flag to a `--clowncopterize-name` flag (named after the
+///   `clowncopterizer`), injected along with the main one. Repeat it to join several groups.
+/// * `deprecated = "note"`: hide the flag from `-h`, and append the note to its `--help`. The
+///   `clowntown_parse` constructors print the note to stderr when the flag is passed explicitly.
+/// * `owner = "team"`, `expires = "2025-06-30"`: who owns the flag and when it should go away,
+///   listed in the generated `CLOWNTOWN_DOCS_MD` table.
+/// * `since = "2.3.0"`: the version that introduced the flag, appended to its `--help` and
+///   listed in the generated docs, so lingering flags stand out.
+/// * `link = "https://runbook/flags/this"`: the flag's runbook, appended to its `--help` and
+///   recorded in `clowntown_manifest()`, so operators can jump straight to it.
+/// * `severity = "low"` or `"high"`: how much damage the flag can do, recorded in
+///   `clowntown_manifest()`. `--help` lists the flag under a "Risky (low)" or "Risky (HIGH)"
+///   heading, unless it sets its own `help_heading`.
+/// * `requires = "arg"`, `conflicts_with = "arg"`: shorthands for the clap attributes of the same
+///   name, keeping the constraints of a risky flag next to its other clowntown options. Both can
+///   be repeated.
+///
+/// The macro consumes these attributes, so clap never sees them and there is nothing to import.
+/// Any other key is a compile error listing the valid ones, so a typo doesn't silently do
+/// nothing:
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     #[clowntown(nver_mass_enable)]
+///     clowntown_drop: bool,
+/// }
+/// ```
+///
+/// They only go on named fields, not on the struct itself:
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// #[clowntown(owner = "storage")]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_drop: bool,
+/// }
+/// ```
+///
+/// Flag names are normalized from kebab-case, snake_case or camelCase, and must make a field name:
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize(clowncopterizer = "live in clowntown")]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_drop: bool,
+/// }
+/// ```
+///
+/// The injected flags can't take the names of those clap generates:
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize(clowncopterizer = "help")]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_drop: bool,
+/// }
+/// ```
+///
+/// Two fields can't share a long flag, the injected ones included:
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long = "clowncopterize")]
+///     yolo: bool,
+///     #[arg(long)]
+///     clowntown_drop: bool,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn clowncopterize(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let clowncopterizer = syn::parse_macro_input!(attr as Clowncopterize);
+
+    let item_struct: syn::ItemStruct = syn::parse_macro_input!(item);
+    let out = clowncopterizer.expand(item_struct);
+    proc_macro::TokenStream::from(out)
+}
+
+/// Generate the clowntown metadata of a struct, leaving its fields and attributes alone
+///
+/// For structs whose master flag is provided by something else: the fields named like
+/// clowntown flags, and `ClownFlag` ones, get the same `CLOWNTOWN_FLAGS`, `CLOWNTOWN_DOCS_MD`,
+/// `clowntown_manifest`, `clowntown_diff` and `apply_clowntown_to` items, and the same
+/// `HasClowntownFlags`, `Clowncopterized` and `From<&Cli> for ClowntownReport` impls, as under
+/// `#[clowncopterize]`, but no flag is injected. Every change the diff reports is explicit, the
+/// masters being unknown. `#[clowntown(...)]` field attributes are read as usual.
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+/// use clowncopterize::{ClowntownIntrospect, HasClowntownFlags};
+///
+/// #[derive(Parser, Debug, ClowntownIntrospect)]
+/// struct Cli {
+///     #[arg(long)]
+///     yolo: bool,
+///     #[arg(long, default_value_if("yolo", "true", "true"))]
+///     #[clowntown(owner = "storage")]
+///     clowntown_drop: bool,
+/// }
+///
+/// let cli = Cli::parse_from(["test", "--yolo"]);
+/// assert_eq!(Cli::CLOWNTOWN_FLAGS, &["clowntown_drop"]);
+/// assert_eq!(cli.enabled_clowntown_flags(), vec!["clowntown_drop"]);
+/// ```
+#[proc_macro_derive(ClowntownIntrospect, attributes(clowntown))]
+pub fn clowntown_introspect(item: TokenStream) -> TokenStream {
+    let item_struct: syn::ItemStruct = syn::parse_macro_input!(item);
+    let out = Clowncopterize::default().introspect(item_struct);
+    proc_macro::TokenStream::from(out)
+}
+
+/// Generate one test per clowntown flag of a clowncopterized struct
+///
+/// The attribute takes the struct type and goes on a function receiving a `&Struct`. For every
+/// matched flag, a `#[test]` named after the flag parses the command line with just that flag
+/// (only it must be set) and then with the injected flag (it must be set too), calling the
+/// function on each result for any extra assertions. The tests live in a module named after the
+/// function, so the matrix follows the struct as flags are added or removed.
+///
+/// The attribute must appear after the struct, in the same module or a child module of it.
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+///
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     /// Turn debugging information on
+///     #[arg(long)]
+///     clowntown_this: bool,
+/// }
+///
+/// #[cfg(test)]
+/// mod tests {
+///     use super::*;
+///
+///     #[clowncopterize::clowncopterize_test(Cli)]
+///     fn clown_flags(cli: &Cli) {
+///         assert!(cli.clowntown_this);
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn clowncopterize_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let ty = syn::parse_macro_input!(attr as syn::TypePath);
+    let test_fn = syn::parse_macro_input!(item as syn::ItemFn);
+
+    let Some(segment) = ty.path.segments.last() else {
+        return syn::Error::new_spanned(&ty, "expected the clowncopterized struct type")
+            .to_compile_error()
+            .into();
+    };
+    let macro_name = test_macro_ident(&segment.ident);
+    let test_name = &test_fn.sig.ident;
+    proc_macro::TokenStream::from(quote::quote! {
+        #test_fn
+        #macro_name!(#test_name, #ty);
+    })
+}
+
+/// Assert at compile time which fields a clowncopterized struct wires to its master flag
+///
+/// Compilation fails unless the listed field names are exactly the flags matched by
+/// `#[clowncopterize]`, in any order. Pinning the list makes every change to the risky surface
+/// of a command line show up in code review.
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+///
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     /// Turn debugging information on
+///     #[arg(long)]
+///     clowntown_this: bool,
+///
+///     /// lists test values
+///     #[arg(long)]
+///     clowntown_that: bool,
+/// }
+///
+/// clowncopterize::assert_covers!(Cli, ["clowntown_this", "clowntown_that"]);
+/// ```
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_this: bool,
+///
+///     #[arg(long)]
+///     clowntown_that: bool,
+/// }
+///
+/// clowncopterize::assert_covers!(Cli, ["clowntown_this"]);
+/// ```
+#[proc_macro]
+pub fn assert_covers(input: TokenStream) -> TokenStream {
+    let AssertCovers { ty, flags } = syn::parse_macro_input!(input as AssertCovers);
+    let message = format!(
+        "the clowntown flags of `{}` differ from the asserted list",
+        ty.to_token_stream()
+    );
+    let helpers = const_str_helpers();
+    proc_macro::TokenStream::from(quote::quote! {
+        const _: () = {
+            #helpers
+            const ASSERTED: &[&str] = &[#(#flags),*];
+            const MATCHED: &[&str] = <#ty>::CLOWNTOWN_FLAGS;
+            assert!(
+                contains_all(MATCHED, ASSERTED) && contains_all(ASSERTED, MATCHED),
+                #message
+            );
+        };
+    })
+}
+
+/// Assert at compile time that a clowncopterized struct has at most so many clowntown flags
+///
+/// Lets a team cap the risky surface of a command line, so adding one more flag past the limit
+/// is a deliberate change to the assertion.
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+///
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_this: bool,
+///
+///     #[arg(long)]
+///     clowntown_that: bool,
+/// }
+///
+/// clowncopterize::assert_max_flags!(Cli, 2);
+/// ```
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_this: bool,
+///
+///     #[arg(long)]
+///     clowntown_that: bool,
+/// }
+///
+/// clowncopterize::assert_max_flags!(Cli, 1);
+/// ```
+#[proc_macro]
+pub fn assert_max_flags(input: TokenStream) -> TokenStream {
+    let AssertMaxFlags { ty, max } = syn::parse_macro_input!(input as AssertMaxFlags);
+    let message = format!(
+        "`{}` has more than {} clowntown flag{}",
+        ty.to_token_stream(),
+        max,
+        if max.base10_digits() == "1" { "" } else { "s" }
+    );
+    proc_macro::TokenStream::from(quote::quote! {
+        const _: () = assert!(<#ty>::CLOWNTOWN_FLAGS.len() <= #max, #message);
+    })
+}
+
+/// Assert at compile time that a clowncopterized struct has a clowntown flag
+///
+/// The flag is given by field name or long flag. Compilation fails if it is renamed, removed, or
+/// no longer matched by `#[clowncopterize]`.
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+///
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_this: bool,
+/// }
+///
+/// clowncopterize::assert_has_flag!(Cli, "clowntown_this");
+/// clowncopterize::assert_has_flag!(Cli, "--clowntown-this");
+/// ```
+///
+/// ```compile_fail
+/// # use clap::Parser;
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     clowntown_this: bool,
+/// }
+///
+/// clowncopterize::assert_has_flag!(Cli, "clowntown_that");
+/// ```
+#[proc_macro]
+pub fn assert_has_flag(input: TokenStream) -> TokenStream {
+    let AssertHasFlag { ty, flag } = syn::parse_macro_input!(input as AssertHasFlag);
+    let name = match normalize_name(&flag.value(), flag.span()) {
+        Ok(name) => name,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let message = format!(
+        "`{}` has no clowntown flag `{}`",
+        ty.to_token_stream(),
+        flag.value()
+    );
+    let helpers = const_str_helpers();
+    proc_macro::TokenStream::from(quote::quote! {
+        const _: () = {
+            #helpers
+            assert!(contains_all(<#ty>::CLOWNTOWN_FLAGS, &[#name]), #message);
+        };
+    })
+}
+
+/// The `const fn`s comparing flag names in the compile-time assertions.
+fn const_str_helpers() -> proc_macro2::TokenStream {
+    quote::quote! {
+        const fn same(a: &str, b: &str) -> bool {
+            let (a, b) = (a.as_bytes(), b.as_bytes());
+            if a.len() != b.len() {
+                return false;
+            }
+            let mut i = 0;
+            while i < a.len() {
+                if a[i] != b[i] {
+                    return false;
+                }
+                i += 1;
+            }
+            true
+        }
+        const fn contains_all(haystack: &[&str], needles: &[&str]) -> bool {
+            let mut i = 0;
+            while i < needles.len() {
+                let mut j = 0;
+                while j < haystack.len() && !same(haystack[j], needles[i]) {
+                    j += 1;
+                }
+                if j == haystack.len() {
+                    return false;
+                }
+                i += 1;
+            }
+            true
+        }
+    }
+}
+
+/// Input of `assert_max_flags!`: `Type, N`.
+struct AssertMaxFlags {
+    ty: syn::Type,
+    max: syn::LitInt,
+}
+
+impl syn::parse::Parse for AssertMaxFlags {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let ty = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let max: syn::LitInt = input.parse()?;
+        max.base10_parse::<usize>()?;
+        if !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(AssertMaxFlags { ty, max })
+    }
+}
+
+/// Input of `assert_has_flag!`: `Type, "flag"`.
+struct AssertHasFlag {
+    ty: syn::Type,
+    flag: syn::LitStr,
+}
+
+impl syn::parse::Parse for AssertHasFlag {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let ty = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let flag = input.parse()?;
+        if !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(AssertHasFlag { ty, flag })
+    }
+}
+
+/// Input of `assert_covers!`: `Type, ["flag", ...]`.
+struct AssertCovers {
+    ty: syn::Type,
+    flags: Vec<syn::LitStr>,
+}
+
+impl syn::parse::Parse for AssertCovers {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let ty = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let content;
+        syn::bracketed!(content in input);
+        let flags =
+            content.parse_terminated(|input| input.parse::<syn::LitStr>(), syn::Token![,])?;
+        // allow a trailing comma after the list
+        if !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(AssertCovers {
+            ty,
+            flags: flags.into_iter().collect(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Clowncopterize {
+    clowncopterizer: String,
+    /// The long flag of the `clowncopterizer`, when it differs from the field name.
+    long: Option<String>,
+    /// The visibility of the injected fields, private unless set.
+    vis: Option<Vis>,
+    /// Attributes put on the injected fields, for the struct's other derives.
+    extra_attrs: Vec<syn::Meta>,
+    /// The `clowncopterizer` field as given by the user, added as is.
+    inject: Option<syn::Field>,
+    /// The `--help` of the `clowncopterizer`, kept verbatim, e.g. an `include_str!`.
+    long_help: Option<syn::Expr>,
+    /// Name of the flag forcing every clowntown flag off, if any.
+    unclowncopterizer: Option<String>,
+    /// Name of the flag printing shell completions, if any.
+    completions: Option<String>,
+    /// Name of the flag printing the provenance of the clowntown flags, if any.
+    provenance: Option<String>,
+    /// Name of the flag the masters require, as a second deliberate action, if any.
+    ack_flag: Option<String>,
+    /// Name of the flag recording the intent to use the `clowncopterizer` without enabling
+    /// anything, if any.
+    shadow: Option<String>,
+    /// Name of the flag taking a deadline after which the command refuses to run, if any.
+    until: Option<String>,
+    /// Name of the flag taking why the masters are used, which they require, if any.
+    reason: Option<String>,
+    /// Name of the flag printing the help of the clowntown flags only, if any.
+    clowntown_help: Option<String>,
+    /// Name of the subcommand activating the `clowncopterizer`, if any.
+    subcommand: Option<String>,
+    /// `Args` structs from other crates whose clowntown flags the masters wire too, with the
+    /// prefix of their flags if not the first of `prefixes`.
+    externals: Vec<(Option<String>, syn::Path)>,
+    /// `fn(&ClowntownGuardContext) -> Result<(), String>` deciding whether to honor a master.
+    guard: Option<syn::Path>,
+    /// The environment variable whose value the guard gets, if any.
+    guard_env: Option<String>,
+    /// `fn() -> Option<Vec<u8>>` returning the key the tokens given to the masters are signed
+    /// with, if the masters take tokens.
+    token_key: Option<syn::Path>,
+    /// What the master flags take as a value, checked by clap, if anything.
+    value_parser: Option<syn::Expr>,
+    /// The clap action of the injected masters.
+    action: MasterAction,
+    /// The `clowncopterizer` takes group names, and can be repeated.
+    repeatable: bool,
+    /// The `clowncopterizer` takes `all`, `none` or a list of flag names.
+    select: bool,
+    /// The `ValueEnum` the `clowncopterizer` takes, if any.
+    mode: Option<Ident>,
+    /// File that must exist for the masters to be honored, if any.
+    sentinel: Option<String>,
+    /// Print the flags forced on by the masters on stderr.
+    report: bool,
+    /// Let `CLOWNTOWN_ALLOW` and `CLOWNTOWN_BLOCK` filter the flags the masters turn on.
+    env_filter: bool,
+    /// The `ClowntownSink` told about the flags in use after parsing, if any.
+    sink: Option<syn::Path>,
+    /// Environment variables, as `NAME` or `NAME=value`, refusing the masters when they match.
+    deny_env: Vec<String>,
+    /// Fail release builds, unless `CLOWNTOWN_ALLOW_RELEASE` is set when building.
+    deny_release: bool,
+    /// TOML file, relative to the crate root, listing the flags no master may turn on.
+    policy: Option<syn::LitStr>,
+    /// Function translating the help we generate, as `fn(key: &str, default: &str) -> String`.
+    localize: Option<syn::Path>,
+    /// Render the clowntown section of the help in a warning color.
+    warning_style: bool,
+    /// List the flags of each master in the command's `after_help`.
+    after_help: bool,
+    /// Append the number of clowntown flags and the masters to the command's `version`.
+    version_audit: bool,
+    /// Maximum number of clowntown flags enabled at once, unless through a master flag.
+    runtime_max: Option<usize>,
+    /// Warn at compile time above this many clowntown flags.
+    warn_above: Option<usize>,
+    /// What replaces the `clowntown_` prefix in the long flags, if anything does.
+    strip_prefix: Option<String>,
+    /// Hide the clowntown flags from the help, leaving only the masters.
+    hide_flags: bool,
+    /// What the names of the fields to wire start with, any of them.
+    prefixes: Vec<String>,
+    /// The groups the fields can join with `#[clowntown(group = "...")]`, when declared.
+    groups: Option<Vec<String>>,
+    /// Hide the injected masters from the help.
+    hidden: bool,
+    /// The injected masters can't be combined with any other argument.
+    exclusive: bool,
+    /// Make the injected flags `global`, even without a `#[command(subcommand)]` field.
+    global: bool,
+    /// The clowntown flags can only be given along their master.
+    locked: bool,
+    /// Export a machine-readable manifest of the clowntown flags.
+    manifest: bool,
+    /// Boolean expressions over the struct's flags that must hold after parsing.
+    invariants: Vec<(syn::LitStr, invariant::Expr)>,
+    /// The master flips flags from their declared default instead of setting them to true.
+    toggle: bool,
+    /// What to do with flags already defaulting to the value their masters set.
+    default_true: DefaultTrue,
+    /// The parse wrapper panics when a master is on under `cfg(test)`.
+    panic_in_tests: bool,
+    verbose: bool,
+    generate_tests: bool,
+}
+
+impl Default for Clowncopterize {
+    fn default() -> Self {
+        Clowncopterize {
+            clowncopterizer: CLOWNCOPTERIZE_FLAG.to_string(),
+            long: None,
+            vis: None,
+            extra_attrs: Vec::new(),
+            inject: None,
+            long_help: None,
+            unclowncopterizer: None,
+            completions: None,
+            provenance: None,
+            ack_flag: None,
+            shadow: None,
+            until: None,
+            reason: None,
+            clowntown_help: None,
+            subcommand: None,
+            externals: Vec::new(),
+            guard: None,
+            guard_env: None,
+            token_key: None,
+            value_parser: None,
+            action: MasterAction::SetTrue,
+            repeatable: false,
+            select: false,
+            mode: None,
+            sentinel: None,
+            report: false,
+            env_filter: false,
+            sink: None,
+            deny_env: Vec::new(),
+            deny_release: false,
+            policy: None,
+            localize: None,
+            warning_style: false,
+            after_help: false,
+            version_audit: false,
+            runtime_max: None,
+            warn_above: None,
+            strip_prefix: None,
+            hide_flags: false,
+            prefixes: vec![CLOWNCOPTERIZE_PREFIX.to_string()],
+            groups: None,
+            hidden: false,
+            exclusive: false,
+            global: false,
+            locked: false,
+            manifest: false,
+            invariants: Vec::new(),
+            toggle: false,
+            default_true: DefaultTrue::Warn,
+            panic_in_tests: false,
+            verbose: false,
+            generate_tests: false,
+        }
+    }
+}
+
+impl syn::parse::Parse for Clowncopterize {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let mut clowncopterize = Clowncopterize::default();
+        let mut keys: Vec<(String, Span)> = Vec::new();
+        let parser = syn::meta::parser(|meta| {
+            let key = meta.path.to_token_stream().to_string();
+            if !Clowncopterize::REPEATABLE.contains(&key.as_str())
+                && keys.iter().any(|(seen, _)| *seen == key)
+            {
+                return Err(meta.error(format!("`{}` is given more than once", key)));
+            }
+            for (a, b) in Clowncopterize::CONFLICTING {
+                let other = if key == *a {
+                    b
+                } else if key == *b {
+                    a
+                } else {
+                    continue;
+                };
+                if keys.iter().any(|(seen, _)| seen == other) {
+                    return Err(meta.error(format!("`{}` and `{}` can't be combined", other, key)));
+                }
+            }
+            keys.push((key, syn::spanned::Spanned::span(&meta.path)));
+            clowncopterize.parse_option(meta)
+        });
+        syn::parse::Parser::parse2(parser, input.parse()?)?;
+        clowncopterize.check_names(&keys)?;
+        clowncopterize.check_action(&keys)?;
+        clowncopterize.check_subcommand(&keys)?;
+        Ok(clowncopterize)
+    }
+}
+
+/// The clap action of the injected masters, from the `action` option.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MasterAction {
+    SetTrue,
+    /// A `u8` field counting the uses of the master.
+    Count,
+    /// The master takes a value.
+    Set,
+}
+
+/// What to do with flags already defaulting to the value their masters set, from the
+/// `default_true` option.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DefaultTrue {
+    Warn,
+    Skip,
+    /// The masters set the opposite of the default.
+    Invert,
+}
+
+/// The visibility of the injected fields, from the `vis` option.
+#[derive(Debug)]
+enum Vis {
+    /// The struct's own, with `vis = "inherit"`.
+    Inherit,
+    Declared(syn::Visibility),
+}
+
+/// A message about the expansion, only surfaced in `verbose` mode.
+struct Note {
+    span: Span,
+    message: String,
+}
+
+/// The casing clap applies to derive long flags from field names, per `#[command(rename_all)]`.
+#[derive(Clone, Copy, Default)]
+enum Casing {
+    Camel,
+    #[default]
+    Kebab,
+    Pascal,
+    ScreamingSnake,
+    Snake,
+    Lower,
+    Upper,
+    Verbatim,
+}
+
+impl Casing {
+    /// The casing set by the `#[command(rename_all = "...")]` of a container, if any.
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::parse::Result<Self> {
+        let mut casing = Casing::default();
+        for meta in attr_metas(attrs, "command") {
+            let syn::Meta::NameValue(name_value) = &meta else {
+                continue;
+            };
+            if !name_value.path.is_ident("rename_all") {
+                continue;
+            }
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) = &name_value.value
+            else {
+                continue;
+            };
+            // clap is lenient on the spelling, e.g. `kebab-case`, `kebab` or `KebabCase`.
+            casing = match lit
+                .value()
+                .to_ascii_uppercase()
+                .replace(['_', '-'], "")
+                .as_str()
+            {
+                "CAMELCASE" | "CAMEL" => Casing::Camel,
+                "KEBABCASE" | "KEBAB" => Casing::Kebab,
+                "PASCALCASE" | "PASCAL" => Casing::Pascal,
+                "SCREAMINGSNAKECASE" | "SCREAMINGSNAKE" => Casing::ScreamingSnake,
+                "SNAKECASE" | "SNAKE" => Casing::Snake,
+                "LOWERCASE" | "LOWER" => Casing::Lower,
+                "UPPERCASE" | "UPPER" => Casing::Upper,
+                "VERBATIMCASE" | "VERBATIM" => Casing::Verbatim,
+                _ => {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!("unsupported `rename_all` casing `{}`", lit.value()),
+                    ));
+                }
+            };
+        }
+        Ok(casing)
+    }
+
+    /// Renames the snake case field `name`.
+    fn rename(self, name: &str) -> String {
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        };
+        let words = name.split('_').filter(|word| !word.is_empty());
+        match self {
+            Casing::Camel => words
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.to_string()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+            Casing::Kebab => words.collect::<Vec<_>>().join("-"),
+            Casing::Pascal => words.map(capitalize).collect(),
+            Casing::ScreamingSnake => name.to_uppercase(),
+            Casing::Snake | Casing::Verbatim => name.to_string(),
+            // like clap_derive, which drops the separators.
+            Casing::Lower => name.to_lowercase().replace('_', ""),
+            Casing::Upper => name.to_uppercase().replace('_', ""),
+        }
+    }
+}
+
+/// A `clap::Error` of kind `DisplayHelp` carrying `text` (a `clap::builder::StyledStr` or
+/// anything converting into one) verbatim, as an expression: `exit()` prints it to stdout and
+/// exits with 0, like clap does for `--help`. Clap has no public constructor for these, so let
+/// a throwaway command render it as its whole help.
+fn display_error(text: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote::quote! {
+        ::clap::Command::new("clowntown")
+            .disable_version_flag(true)
+            .override_usage(#text)
+            .help_template("{usage}")
+            .try_get_matches_from(["clowntown", "--help"])
+            .expect_err("`--help` always displays the help")
+    }
+}
+
+/// The `--long` flag clap derives for a field named `name`.
+fn long_flag(name: &str, casing: Casing) -> String {
+    format!("--{}", casing.rename(name))
+}
+
+/// The items of every `#[name(...)]` attribute in `attrs`, e.g. `long` or `group = "mode"` for
+/// `#[arg(long, group = "mode")]`.
+fn attr_metas(attrs: &[syn::Attribute], name: &str) -> Vec<syn::Meta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident(name))
+        .filter_map(|attr| {
+            attr.parse_args_with(
+                syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+            )
+            .ok()
+        })
+        .flatten()
+        .collect()
+}
+
+/// Appends `items` to the first `#[arg(...)]` attribute of `field`.
+fn extend_arg(field: &mut syn::Field, items: proc_macro2::TokenStream) {
+    if let Some(attr) = field
+        .attrs
+        .iter_mut()
+        .find(|attr| attr.path().is_ident("arg"))
+    {
+        let meta = attr.meta.require_list().unwrap();
+        let mut tokens = meta.tokens.clone();
+        if !tokens.is_empty() {
+            tokens.extend(quote::quote!(,));
+        }
+        tokens.extend(items);
+        attr.meta = syn::Meta::List(syn::MetaList {
+            path: meta.path.clone(),
+            delimiter: meta.delimiter.clone(),
+            tokens,
+        });
+    }
+}
+
+/// Whether `ty` is `ClownFlag`, however it is imported.
+fn is_clown_flag(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(ty) if ty.qself.is_none()
+        && ty.path.segments.last().is_some_and(|segment| segment.ident == "ClownFlag"))
+}
+
+/// Reports the fields sharing a long flag, e.g. an injected master named like a declared flag,
+/// which clap only catches with a debug assertion when building the command.
+fn check_duplicate_longs(ast: &syn::ItemStruct, expansion: &mut Expansion) {
+    let syn::Fields::Named(fields) = &ast.fields else {
+        return;
+    };
+    let mut seen: Vec<(String, &Ident, bool)> = Vec::new();
+    for (index, field) in fields.named.iter().enumerate() {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let long = attr_metas(&field.attrs, "arg")
+            .iter()
+            .chain(&attr_metas(&field.attrs, "clap"))
+            .find_map(|meta| match meta {
+                syn::Meta::Path(path) if path.is_ident("long") => {
+                    Some(expansion.casing.rename(&ident.to_string()))
+                }
+                meta => meta_str(meta, "long"),
+            });
+        let Some(long) = long else {
+            continue;
+        };
+        let injected = index >= expansion.first_injected;
+        let Some((_, other, other_injected)) = seen.iter().find(|(other, ..)| *other == long)
+        else {
+            seen.push((long, ident, injected));
+            continue;
+        };
+        // the injected field has no span of its own.
+        let span = if injected { other.span() } else { ident.span() };
+        let origin = match (*other_injected, injected) {
+            (false, false) => "",
+            (true, true) => ", both injected by `#[clowncopterize]`; rename one with its option",
+            _ => ", one injected by `#[clowncopterize]`; rename one of them",
+        };
+        expansion.errors.push(syn::Error::new(
+            span,
+            format!(
+                "`{}` and `{}` both have the long flag `--{}`{}",
+                other, ident, long, origin
+            ),
+        ));
+    }
+}
+
+/// Turns a bare `long` in the `#[arg(...)]` attributes of `field` into `long = "name"`.
+fn set_long(field: &mut syn::Field, name: &str) {
+    for attr in field
+        .attrs
+        .iter_mut()
+        .filter(|attr| attr.path().is_ident("arg"))
+    {
+        let Ok(metas) = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        ) else {
+            continue;
+        };
+        let metas = metas.into_iter().map(|meta| match meta {
+            syn::Meta::Path(path) if path.is_ident("long") => syn::parse_quote!(long = #name),
+            meta => meta,
+        });
+        let metas: Vec<syn::Meta> = metas.collect();
+        *attr = syn::parse_quote!(#[arg(#(#metas),*)]);
+    }
+}
+
+/// Adds serde defaults to the clowntown flags and the injected fields of a `Deserialize` struct,
+/// so they can be missing from the serialized data.
+fn serde_defaults(ast: &mut syn::ItemStruct, expansion: &Expansion) {
+    let syn::Fields::Named(fields) = &mut ast.fields else {
+        return;
+    };
+    for field in fields.named.iter_mut() {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let default =
+            if let Some(clown) = expansion.clowns.iter().find(|clown| clown.ident == *ident) {
+                clown.default
+            } else if expansion
+                .masters
+                .iter()
+                .any(|master| master.injected && master.ident == *ident)
+                || expansion.unclowncopterizer.as_ref() == Some(ident)
+            {
+                false
+            } else {
+                continue;
+            };
+        let has_default = attr_metas(&field.attrs, "serde")
+            .iter()
+            .any(|meta| meta.path().is_ident("default"));
+        if has_default {
+            continue;
+        }
+        let attr: syn::Attribute = if default {
+            syn::parse_quote!(#[serde(default = "::clowncopterize::__private::default_true")])
+        } else {
+            syn::parse_quote!(#[serde(default)])
+        };
+        field.attrs.push(attr);
+    }
+}
+
+/// Adds rustdoc search aliases, so searching for "clowntown" or "dangerous" finds the struct and
+/// its risky fields, and searching for a flag as typed on the command line finds its field.
+fn doc_aliases(ast: &mut syn::ItemStruct, expansion: &Expansion) {
+    // rustdoc refuses an alias equal to the item's name.
+    let aliases: Vec<_> = ["clowntown", "dangerous"]
+        .into_iter()
+        .filter(|alias| !ast.ident.to_string().eq_ignore_ascii_case(alias))
+        .collect();
+    ast.attrs
+        .push(syn::parse_quote!(#[doc(alias(#(#aliases),*))]));
+    let syn::Fields::Named(fields) = &mut ast.fields else {
+        return;
+    };
+    for field in fields.named.iter_mut() {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let Some(clown) = expansion.clowns.iter().find(|clown| clown.ident == *ident) else {
+            continue;
+        };
+        let long = clown.long();
+        let aliases: Vec<_> = ["dangerous", long.trim_start_matches("--")]
+            .into_iter()
+            .filter(|alias| *ident != alias)
+            .collect();
+        if !aliases.is_empty() {
+            field
+                .attrs
+                .push(syn::parse_quote!(#[doc(alias(#(#aliases),*))]));
+        }
+    }
+}
+
+/// The doc comment in `attrs`, as clap would use it for the long help.
+fn doc_string(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(name_value) if name_value.path.is_ident("doc") => {
+                lit_strs(&name_value.value).into_iter().next()
+            }
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// The string literal value of `meta` if it is `name = "value"`.
+fn meta_str(meta: &syn::Meta, name: &str) -> Option<String> {
+    match meta {
+        syn::Meta::NameValue(name_value) if name_value.path.is_ident(name) => {
+            lit_strs(&name_value.value).into_iter().next()
+        }
+        _ => None,
+    }
+}
+
+/// The default value of a boolean flag, as declared in its `#[arg(...)]` items.
+fn declared_default(arg_metas: &[syn::Meta]) -> bool {
+    for meta in arg_metas {
+        match meta {
+            syn::Meta::NameValue(name_value) if name_value.path.is_ident("default_value_t") => {
+                if let syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Bool(lit),
+                    ..
+                }) = &name_value.value
+                {
+                    return lit.value;
+                }
+            }
+            syn::Meta::NameValue(name_value) if name_value.path.is_ident("action") => {
+                if let syn::Expr::Path(path) = &name_value.value
+                    && path
+                        .path
+                        .segments
+                        .last()
+                        .is_some_and(|segment| segment.ident == "SetFalse")
+                {
+                    return true;
+                }
+            }
+            meta => {
+                if let Some(value) = meta_str(meta, "default_value") {
+                    return value == "true";
+                }
+            }
+        }
+    }
+    false
+}
+
+/// The string literals in `expr`, looking through arrays and references.
+fn lit_strs(expr: &syn::Expr) -> Vec<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => vec![lit.value()],
+        syn::Expr::Array(array) => array.elems.iter().flat_map(lit_strs).collect(),
+        syn::Expr::Reference(reference) => lit_strs(&reference.expr),
+        _ => Vec::new(),
+    }
+}
+
+/// An `ArgGroup` declared on the command, e.g. with
+/// `#[command(group(ArgGroup::new("mode").args(["a", "b"])))]`.
+struct CommandGroup {
+    id: String,
+    args: Vec<String>,
+}
+
+impl CommandGroup {
+    /// Reads the groups declared with `group(...)` or `groups([...])` in `#[command(...)]`.
+    fn from_attrs(attrs: &[syn::Attribute]) -> Vec<Self> {
+        let mut groups = Vec::new();
+        for meta in attr_metas(attrs, "command") {
+            let syn::Meta::List(list) = meta else {
+                continue;
+            };
+            let Ok(expr) = syn::parse2::<syn::Expr>(list.tokens.clone()) else {
+                continue;
+            };
+            if list.path.is_ident("group") {
+                groups.extend(Self::from_expr(&expr));
+            } else if let syn::Expr::Array(array) = &expr
+                && list.path.is_ident("groups")
+            {
+                groups.extend(array.elems.iter().filter_map(Self::from_expr));
+            }
+        }
+        groups
+    }
+
+    /// Follows an `ArgGroup::new("id").arg("a").args(["b", "c"])` builder chain.
+    fn from_expr(expr: &syn::Expr) -> Option<Self> {
+        match expr {
+            syn::Expr::Call(call) => {
+                let syn::Expr::Path(path) = &*call.func else {
+                    return None;
+                };
+                if path.path.segments.last()?.ident != "new" {
+                    return None;
+                }
+                let id = lit_strs(call.args.first()?).into_iter().next()?;
+                Some(CommandGroup {
+                    id,
+                    args: Vec::new(),
+                })
+            }
+            syn::Expr::MethodCall(call) => {
+                let mut group = Self::from_expr(&call.receiver)?;
+                if call.method == "arg" || call.method == "args" {
+                    group.args.extend(call.args.iter().flat_map(lit_strs));
+                }
+                Some(group)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Options set on a clowntown flag with `#[clowntown(...)]`.
+#[derive(Debug, Default)]
+struct FieldOptions {
+    /// Master flag wiring this field, instead of the `clowncopterizer` one.
+    master: Option<String>,
+    /// The master sets this flag to false rather than true.
+    invert: bool,
+    /// Named groups of flags getting their own master flag.
+    groups: Vec<String>,
+    /// Arg ids this flag requires, as with clap's `requires`.
+    requires: Vec<syn::LitStr>,
+    /// Arg ids this flag conflicts with, as with clap's `conflicts_with`.
+    conflicts_with: Vec<syn::LitStr>,
+    /// Why the flag is deprecated, and what to use instead.
+    deprecated: Option<String>,
+    /// Who to ask about the flag, for the generated docs.
+    owner: Option<String>,
+    /// When the flag is expected to go away, for the generated docs.
+    expires: Option<String>,
+    /// The version that introduced the flag.
+    since: Option<String>,
+    /// The flag's runbook.
+    link: Option<String>,
+    /// `low` or `high`.
+    severity: Option<String>,
+    /// No master turns the flag on, and the masters conflict with it.
+    never_mass_enable: bool,
+}
+
+impl FieldOptions {
+    /// The keys `#[clowntown(...)]` takes on a clowntown flag.
+    const KEYS: &'static [&'static str] = &[
+        "master",
+        "invert",
+        "never_mass_enable",
+        "group",
+        "requires",
+        "conflicts_with",
+        "deprecated",
+        "owner",
+        "expires",
+        "since",
+        "link",
+        "severity",
+    ];
+
+    /// Parses the `#[clowntown(...)]` attributes of `field`, removing them so clap never sees them.
+    fn take(field: &mut syn::Field, expansion: &mut Expansion) -> Self {
+        let mut options = FieldOptions::default();
+        field.attrs.retain(|attr| {
+            if !attr.path().is_ident("clowntown") {
+                return true;
+            }
+            if let Err(err) = attr.parse_nested_meta(|meta| options.parse_option(meta)) {
+                expansion.errors.push(err);
+            }
+            false
+        });
+        options
+    }
+
+    fn parse_option(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::parse::Result<()> {
+        if meta.path.is_ident("master") {
+            let attr_value = meta.value()?.parse::<syn::LitStr>()?;
+            self.master = Some(checked_flag_name(&attr_value.value(), attr_value.span())?);
+        } else if meta.path.is_ident("invert") {
+            self.invert = true;
+        } else if meta.path.is_ident("never_mass_enable") {
+            self.never_mass_enable = true;
+        } else if meta.path.is_ident("group") {
+            let attr_value = meta.value()?.parse::<syn::LitStr>()?;
+            self.groups
+                .push(normalize_name(&attr_value.value(), attr_value.span())?);
+        } else if meta.path.is_ident("requires") {
+            self.requires.push(meta.value()?.parse()?);
+        } else if meta.path.is_ident("conflicts_with") {
+            self.conflicts_with.push(meta.value()?.parse()?);
+        } else if meta.path.is_ident("deprecated") {
+            self.deprecated = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("owner") {
+            self.owner = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("expires") {
+            self.expires = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("since") {
+            self.since = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("link") {
+            self.link = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("severity") {
+            let attr_value = meta.value()?.parse::<syn::LitStr>()?;
+            let severity = attr_value.value().to_lowercase();
+            if severity != "low" && severity != "high" {
+                return Err(syn::Error::new(
+                    attr_value.span(),
+                    "expected severity \"low\" or \"high\"",
+                ));
+            }
+            self.severity = Some(severity);
+        } else {
+            return Err(unknown_key(&meta, Self::KEYS));
+        }
+        Ok(())
+    }
+}
+
+/// The error for an unknown `#[clowntown(...)]` key, listing the `valid` ones and suggesting the
+/// closest.
+fn unknown_key(meta: &syn::meta::ParseNestedMeta, valid: &[&str]) -> syn::Error {
+    let key = meta.path.to_token_stream().to_string().replace(' ', "");
+    let closest = valid
+        .iter()
+        .map(|candidate| (edit_distance(&key, candidate), candidate))
+        .min()
+        .filter(|(distance, candidate)| *distance <= candidate.len().max(3) / 3);
+    let hint = match closest {
+        Some((_, candidate)) => format!("; did you mean `{}`?", candidate),
+        None => String::new(),
+    };
+    meta.error(format!(
+        "unknown clowntown option `{}`, expected one of: {}{}",
+        key,
+        valid.join(", "),
+        hint
+    ))
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a == *b {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Takes the `#[clowntown(nested)]` attribute of a field that isn't a clowntown flag, returning
+/// whether it was there.
+fn take_nested(field: &mut syn::Field, expansion: &mut Expansion) -> bool {
+    let mut nested = false;
+    field.attrs.retain(|attr| {
+        if !attr.path().is_ident("clowntown") {
+            return true;
+        }
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("nested") {
+                nested = true;
+                Ok(())
+            } else if meta
+                .path
+                .get_ident()
+                .is_some_and(|ident| !FieldOptions::KEYS.contains(&ident.to_string().as_str()))
+            {
+                Err(unknown_key(&meta, &["nested"]))
+            } else {
+                let attr_name = meta.path.to_token_stream();
+                Err(meta.error(format!(
+                    "`{}` only applies to clowntown flags, fields that aren't can only be `nested`",
+                    attr_name
+                )))
+            }
+        });
+        if let Err(err) = parsed {
+            expansion.errors.push(err);
+        }
+        false
+    });
+    nested
+}
+
+/// Takes the `#[clowntown(...)]` attributes no field handling consumes, on the struct itself or on
+/// the fields of a tuple struct, reporting them as misplaced. Leaving them would have rustc fail
+/// to resolve `clowntown` instead.
+fn take_stray_helpers(ast: &mut syn::ItemStruct, expansion: &mut Expansion) {
+    let mut take = |attrs: &mut Vec<syn::Attribute>, message: &str| {
+        attrs.retain(|attr| {
+            if !attr.path().is_ident("clowntown") {
+                return true;
+            }
+            expansion
+                .errors
+                .push(syn::Error::new_spanned(attr, message));
+            false
+        });
+    };
+    take(
+        &mut ast.attrs,
+        "`#[clowntown(...)]` goes on the fields; options of the whole struct go in \
+         `#[clowncopterize(...)]`",
+    );
+    if !matches!(ast.fields, syn::Fields::Named(_)) {
+        for field in ast.fields.iter_mut() {
+            take(
+                &mut field.attrs,
+                "`#[clowntown(...)]` needs a named field, clowntown flags are matched by name",
+            );
+        }
+    }
+}
+
+/// A field that was matched as a clowntown flag.
+struct ClownField {
+    ident: Ident,
+    /// The `--long` flag, without the dashes.
+    long: String,
+    /// Names of the master flag fields wiring this one, starting with its main master.
+    masters: Vec<String>,
+    /// Ids of the arg groups this field belongs to.
+    arg_groups: Vec<String>,
+    /// The clowntown groups of the flag, from `#[clowntown(group = "...")]`.
+    groups: Vec<String>,
+    /// Whether the field is a `ClownFlag` rather than a `bool`.
+    typed: bool,
+    /// The value the flag has when nothing sets it.
+    default: bool,
+    /// The value the master sets the flag to.
+    risky: bool,
+    /// The deprecation note, for flags on their way out.
+    deprecated: Option<String>,
+    /// The first paragraph of the doc comment.
+    help: String,
+    owner: Option<String>,
+    expires: Option<String>,
+    since: Option<String>,
+    link: Option<String>,
+    /// `low` or `high`.
+    severity: Option<String>,
+    /// The environment variable clap reads the flag from, if any.
+    env: Option<String>,
+}
+
+impl ClownField {
+    /// The master setting this flag along with all the others, by default the clowncopterizer.
+    fn master(&self) -> &str {
+        &self.masters[0]
+    }
+
+    fn long(&self) -> String {
+        format!("--{}", self.long)
+    }
+
+    /// Whether the flag already defaults to the value its masters set.
+    fn is_redundant(&self) -> bool {
+        self.risky == self.default
+    }
+
+    /// Where the flag's `bool` is, from the struct: the field itself, or the value of a
+    /// `ClownFlag` field. It can be both read and assigned.
+    fn place(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        if self.typed {
+            quote::quote!(#ident.0)
+        } else {
+            quote::quote!(#ident)
+        }
+    }
+}
+
+/// A flag turning a set of clowntown flags on.
+struct Master {
+    ident: Ident,
+    /// The `--long` flag, without the dashes.
+    long: String,
+    /// Whether we added the field, as opposed to the user declaring it in the struct.
+    injected: bool,
+    shape: MasterShape,
+}
+
+/// The type of a master field.
+#[derive(Clone, Copy, PartialEq)]
+enum MasterShape {
+    Bool,
+    /// A `u8` counting the uses of the master, with `action = "Count"`.
+    Count,
+    /// A `Vec<String>` of the requested groups, with `repeatable`.
+    Groups,
+    /// A `Vec<String>` of the selected flags, `all` or `none`, with `select`.
+    Select,
+    /// The generated `ValueEnum`, with `mode`.
+    Mode,
+}
+
+impl Master {
+    fn long(&self) -> String {
+        format!("--{}", self.long)
+    }
+
+    /// Whether the master is on, as a `bool` expression on `receiver`.
+    fn on(&self, receiver: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        match self.shape {
+            MasterShape::Bool => quote::quote!(#receiver.#ident),
+            MasterShape::Count => quote::quote!((#receiver.#ident > 0)),
+            MasterShape::Groups => quote::quote!(!#receiver.#ident.is_empty()),
+            MasterShape::Select => {
+                quote::quote!(#receiver.#ident.iter().any(|flag| flag != "none"))
+            }
+            MasterShape::Mode => quote::quote!(#receiver.#ident.is_on()),
+        }
+    }
+}
+
+/// What we learned while rewriting the struct, used to generate the extra items.
+#[derive(Default)]
+struct Expansion {
+    notes: Vec<Note>,
+    errors: Vec<syn::Error>,
+    clowns: Vec<ClownField>,
+    masters: Vec<Master>,
+    /// The injected flag forcing every clowntown flag off.
+    unclowncopterizer: Option<Ident>,
+    /// The injected flag printing shell completions.
+    completions: Option<Ident>,
+    /// The injected flag printing the provenance of the clowntown flags.
+    provenance: Option<Ident>,
+    /// The injected flag printing the help of the clowntown flags.
+    clowntown_help: Option<Ident>,
+    /// The injected flag the masters require.
+    ack_flag: Option<Ident>,
+    /// The injected flag taking why the masters are used, which they require.
+    reason: Option<Ident>,
+    /// The injected flag recording the intent to use the `clowncopterizer`.
+    shadow: Option<Ident>,
+    /// The generated `ValueEnum` the `clowncopterizer` takes, with `mode`.
+    mode: Option<Ident>,
+    /// The clowntown flags marked `never_mass_enable`, which the masters conflict with.
+    never_mass_enable: Vec<Ident>,
+    /// Flattened fields whose clowntown flags we report, with their types.
+    nested: Vec<(Ident, syn::Type)>,
+    /// Index of the first field we injected.
+    first_injected: usize,
+    /// Whether the struct has a `#[command(subcommand)]` field.
+    has_subcommand: bool,
+    /// Whether the struct derives `Parser`, and so gets the parse wrapper.
+    is_parser: bool,
+    /// How clap derives long flags from field names.
+    casing: Casing,
+}
+
+impl Expansion {
+    fn note(&mut self, span: Span, message: String) {
+        self.notes.push(Note { span, message });
+    }
+
+    /// The arguments to pass along a master flag, i.e. the acknowledgement and reason flags if
+    /// any.
+    fn master_companions(&self) -> Vec<String> {
+        self.ack_flag
+            .iter()
+            .map(|ack| long_flag(&ack.to_string(), self.casing))
+            .chain(
+                self.reason
+                    .iter()
+                    .map(|reason| format!("{}=test", long_flag(&reason.to_string(), self.casing))),
+            )
+            .collect()
+    }
+
+    /// What records the intent to use a master without turning anything on, as a `bool`
+    /// expression on `self` and as given on the command line, with that master: the shadow flag,
+    /// or `dry-run` with `mode`.
+    fn shadowed(&self) -> Option<(proc_macro2::TokenStream, String, &Master)> {
+        // both only come along the `clowncopterizer`, the first master.
+        let master = self.masters.first()?;
+        if let Some(shadow) = &self.shadow {
+            let long = long_flag(&shadow.to_string(), self.casing);
+            return Some((quote::quote!(self.#shadow), long, master));
+        }
+        let ident = &master.ident;
+        (master.shape == MasterShape::Mode).then(|| {
+            let long = format!("{}=dry-run", master.long());
+            (quote::quote!(self.#ident.is_dry_run()), long, master)
+        })
+    }
+
+    /// Whether the master named `name` is on, as a `bool` expression on `receiver`.
+    fn master_on(
+        &self,
+        receiver: proc_macro2::TokenStream,
+        name: &str,
+    ) -> proc_macro2::TokenStream {
+        match self.masters.iter().find(|master| master.ident == name) {
+            Some(master) => master.on(receiver),
+            None => {
+                let ident = Ident::new(name, Span::call_site());
+                quote::quote!(#receiver.#ident)
+            }
+        }
+    }
+
+    /// Resolves the `values` of a `select` master against its flags, as a
+    /// `Result<Vec<bool>, String>` expression.
+    fn select_values(
+        &self,
+        master: &Master,
+        values: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let ids = self.clowns_of(master).map(|clown| clown.ident.to_string());
+        let longs = self.clowns_of(master).map(ClownField::long);
+        quote::quote! {
+            ::clowncopterize::__private::select_values(#values, &[#(#ids),*], &[#(#longs),*])
+        }
+    }
+
+    /// The clowntown flags wired to `master`.
+    fn clowns_of<'a>(&'a self, master: &'a Master) -> impl Iterator<Item = &'a ClownField> {
+        self.clowns
+            .iter()
+            .filter(move |clown| clown.masters.iter().any(|name| master.ident == name))
+    }
+}
+
+/// Whether `ast` has `trait_name` in one of its `#[derive(...)]`.
+fn derives(ast: &syn::ItemStruct, trait_name: &str) -> bool {
+    ast.attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(
+                syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+            )
+            .ok()
+        })
+        .flatten()
+        .any(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| segment.ident == trait_name)
+        })
+}
+
+/// Name of the `macro_rules!` generated for `#[clowncopterize_test]` on struct `name`.
+fn test_macro_ident(name: &Ident) -> Ident {
+    Ident::new(
+        &format!("clowncopterize_{}_test", snake_case(&name.to_string())),
+        Span::call_site(),
+    )
+}
+
+/// The `CLOWNTOWN_FLAGS`, `CLOWNTOWN_DOCS_MD` and `clowntown_manifest` items of the struct.
+fn manifest_items(name: &Ident, expansion: &Expansion) -> proc_macro2::TokenStream {
+    let flags = expansion.clowns.iter().map(|clown| clown.ident.to_string());
+    let docs_md = docs_md(expansion);
+    let infos = expansion
+        .clowns
+        .iter()
+        .map(|clown| clown_flag_info(name, clown));
+    quote::quote! {
+        /// Names of the fields wired to the clowncopterize flag.
+        #[doc(alias = "dangerous")]
+        pub const CLOWNTOWN_FLAGS: &'static [&'static str] = &[#(#flags),*];
+
+        /// A markdown table of the clowntown flags, with their help, owner, version and expiry.
+        pub const CLOWNTOWN_DOCS_MD: &'static str = #docs_md;
+
+        /// The metadata of every clowntown flag, in declaration order.
+        #[doc(alias = "dangerous")]
+        pub fn clowntown_manifest() -> &'static [::clowncopterize::ClownFlagInfo] {
+            const MANIFEST: &[::clowncopterize::ClownFlagInfo] = &[#(#infos),*];
+            MANIFEST
+        }
+    }
+}
+
+/// A `clowncopterize::ClownFlagInfo` expression describing `clown`, declared in struct `name`.
+fn clown_flag_info(name: &Ident, clown: &ClownField) -> proc_macro2::TokenStream {
+    let field = clown.ident.to_string();
+    let long = clown.long();
+    let struct_name = name.to_string();
+    let help = &clown.help;
+    let opt = |value: &Option<String>| match value {
+        Some(value) => quote::quote!(::std::option::Option::Some(#value)),
+        None => quote::quote!(::std::option::Option::None),
+    };
+    let owner = opt(&clown.owner);
+    let since = opt(&clown.since);
+    let expires = opt(&clown.expires);
+    let link = opt(&clown.link);
+    let level = match clown.severity.as_deref() {
+        Some("high") => quote::quote!(::std::option::Option::Some(
+            ::clowncopterize::ClowntownSeverity::High
+        )),
+        Some(_) => quote::quote!(::std::option::Option::Some(
+            ::clowncopterize::ClowntownSeverity::Low
+        )),
+        None => quote::quote!(::std::option::Option::None),
+    };
+    quote::quote! {
+        ::clowncopterize::ClownFlagInfo {
+            name: #field,
+            long: #long,
+            struct_name: #struct_name,
+            module_path: ::std::module_path!(),
+            help: #help,
+            owner: #owner,
+            level: #level,
+            since: #since,
+            expires: #expires,
+            link: #link,
+        }
+    }
+}
+
+/// Quotes `text` as a JSON string.
+fn json_str(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The manifest behind `CLOWNTOWN_MANIFEST_JSON`.
+fn manifest_json(name: &str, expansion: &Expansion) -> String {
+    let opt = |value: &Option<String>| value.as_deref().map_or("null".to_string(), json_str);
+    let masters: Vec<_> = expansion
+        .masters
+        .iter()
+        .map(|master| json_str(&master.long()))
+        .collect();
+    let flags: Vec<_> = expansion
+        .clowns
+        .iter()
+        .map(|clown| {
+            format!(
+                "{{\"field\":{},\"long\":{},\"masters\":[{}],\"default\":{},\"help\":{},\"owner\":{},\"level\":{},\"since\":{},\"expires\":{},\"link\":{}}}",
+                json_str(&clown.ident.to_string()),
+                json_str(&clown.long()),
+                clown
+                    .masters
+                    .iter()
+                    .map(|master| json_str(
+                        &expansion
+                            .masters
+                            .iter()
+                            .find(|known| known.ident == master)
+                            .map_or_else(|| long_flag(master, expansion.casing), Master::long),
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                clown.default,
+                json_str(&clown.help),
+                opt(&clown.owner),
+                opt(&clown.severity),
+                opt(&clown.since),
+                opt(&clown.expires),
+                opt(&clown.link),
+            )
+        })
+        .collect();
+    format!(
+        "{{\"crate\":{},\"version\":{},\"struct\":{},\"masters\":[{}],\"flags\":[{}]}}",
+        json_str(&std::env::var("CARGO_PKG_NAME").unwrap_or_default()),
+        json_str(&std::env::var("CARGO_PKG_VERSION").unwrap_or_default()),
+        json_str(name),
+        masters.join(","),
+        flags.join(",")
+    )
+}
+
+/// The markdown table behind `CLOWNTOWN_DOCS_MD`.
+fn docs_md(expansion: &Expansion) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut md = String::from(
+        "| Flag | Help | Owner | Since | Expires |\n| --- | --- | --- | --- | --- |\n",
+    );
+    for clown in expansion.clowns.iter() {
+        md.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} |\n",
+            clown.long(),
+            cell(&clown.help),
+            cell(clown.owner.as_deref().unwrap_or_default()),
+            cell(clown.since.as_deref().unwrap_or_default()),
+            cell(clown.expires.as_deref().unwrap_or_default()),
+        ));
+    }
+    md
+}
+
+/// The builder type generated for a struct.
+fn builder_ident(name: &Ident) -> Ident {
+    Ident::new(&format!("{}ClowntownBuilder", name), name.span())
+}
+
+/// Converts a `CamelCase` type name into `snake_case`.
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// The flag name after `=` in an option, as a string in kebab-case, snake_case or camelCase, or
+/// as a bare identifier, normalized to the field name.
+fn flag_name(meta: &syn::meta::ParseNestedMeta) -> syn::parse::Result<String> {
+    let value = meta.value()?;
+    let (name, span) = if value.peek(syn::LitStr) {
+        let lit = value.parse::<syn::LitStr>()?;
+        (lit.value(), lit.span())
+    } else {
+        let ident = value.parse::<Ident>()?;
+        (ident.to_string(), ident.span())
+    };
+    checked_flag_name(&name, span)
+}
+
+/// `name` normalized to a field name, failing when it can't be one or clap already uses it.
+fn checked_flag_name(name: &str, span: Span) -> syn::parse::Result<String> {
+    let normalized = normalize_name(name, span)?;
+    let why = if normalized.starts_with(|c: char| c.is_ascii_digit()) {
+        "it starts with a digit"
+    } else if syn::parse_str::<Ident>(&normalized).is_err() {
+        "it is a Rust keyword"
+    } else {
+        check_reserved(&normalized, span)?;
+        return Ok(normalized);
+    };
+    Err(syn::Error::new(
+        span,
+        format!(
+            "`{}` can't be a flag name: {}, expected e.g. `i-live-in-clowntown`",
+            name, why
+        ),
+    ))
+}
+
+/// Normalizes `name`, or a part of one, from kebab-case, snake_case or camelCase to the
+/// snake_case of field names, e.g. `--I-Live-In-Clowntown` or `iLiveInClowntown` to
+/// `i_live_in_clowntown`. Fails on what can't be in a field name.
+fn normalize_name(name: &str, span: Span) -> syn::parse::Result<String> {
+    let fail = |why: String| {
+        Err(syn::Error::new(
+            span,
+            format!(
+                "`{}` can't be a flag name: {}, expected e.g. `i-live-in-clowntown`",
+                name, why
+            ),
+        ))
+    };
+    let trimmed = name.trim().trim_start_matches("--");
+    if trimmed.is_empty() {
+        return fail("it is empty".to_string());
+    }
+    if let Some(c) = trimmed
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+    {
+        return fail(format!(
+            "`{}` is not an ASCII letter, a digit, `-` or `_`",
+            c.escape_default()
+        ));
+    }
+    if trimmed.starts_with(['-', '_']) || trimmed.ends_with(['-', '_']) {
+        return fail("it starts or ends with a separator".to_string());
+    }
+    let mut normalized = String::new();
+    let mut previous = '_';
+    for c in trimmed.chars() {
+        if c == '-' {
+            normalized.push('_');
+        } else if c.is_ascii_uppercase() {
+            // a new word in camelCase, not in an acronym or after a separator.
+            if previous.is_ascii_lowercase() || previous.is_ascii_digit() {
+                normalized.push('_');
+            }
+            normalized.push(c.to_ascii_lowercase());
+        } else {
+            normalized.push(c);
+        }
+        previous = c;
+    }
+    Ok(normalized)
+}
+
+/// Fails when `name` is one of the long flags clap generates, which would clash with ours.
+fn check_reserved(name: &str, span: Span) -> syn::parse::Result<()> {
+    let long = name.replace('_', "-");
+    if CLAP_RESERVED.contains(&long.as_str()) {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "`--{}` is generated by clap, pick another name, e.g. `i-live-in-clowntown`",
+                long
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// The names in a list option, like `groups(db, net)` or `prefixes("clowntown", "danger")`, each
+/// an identifier or a string, normalized like `flag_name`.
+fn name_list(meta: &syn::meta::ParseNestedMeta) -> syn::parse::Result<Vec<String>> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    let names = content.parse_terminated(
+        |input| {
+            let (name, span) = if input.peek(syn::LitStr) {
+                let lit = input.parse::<syn::LitStr>()?;
+                (lit.value(), lit.span())
+            } else {
+                let ident = input.parse::<Ident>()?;
+                (ident.to_string(), ident.span())
+            };
+            let name = normalize_name(&name, span)?;
+            if syn::parse_str::<Ident>(&name).is_err() {
+                return Err(syn::Error::new(
+                    span,
+                    format!("`{}` can't be part of a flag name", name),
+                ));
+            }
+            Ok(name)
+        },
+        syn::Token![,],
+    )?;
+    Ok(names.into_iter().collect())
+}
+
+/// Emits `message` as a compiler warning pointing at `span`.
+///
+/// `proc_macro::Diagnostic` is nightly-only, so we go through the deprecation lint instead:
+/// using a `#[deprecated]` item prints its note as a regular warning on stable.
+fn warning(span: Span, message: &str) -> proc_macro2::TokenStream {
+    quote::quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const clowncopterize: () = ();
+            clowncopterize
+        };
+    }
+}
+
+impl Clowncopterize {
+    /// The options that can't be combined, as the second one replaces the first.
+    const CONFLICTING: &'static [(&'static str, &'static str)] = &[
+        ("prefix", "prefixes"),
+        ("token_key", "value_parser"),
+        ("exclusive", "ack_flag"),
+        ("exclusive", "reason"),
+        ("exclusive", "companions"),
+        ("companions", "ack_flag"),
+        ("companions", "reason"),
+        ("repeatable", "action"),
+        ("repeatable", "value_parser"),
+        ("repeatable", "token_key"),
+        ("repeatable", "select"),
+        ("select", "action"),
+        ("select", "value_parser"),
+        ("select", "token_key"),
+        ("mode", "shadow"),
+        ("mode", "repeatable"),
+        ("mode", "select"),
+        ("mode", "action"),
+        ("mode", "value_parser"),
+        ("mode", "token_key"),
+        ("inject", "clowncopterizer"),
+        ("inject", "long"),
+        ("inject", "action"),
+        ("inject", "repeatable"),
+        ("inject", "select"),
+        ("inject", "mode"),
+        ("inject", "value_parser"),
+        ("inject", "token_key"),
+    ];
+
+    /// The options that can be given several times, each adding to a list.
+    const REPEATABLE: &'static [&'static str] =
+        &["external", "deny_env", "invariant", "extra_attrs"];
+
+    /// Fails when an `action` not taking a value is given along an option making the master take
+    /// one, `token_key` or `value_parser`.
+    fn check_action(&self, keys: &[(String, Span)]) -> syn::parse::Result<()> {
+        if self.action == MasterAction::Set || !keys.iter().any(|(key, _)| key == "action") {
+            return Ok(());
+        }
+        match keys
+            .iter()
+            .find(|(key, _)| key == "token_key" || key == "value_parser")
+        {
+            Some((key, span)) => Err(syn::Error::new(
+                *span,
+                format!(
+                    "`{}` makes the master take a value, which `action = \"{:?}\"` doesn't",
+                    key, self.action
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails when `subcommand` is given along an option making the `clowncopterizer` take a
+    /// value, which the subcommand has no room for.
+    fn check_subcommand(&self, keys: &[(String, Span)]) -> syn::parse::Result<()> {
+        if self.subcommand.is_none() || !self.master_takes_value(&self.clowncopterizer) {
+            return Ok(());
+        }
+        let span = keys
+            .iter()
+            .find(|(key, _)| key == "subcommand")
+            .map_or_else(Span::call_site, |(_, span)| *span);
+        Err(syn::Error::new(
+            span,
+            "`subcommand` can't be used with a master taking a value",
+        ))
+    }
+
+    /// Fails when two options name the same injected flag, e.g. `shadow = "clowncopterize"`.
+    fn check_names(&self, keys: &[(String, Span)]) -> syn::parse::Result<()> {
+        let names = [
+            ("clowncopterizer", Some(self.clowncopterizer.clone())),
+            ("unclowncopterizer", self.unclowncopterizer()),
+            ("ack_flag", self.ack_flag()),
+            ("shadow", self.shadow()),
+            ("until", self.until()),
+            ("reason", self.reason()),
+            ("completions", self.completions.clone()),
+            ("provenance", self.provenance.clone()),
+            ("clowntown_help", self.clowntown_help.clone()),
+        ];
+        let names: Vec<_> = names
+            .into_iter()
+            .filter_map(|(key, name)| Some((key, name?)))
+            .collect();
+        for (i, (key, name)) in names.iter().enumerate() {
+            let Some((other, _)) = names[..i].iter().find(|(_, other)| other == name) else {
+                continue;
+            };
+            let span = keys
+                .iter()
+                .rev()
+                .find(|(seen, _)| seen == key)
+                .map_or_else(Span::call_site, |(_, span)| *span);
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "`{}` and `{}` both name the injected flag `--{}`",
+                    other,
+                    key,
+                    name.replace('_', "-")
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_option(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::parse::Result<()> {
+        if meta.path.is_ident("clowncopterizer") {
+            self.clowncopterizer = flag_name(&meta)?;
+        } else if meta.path.is_ident("long_help") {
+            self.long_help = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("action") {
+            let action = meta.value()?.parse::<syn::LitStr>()?;
+            self.action = match action.value().as_str() {
+                "SetTrue" => MasterAction::SetTrue,
+                "Count" => MasterAction::Count,
+                "Set" => MasterAction::Set,
+                other => {
+                    return Err(syn::Error::new(
+                        action.span(),
+                        format!(
+                            "unknown action `{}`, expected one of: SetTrue, Count, Set",
+                            other
+                        ),
+                    ));
+                }
+            };
+        } else if meta.path.is_ident("vis") {
+            let vis = meta.value()?.parse::<syn::LitStr>()?;
+            self.vis = Some(if vis.value() == "inherit" {
+                Vis::Inherit
+            } else {
+                Vis::Declared(vis.parse()?)
+            });
+        } else if meta.path.is_ident("extra_attrs") {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            let attrs =
+                content.parse_terminated(|input| input.parse::<syn::LitStr>(), syn::Token![,])?;
+            for attr in attrs {
+                self.extra_attrs.push(attr.parse()?);
+            }
+        } else if meta.path.is_ident("inject") {
+            let value = meta.value()?;
+            let field = if value.peek(syn::LitStr) {
+                value
+                    .parse::<syn::LitStr>()?
+                    .parse_with(syn::Field::parse_named)?
+            } else {
+                let content;
+                syn::braced!(content in value);
+                let field = content.call(syn::Field::parse_named)?;
+                if !content.is_empty() {
+                    return Err(content.error("`inject` takes a single field"));
+                }
+                field
+            };
+            if !matches!(&field.ty, syn::Type::Path(ty) if ty.path.is_ident("bool")) {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "the `inject` field is a master flag, it must be a `bool`",
+                ));
+            }
+            self.long = attr_metas(&field.attrs, "arg")
+                .iter()
+                .find_map(|meta| meta_str(meta, "long"));
+            self.clowncopterizer = field.ident.as_ref().unwrap().to_string();
+            self.inject = Some(field);
+        } else if meta.path.is_ident("long") {
+            let long = meta.value()?.parse::<syn::LitStr>()?;
+            let name = long.value().trim_start_matches("--").to_string();
+            if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '=') {
+                return Err(syn::Error::new(
+                    long.span(),
+                    format!("`{}` can't be a long flag", long.value()),
+                ));
+            }
+            check_reserved(&name, long.span())?;
+            self.long = Some(name);
+        } else if meta.path.is_ident("unclowncopterizer") {
+            self.unclowncopterizer = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                String::new()
+            });
+        } else if meta.path.is_ident("completions") {
+            if !cfg!(feature = "completions") {
+                return Err(meta.error(
+                    "the `completions` option needs the `completions` feature of clowncopterize",
+                ));
+            }
+            self.completions = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                "clowntown_completions".to_string()
+            });
+        } else if meta.path.is_ident("provenance") {
+            self.provenance = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                "clowntown_provenance".to_string()
+            });
+        } else if meta.path.is_ident("warning_style") {
+            self.warning_style = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("after_help") {
+            self.after_help = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("version_audit") {
+            self.version_audit = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("external") {
+            // resolved at expansion time, as `prefix` may come later.
+            let mut prefix = None;
+            let mut path = None;
+            meta.parse_nested_meta(|meta| {
+                if meta.path.is_ident("prefix") {
+                    let lit = meta.value()?.parse::<syn::LitStr>()?;
+                    prefix = Some(normalize_name(&lit.value(), lit.span())?);
+                } else if meta.path.is_ident("in") {
+                    path = Some(meta.value()?.parse::<syn::LitStr>()?.parse::<syn::Path>()?);
+                } else {
+                    return Err(unknown_key(&meta, &["prefix", "in"]));
+                }
+                Ok(())
+            })?;
+            let Some(path) = path else {
+                return Err(meta.error("`external` needs the struct, as `in = \"path::to::Args\"`"));
+            };
+            self.externals.push((prefix, path));
+        } else if meta.path.is_ident("guard") {
+            if meta.input.peek(syn::token::Paren) {
+                let mut guard = None;
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("fn") {
+                        guard = Some(meta.value()?.parse::<syn::LitStr>()?.parse()?);
+                    } else if meta.path.is_ident("env") {
+                        self.guard_env = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                    } else {
+                        return Err(unknown_key(&meta, &["fn", "env"]));
+                    }
+                    Ok(())
+                })?;
+                let Some(guard) = guard else {
+                    return Err(
+                        meta.error("`guard` needs the function, as `fn = \"path::to::guard\"`")
+                    );
+                };
+                self.guard = Some(guard);
+            } else {
+                self.guard = Some(meta.value()?.parse::<syn::LitStr>()?.parse()?);
+            }
+        } else if meta.path.is_ident("value_parser") {
+            self.value_parser = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("token_key") {
+            if !cfg!(feature = "token") {
+                return Err(meta
+                    .error("the `token_key` option needs the `token` feature of clowncopterize"));
+            }
+            self.token_key = Some(meta.value()?.parse::<syn::LitStr>()?.parse()?);
+        } else if meta.path.is_ident("sink") {
+            self.sink = Some(meta.value()?.parse::<syn::LitStr>()?.parse()?);
+        } else if meta.path.is_ident("env_filter") {
+            self.env_filter = true;
+        } else if meta.path.is_ident("report") {
+            let report = meta.value()?.parse::<syn::LitStr>()?;
+            self.report = match report.value().as_str() {
+                "stderr" => true,
+                "off" => false,
+                _ => {
+                    return Err(syn::Error::new(
+                        report.span(),
+                        "expected `report = \"stderr\"` or `report = \"off\"`",
+                    ));
+                }
+            };
+        } else if meta.path.is_ident("sentinel") {
+            self.sentinel = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("deny_env") {
+            self.deny_env
+                .push(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("deny_release") {
+            self.deny_release = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("policy") {
+            self.policy = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("localize") {
+            self.localize = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("ack_flag") {
+            self.ack_flag = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                String::new()
+            });
+        } else if meta.path.is_ident("shadow") {
+            self.shadow = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                String::new()
+            });
+        } else if meta.path.is_ident("reason") {
+            self.reason = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                String::new()
+            });
+        } else if meta.path.is_ident("companions") {
+            self.ack_flag = Some(String::new());
+            self.reason = Some(String::new());
+        } else if meta.path.is_ident("until") {
+            self.until = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                String::new()
+            });
+        } else if meta.path.is_ident("clowntown_help") {
+            self.clowntown_help = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                "clowntown_help".to_string()
+            });
+        } else if meta.path.is_ident("subcommand") {
+            self.subcommand = Some(if meta.input.peek(syn::Token![=]) {
+                flag_name(&meta)?
+            } else {
+                String::new()
+            });
+        } else if meta.path.is_ident("runtime_max") {
+            self.runtime_max = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+        } else if meta.path.is_ident("warn_above") {
+            self.warn_above = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+        } else if meta.path.is_ident("strip_prefix") {
+            self.strip_prefix = match meta.value()?.parse::<syn::Lit>()? {
+                syn::Lit::Bool(strip) => strip.value.then(String::new),
+                syn::Lit::Str(prefix) if prefix.value().is_empty() => Some(String::new()),
+                syn::Lit::Str(prefix) => Some(normalize_name(&prefix.value(), prefix.span())?),
+                lit => return Err(syn::Error::new(lit.span(), "expected a bool or a string")),
+            };
+        } else if meta.path.is_ident("hide_flags") {
+            self.hide_flags = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("prefix") {
+            self.prefixes = vec![flag_name(&meta)?];
+        } else if meta.path.is_ident("prefixes") {
+            self.prefixes = name_list(&meta)?;
+            if self.prefixes.is_empty() {
+                return Err(meta.error("`prefixes` needs at least one prefix"));
+            }
+        } else if meta.path.is_ident("groups") {
+            self.groups = Some(name_list(&meta)?);
+        } else if meta.path.is_ident("hidden") {
+            self.hidden = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("exclusive") {
+            self.exclusive = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("global") {
+            self.global = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("locked") {
+            self.locked = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else if meta.path.is_ident("manifest") {
+            self.manifest = true;
+        } else if meta.path.is_ident("repeatable") {
+            self.repeatable = true;
+        } else if meta.path.is_ident("select") {
+            self.select = true;
+        } else if meta.path.is_ident("mode") {
+            self.mode = Some(if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::LitStr>()?.parse()?
+            } else {
+                Ident::new("ClowncopterizeMode", Span::call_site())
+            });
+        } else if meta.path.is_ident("invariant") {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            let source = content.parse::<syn::LitStr>()?;
+            let expr = invariant::parse(&source.value()).map_err(|message| {
+                syn::Error::new(source.span(), format!("invalid invariant: {}", message))
+            })?;
+            self.invariants.push((source, expr));
+        } else if meta.path.is_ident("panic_in_tests") {
+            self.panic_in_tests = true;
+        } else if meta.path.is_ident("toggle") {
+            self.toggle = true;
+        } else if meta.path.is_ident("default_true") {
+            let default_true = meta.value()?.parse::<syn::LitStr>()?;
+            self.default_true = match default_true.value().as_str() {
+                "warn" => DefaultTrue::Warn,
+                "skip" => DefaultTrue::Skip,
+                "invert" => DefaultTrue::Invert,
+                other => {
+                    return Err(syn::Error::new(
+                        default_true.span(),
+                        format!(
+                            "unknown `default_true` value `{}`, expected one of: warn, skip, invert",
+                            other
+                        ),
+                    ));
+                }
+            };
+        } else if meta.path.is_ident("verbose") {
+            self.verbose = true;
+        } else if meta.path.is_ident("generate_tests") {
+            self.generate_tests = meta.value()?.parse::<syn::LitBool>()?.value;
+        } else {
+            let attr_name = meta.path.to_token_stream();
+            return Err(meta.error(format!("Unexpected attribute {}", attr_name)));
+        }
+        Ok(())
+    }
+
+    /// The long flag of the master named `name`, e.g. `--clowncopterize`, honoring `long`.
+    fn master_long(&self, name: &str, casing: Casing) -> String {
+        match &self.long {
+            Some(long) if name == self.clowncopterizer => format!("--{}", long),
+            _ => long_flag(name, casing),
+        }
+    }
+
+    /// The longest of the `prefixes` that `name` starts with, if any.
+    fn prefix_of(&self, name: &str) -> Option<&str> {
+        self.prefixes
+            .iter()
+            .filter(|prefix| name.starts_with(prefix.as_str()))
+            .max_by_key(|prefix| prefix.len())
+            .map(String::as_str)
+    }
+
+    /// `, global = true` for an injected flag, when the struct has subcommands or asks for it.
+    fn global_arg(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        if self.global || expansion.has_subcommand {
+            quote::quote!(, global = true)
+        } else {
+            proc_macro2::TokenStream::new()
+        }
+    }
+
+    /// `, help = ...` for an injected flag, when the help goes through the `localize` function.
+    fn localized_help(&self, key: &str, default: &str) -> proc_macro2::TokenStream {
+        match &self.localize {
+            Some(localize) => quote::quote!(, help = #localize(#key, #default)),
+            None => proc_macro2::TokenStream::new(),
+        }
+    }
+
+    /// `text`, in the warning style if enabled. Clap strips the escapes when colors are off,
+    /// e.g. with `NO_COLOR` or when not writing to a terminal.
+    fn styled(&self, text: &str) -> String {
+        if self.warning_style {
+            format!("{}{}{}", WARNING_STYLE, text, WARNING_STYLE_RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Whether the master flags take a value, a token or one checked by the `value_parser`.
+    fn takes_value(&self) -> bool {
+        self.token_key.is_some() || self.value_parser.is_some() || self.action == MasterAction::Set
+    }
+
+    /// Whether the master named `name` takes a value, like the `repeatable` clowncopterizer.
+    fn master_takes_value(&self, name: &str) -> bool {
+        self.takes_value() || ((self.repeatable || self.select) && name == self.clowncopterizer)
+    }
+
+    /// The `#[arg(...)]` items making the master `long` take a value, if it does.
+    fn master_value_args(&self, long: &str) -> Option<proc_macro2::TokenStream> {
+        match &self.value_parser {
+            Some(value_parser) => Some(Self::value_parser_args(value_parser)),
+            None if self.token_key.is_none() && self.action == MasterAction::Set => {
+                Some(Self::value_parser_args(&syn::parse_quote!(
+                    ::clap::builder::NonEmptyStringValueParser::new()
+                )))
+            }
+            None => self.token_args(long),
+        }
+    }
+
+    /// The `#[arg(...)]` items making a master take a value checked by `value_parser`, turning
+    /// its clowntown flags on when clap accepts one. Like for tokens, the empty default stands
+    /// for the master being off.
+    fn value_parser_args(value_parser: &syn::Expr) -> proc_macro2::TokenStream {
+        quote::quote! {
+            action = ::clap::ArgAction::Set,
+            value_name = "VALUE",
+            default_value = "",
+            hide_default_value = true,
+            value_parser = {
+                #[derive(Clone)]
+                struct ClowntownValue<P>(P);
+
+                impl<P: ::clap::builder::TypedValueParser> ::clap::builder::TypedValueParser
+                    for ClowntownValue<P>
+                {
+                    type Value = bool;
+
+                    fn parse_ref(
+                        &self,
+                        cmd: &::clap::Command,
+                        arg: ::std::option::Option<&::clap::Arg>,
+                        value: &::std::ffi::OsStr,
+                    ) -> ::std::result::Result<bool, ::clap::Error> {
+                        self.parse_ref_(cmd, arg, value, ::clap::parser::ValueSource::CommandLine)
+                    }
+
+                    fn parse_ref_(
+                        &self,
+                        cmd: &::clap::Command,
+                        arg: ::std::option::Option<&::clap::Arg>,
+                        value: &::std::ffi::OsStr,
+                        source: ::clap::parser::ValueSource,
+                    ) -> ::std::result::Result<bool, ::clap::Error> {
+                        if source == ::clap::parser::ValueSource::DefaultValue {
+                            return ::std::result::Result::Ok(false);
+                        }
+                        self.0.parse_ref_(cmd, arg, value, source).map(|_| true)
+                    }
+
+                    fn possible_values(
+                        &self,
+                    ) -> ::std::option::Option<
+                        ::std::boxed::Box<dyn ::std::iter::Iterator<Item = ::clap::builder::PossibleValue> + '_>,
+                    > {
+                        self.0.possible_values()
+                    }
+                }
+
+                ClowntownValue(#value_parser)
+            }
+        }
+    }
+
+    /// The `#[arg(...)]` items making the master `long` take a token checked against the
+    /// `token_key`, if any.
+    ///
+    /// The empty default stands for the master being off; an explicit empty token is refused
+    /// like any invalid one, since the clowntown flags are wired to the master being present.
+    fn token_args(&self, long: &str) -> Option<proc_macro2::TokenStream> {
+        let key = self.token_key.as_ref()?;
+        Some(quote::quote! {
+            action = ::clap::ArgAction::Set,
+            value_name = "TOKEN",
+            default_value = "",
+            hide_default_value = true,
+            value_parser = {
+                #[derive(Clone)]
+                struct ClowntownToken;
+
+                impl ::clap::builder::TypedValueParser for ClowntownToken {
+                    type Value = bool;
+
+                    fn parse_ref(
+                        &self,
+                        cmd: &::clap::Command,
+                        arg: ::std::option::Option<&::clap::Arg>,
+                        value: &::std::ffi::OsStr,
+                    ) -> ::std::result::Result<bool, ::clap::Error> {
+                        self.parse_ref_(cmd, arg, value, ::clap::parser::ValueSource::CommandLine)
+                    }
+
+                    fn parse_ref_(
+                        &self,
+                        _cmd: &::clap::Command,
+                        _arg: ::std::option::Option<&::clap::Arg>,
+                        value: &::std::ffi::OsStr,
+                        source: ::clap::parser::ValueSource,
+                    ) -> ::std::result::Result<bool, ::clap::Error> {
+                        if source == ::clap::parser::ValueSource::DefaultValue {
+                            return ::std::result::Result::Ok(false);
+                        }
+                        ::clowncopterize::__private::verify_token(
+                            #key().as_deref(),
+                            #long,
+                            &value.to_string_lossy(),
+                            ::std::time::SystemTime::now(),
+                        )
+                        .map(|()| true)
+                        .map_err(|reason| {
+                            ::clap::Error::raw(
+                                ::clap::error::ErrorKind::ValueValidation,
+                                ::std::format!("'{}' is refused: {}\n", #long, reason),
+                            )
+                        })
+                    }
+                }
+
+                ClowntownToken
+            }
+        })
+    }
+
+    /// `, help_heading = ...` putting an injected flag in the clowntown section, if styled.
+    fn injected_heading(&self) -> proc_macro2::TokenStream {
+        if self.warning_style {
+            let heading = self.styled(CLOWNTOWN_HEADING);
+            quote::quote!(, help_heading = #heading)
+        } else {
+            proc_macro2::TokenStream::new()
+        }
+    }
+
+    /// Name of the flag forcing every clowntown flag off, if enabled.
+    fn unclowncopterizer(&self) -> Option<String> {
+        match self.unclowncopterizer.as_deref() {
+            Some("") => Some(format!("un_{}", self.clowncopterizer)),
+            Some(name) => Some(name.replace("-", "_")),
+            None => None,
+        }
+    }
+
+    /// Name of the flag the masters require, if enabled.
+    fn ack_flag(&self) -> Option<String> {
+        match self.ack_flag.as_deref() {
+            Some("") => Some(format!("{}_ack", self.clowncopterizer)),
+            Some(name) => Some(name.replace("-", "_")),
+            None => None,
+        }
+    }
+
+    /// Name of the flag recording the intent to use the `clowncopterizer`, if enabled.
+    fn shadow(&self) -> Option<String> {
+        match self.shadow.as_deref() {
+            Some("") => Some(format!("{}_shadow", self.clowncopterizer)),
+            Some(name) => Some(name.replace("-", "_")),
+            None => None,
+        }
+    }
+
+    /// Name of the subcommand activating the `clowncopterizer`, if enabled.
+    fn subcommand(&self) -> Option<String> {
+        match self.subcommand.as_deref() {
+            Some("") => Some(self.clowncopterizer.replace('_', "-")),
+            Some(name) => Some(name.replace('_', "-")),
+            None => None,
+        }
+    }
+
+    /// Name of the flag taking the reason for using the masters, if enabled.
+    fn reason(&self) -> Option<String> {
+        match self.reason.as_deref() {
+            Some("") => Some(format!("{}_reason", self.clowncopterizer)),
+            Some(name) => Some(name.replace("-", "_")),
+            None => None,
+        }
+    }
+
+    /// Name of the flag taking a deadline, if enabled.
+    fn until(&self) -> Option<String> {
+        match self.until.as_deref() {
+            Some("") => Some(format!("{}_until", self.clowncopterizer)),
+            Some(name) => Some(name.replace("-", "_")),
+            None => None,
+        }
+    }
+
+    fn expand(&self, ast: syn::ItemStruct) -> proc_macro2::TokenStream {
+        let mut expansion = Expansion {
+            is_parser: derives(&ast, "Parser"),
+            has_subcommand: ast.fields.iter().any(|field| {
+                attr_metas(&field.attrs, "command")
+                    .into_iter()
+                    .chain(attr_metas(&field.attrs, "clap"))
+                    .any(|meta| meta.path().is_ident("subcommand"))
+            }),
+            ..Default::default()
+        };
+        match Casing::from_attrs(&ast.attrs) {
+            Ok(casing) => expansion.casing = casing,
+            Err(err) => expansion.errors.push(err),
+        }
+        let mut ast = self.clowncopterize_struct(ast, &mut expansion);
+        check_duplicate_longs(&ast, &mut expansion);
+        let policy = match &self.policy {
+            Some(policy) => self.check_policy(policy, &mut expansion),
+            None => proc_macro2::TokenStream::new(),
+        };
+        let deny_release = if self.deny_release {
+            let message = format!(
+                "`{}` has clowntown master flags, which are denied in release builds; set \
+                 CLOWNTOWN_ALLOW_RELEASE when building to allow them",
+                ast.ident
+            );
+            // evaluated in the user's crate, which knows its own profile.
+            quote::quote! {
+                #[cfg(not(debug_assertions))]
+                const _: () = if ::std::option_env!("CLOWNTOWN_ALLOW_RELEASE").is_none() {
+                    ::std::panic!(#message);
+                };
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        if self.after_help && !expansion.clowns.is_empty() {
+            self.after_help(&mut ast, &expansion);
+        }
+        if self.version_audit {
+            self.version_audit(&mut ast, &mut expansion);
+        }
+        if !expansion.clowns.is_empty() {
+            doc_aliases(&mut ast, &expansion);
+        }
+        if cfg!(feature = "serde") && derives(&ast, "Deserialize") {
+            serde_defaults(&mut ast, &expansion);
+        }
+        let tests = if self.generate_tests && !expansion.clowns.is_empty() {
+            self.generate_tests(&ast, &expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        if let syn::Fields::Named(fields) = &ast.fields {
+            for (source, expr) in self.invariants.iter() {
+                for flag in expr.flags() {
+                    let known = fields.named.iter().any(|field| {
+                        field.ident.as_ref().is_some_and(|ident| ident == flag)
+                            && matches!(&field.ty, syn::Type::Path(ty) if ty.path.is_ident("bool"))
+                    });
+                    if !known {
+                        expansion.errors.push(syn::Error::new(
+                            source.span(),
+                            format!(
+                                "invariant refers to `{}`, which is not a `bool` field",
+                                flag
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        let inherent_impl = self.inherent_impl(&ast, &expansion);
+        let builder = if expansion.is_parser {
+            self.builder(&ast, &expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let provider = if cfg!(feature = "figment") && !expansion.clowns.is_empty() {
+            self.figment_provider(&ast, &expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let arbitrary = if cfg!(feature = "proptest")
+            && expansion.is_parser
+            && derives(&ast, "Debug")
+            && ast.generics.params.is_empty()
+        {
+            self.arbitrary(&ast)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let has_clowntown_flags = self.has_clowntown_flags(&ast, &expansion);
+        let clowncopterized = self.clowncopterized(&ast, &expansion);
+        let report_from = self.clowntown_report_from(&ast, &expansion);
+        let mode = match &expansion.mode {
+            Some(mode) => self.mode_enum(&ast, mode),
+            None => proc_macro2::TokenStream::new(),
+        };
+        let registry = if cfg!(feature = "inventory") {
+            self.inventory_registry(&ast, &expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let manifest = if self.manifest {
+            self.manifest(&ast, &expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let test_macro = if !expansion.clowns.is_empty() {
+            self.test_macro(&ast, &expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let mut warnings: Vec<_> = expansion
+            .notes
+            .iter()
+            .filter(|_| self.verbose)
+            .map(|note| warning(note.span, &note.message))
+            .collect();
+        if let Some(threshold) = self
+            .warn_above
+            .filter(|threshold| expansion.clowns.len() > *threshold)
+        {
+            warnings.push(warning(
+                ast.ident.span(),
+                &format!(
+                    "`{}` has {} clowntown flags, more than the {} of `warn_above`; consider \
+                     retiring the ones no incident needs anymore",
+                    ast.ident,
+                    expansion.clowns.len(),
+                    threshold
+                ),
+            ));
+        }
+        if self.default_true == DefaultTrue::Warn {
+            warnings.extend(
+                expansion
+                    .clowns
+                    .iter()
+                    .filter(|clown| clown.is_redundant())
+                    .map(|clown| {
+                        warning(
+                            clown.ident.span(),
+                            &format!(
+                                "`{}` already defaults to {}, the value its masters would set, so \
+                                 they leave it alone; mark it `#[clowntown(invert)]`, or set \
+                                 `default_true = \"skip\"` or `\"invert\"`",
+                                clown.ident, clown.default
+                            ),
+                        )
+                    }),
+            );
+        }
+        let errors = expansion.errors.iter().map(syn::Error::to_compile_error);
+        quote::quote! {
+            #ast
+            #mode
+            #deny_release
+            #policy
+            #inherent_impl
+            #builder
+            #has_clowntown_flags
+            #clowncopterized
+            #report_from
+            #registry
+            #manifest
+            #provider
+            #arbitrary
+            #tests
+            #test_macro
+            #(#warnings)*
+            #(#errors)*
+        }
+    }
+
+    /// Expands `#[derive(ClowntownIntrospect)]`: the metadata and trait impls of the clowntown
+    /// flags, without touching the struct.
+    fn introspect(&self, ast: syn::ItemStruct) -> proc_macro2::TokenStream {
+        let mut expansion = Expansion::default();
+        match Casing::from_attrs(&ast.attrs) {
+            Ok(casing) => expansion.casing = casing,
+            Err(err) => expansion.errors.push(err),
+        }
+        // only run for the expansion: a derive cannot rewrite the struct it is given.
+        let _ = self.clowncopterize_struct(ast.clone(), &mut expansion);
+        // the masters are the user's business, so every change reads as explicit.
+        expansion.masters.clear();
+        for clown in &mut expansion.clowns {
+            clown.masters.clear();
+        }
+        let name = &ast.ident;
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        let manifest = manifest_items(name, &expansion);
+        let diff = self.diff(&expansion);
+        let apply_to = self.apply_to(&expansion);
+        let has_clowntown_flags = self.has_clowntown_flags(&ast, &expansion);
+        let clowncopterized = self.clowncopterized(&ast, &expansion);
+        let report_from = self.clowntown_report_from(&ast, &expansion);
+        let errors = expansion.errors.iter().map(syn::Error::to_compile_error);
+        quote::quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                #manifest
+                #diff
+                #apply_to
+            }
+            #has_clowntown_flags
+            #clowncopterized
+            #report_from
+            #(#errors)*
+        }
+    }
+
+    /// Reports the clowntown flags wired to a master although `policy` says they must never be
+    /// mass-enabled, and makes the crate rebuild when the policy changes.
+    fn check_policy(
+        &self,
+        policy: &syn::LitStr,
+        expansion: &mut Expansion,
+    ) -> proc_macro2::TokenStream {
+        let path = std::path::Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default())
+            .join(policy.value());
+        let never_mass_enable = std::fs::read_to_string(&path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))
+            .and_then(|contents| {
+                contents
+                    .parse::<toml::Table>()
+                    .map_err(|err| format!("cannot parse {}: {}", path.display(), err))
+            })
+            .and_then(|table| match table.get("never_mass_enable") {
+                None => Ok(Vec::new()),
+                Some(toml::Value::Array(flags)) => flags
+                    .iter()
+                    .map(|flag| match flag {
+                        toml::Value::String(flag) => Ok(flag.clone()),
+                        _ => Err("`never_mass_enable` must be a list of strings".to_string()),
+                    })
+                    .collect(),
+                Some(_) => Err("`never_mass_enable` must be a list of strings".to_string()),
+            });
+        let never_mass_enable = match never_mass_enable {
+            Ok(flags) => flags,
+            Err(message) => {
+                expansion
+                    .errors
+                    .push(syn::Error::new(policy.span(), message));
+                return proc_macro2::TokenStream::new();
+            }
+        };
+        for clown in expansion.clowns.iter() {
+            if never_mass_enable
+                .iter()
+                .any(|flag| clown.ident == flag || clown.long() == *flag)
+            {
+                expansion.errors.push(syn::Error::new(
+                    clown.ident.span(),
+                    format!(
+                        "`{}` must never be mass-enabled per {}, but {} would turn it on; mark it `#[clowntown(never_mass_enable)]`",
+                        clown.ident,
+                        policy.value(),
+                        clown
+                            .masters
+                            .iter()
+                            .map(|master| format!("`{}`", self.master_long(master, expansion.casing)))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+        }
+        let path = path.display().to_string();
+        quote::quote! {
+            const _: &[u8] = ::std::include_bytes!(#path);
+        }
+    }
+
+    /// Appends the flags each master turns on to the command's `after_help`.
+    fn after_help(&self, ast: &mut syn::ItemStruct, expansion: &Expansion) {
+        let listing = expansion
+            .masters
+            .iter()
+            .map(|master| {
+                let longs: Vec<_> = expansion.clowns_of(master).map(ClownField::long).collect();
+                format!(
+                    "The following flags are mass-enabled by {}: {}",
+                    master.long(),
+                    longs.join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        // clap keeps the last `after_help`, so ours wraps the user's one if there is one.
+        let user = attr_metas(&ast.attrs, "command")
+            .into_iter()
+            .chain(attr_metas(&ast.attrs, "clap"))
+            .filter_map(|meta| match meta {
+                syn::Meta::NameValue(name_value) if name_value.path.is_ident("after_help") => {
+                    Some(name_value.value)
+                }
+                _ => None,
+            })
+            .last();
+        let after_help = match user {
+            Some(user) => quote::quote!(::std::format!("{}\n\n{}", #user, #listing)),
+            None => quote::quote!(#listing),
+        };
+        ast.attrs
+            .push(syn::parse_quote!(#[command(after_help = #after_help)]));
+    }
+
+    /// Appends the number of clowntown flags and the masters to the command's `version`.
+    fn version_audit(&self, ast: &mut syn::ItemStruct, expansion: &mut Expansion) {
+        let masters: Vec<_> = expansion.masters.iter().map(Master::long).collect();
+        let audit = format!(
+            " (clowntown: {} flag{}, {})",
+            expansion.clowns.len(),
+            if expansion.clowns.len() == 1 { "" } else { "s" },
+            if masters.is_empty() {
+                "no master".to_string()
+            } else {
+                masters.join(", ")
+            }
+        );
+        // clap keeps the last `version`, so ours wraps the user's one. `concat!` keeps it a
+        // `&'static str`, which is all clap takes without its `string` feature.
+        let user = attr_metas(&ast.attrs, "command")
+            .into_iter()
+            .chain(attr_metas(&ast.attrs, "clap"))
+            .filter_map(|meta| match meta {
+                syn::Meta::Path(path) if path.is_ident("version") => {
+                    Some(quote::quote!(::std::env!("CARGO_PKG_VERSION")))
+                }
+                syn::Meta::NameValue(name_value) if name_value.path.is_ident("version") => {
+                    Some(name_value.value.to_token_stream())
+                }
+                _ => None,
+            })
+            .last();
+        let Some(user) = user else {
+            expansion.errors.push(syn::Error::new(
+                ast.ident.span(),
+                "`version_audit` needs a `#[command(version)]` to append to",
+            ));
+            return;
+        };
+        ast.attrs
+            .push(syn::parse_quote!(#[command(version = ::std::concat!(#user, #audit))]));
+    }
+
+    /// Emits the `HasClowntownFlags` impl, reporting the nested structs' flags along ours.
+    fn has_clowntown_flags(
+        &self,
+        ast: &syn::ItemStruct,
+        expansion: &Expansion,
+    ) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let names = expansion.clowns.iter().map(|clown| clown.ident.to_string());
+        let nested_idents = expansion.nested.iter().map(|(ident, _)| ident);
+        let nested_tys = expansion.nested.iter().map(|(_, ty)| ty);
+        quote::quote! {
+            impl #impl_generics ::clowncopterize::HasClowntownFlags for #name #ty_generics #where_clause {
+                fn clowntown_flags() -> ::std::vec::Vec<::clowncopterize::ClownFlagInfo> {
+                    let mut flags = Self::clowntown_manifest().to_vec();
+                    #(
+                        flags.extend(
+                            <#nested_tys as ::clowncopterize::HasClowntownFlags>::clowntown_flags(),
+                        );
+                    )*
+                    flags
+                }
+
+                fn enabled_clowntown_flags(&self) -> ::std::vec::Vec<&'static str> {
+                    let mut enabled = ::std::vec::Vec::new();
+                    #(
+                        if self.#idents == #values {
+                            enabled.push(#names);
+                        }
+                    )*
+                    #(
+                        enabled.extend(
+                            ::clowncopterize::HasClowntownFlags::enabled_clowntown_flags(
+                                &self.#nested_idents,
+                            ),
+                        );
+                    )*
+                    enabled
+                }
+            }
+        }
+    }
+
+    /// Emits the `Clowncopterized` impl, setting the nested structs' flags along ours.
+    fn clowncopterized(
+        &self,
+        ast: &syn::ItemStruct,
+        expansion: &Expansion,
+    ) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let names = expansion.clowns.iter().map(|clown| clown.ident.to_string());
+        let nested_idents = expansion.nested.iter().map(|(ident, _)| ident);
+        quote::quote! {
+            impl #impl_generics ::clowncopterize::Clowncopterized for #name #ty_generics #where_clause {
+                fn set_clowntown_flag(&mut self, name: &str, value: bool) -> bool {
+                    #(
+                        if name == #names {
+                            self.#idents = value;
+                            return true;
+                        }
+                    )*
+                    #(
+                        if ::clowncopterize::Clowncopterized::set_clowntown_flag(
+                            &mut self.#nested_idents,
+                            name,
+                            value,
+                        ) {
+                            return true;
+                        }
+                    )*
+                    false
+                }
+            }
+        }
+    }
+
+    /// Emits the `From<&Cli> for ClowntownReport` impl, reporting the nested structs' flags
+    /// along ours.
+    fn clowntown_report_from(
+        &self,
+        ast: &syn::ItemStruct,
+        expansion: &Expansion,
+    ) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let nested_idents = expansion.nested.iter().map(|(ident, _)| ident);
+        quote::quote! {
+            impl #impl_generics ::std::convert::From<&#name #ty_generics> for ::clowncopterize::ClowntownReport #where_clause {
+                fn from(cli: &#name #ty_generics) -> Self {
+                    let mut flags = ::clowncopterize::__private::report_entries(
+                        <#name #ty_generics>::clowntown_manifest(),
+                        &[#(cli.#idents == #values),*],
+                        &cli.clowntown_diff(),
+                    );
+                    #(
+                        flags.extend(::clowncopterize::ClowntownReport::from(&cli.#nested_idents).flags);
+                    )*
+                    ::clowncopterize::ClowntownReport { flags }
+                }
+            }
+        }
+    }
+
+    /// Emits the `inventory` registration of every clowntown flag.
+    fn inventory_registry(
+        &self,
+        ast: &syn::ItemStruct,
+        expansion: &Expansion,
+    ) -> proc_macro2::TokenStream {
+        let infos = expansion
+            .clowns
+            .iter()
+            .map(|clown| clown_flag_info(&ast.ident, clown));
+        quote::quote! {
+            #(
+                ::clowncopterize::__private::inventory::submit! { #infos }
+            )*
+        }
+    }
+
+    /// Emits the manifest of the clowntown flags, as a const, in the `.clowntown` link section
+    /// and through `write_clowntown_manifest`.
+    fn manifest(&self, ast: &syn::ItemStruct, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let json = manifest_json(&name.to_string(), expansion);
+        let file_name = format!("clowntown-{}.json", name);
+        // One JSON document per line, so the records of several structs linked in the same
+        // binary stay apart.
+        let record = format!("{}\n", json);
+        let len = record.len();
+        let bytes = syn::LitByteStr::new(record.as_bytes(), Span::call_site());
+        let static_ident = Ident::new(
+            &format!(
+                "__CLOWNTOWN_MANIFEST_{}",
+                snake_case(&name.to_string()).to_uppercase()
+            ),
+            Span::call_site(),
+        );
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        quote::quote! {
+            #[used]
+            #[cfg_attr(target_vendor = "apple", unsafe(link_section = "__DATA,__clowntown"))]
+            #[cfg_attr(windows, unsafe(link_section = ".clwntwn"))]
+            #[cfg_attr(
+                not(any(target_vendor = "apple", windows)),
+                unsafe(link_section = ".clowntown")
+            )]
+            #[doc(hidden)]
+            #[allow(non_upper_case_globals)]
+            static #static_ident: [u8; #len] = *#bytes;
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// A JSON manifest of the clowntown flags, for fleet auditing tools.
+                pub const CLOWNTOWN_MANIFEST_JSON: &'static str = #json;
+
+                /// Writes `CLOWNTOWN_MANIFEST_JSON` to `dir`, as `clowntown-<Struct>.json`, and
+                /// returns its path.
+                pub fn write_clowntown_manifest<P>(
+                    dir: P,
+                ) -> ::std::io::Result<::std::path::PathBuf>
+                where
+                    P: ::std::convert::AsRef<::std::path::Path>,
+                {
+                    let path = dir.as_ref().join(#file_name);
+                    ::std::fs::write(&path, Self::CLOWNTOWN_MANIFEST_JSON)?;
+                    ::std::result::Result::Ok(path)
+                }
+            }
+        }
+    }
+
+    /// Emits the inherent `impl` block carrying the generated consts and methods.
+    fn inherent_impl(
+        &self,
+        ast: &syn::ItemStruct,
+        expansion: &Expansion,
+    ) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        let parse_wrapper = if expansion.is_parser {
+            let parse_wrapper = self.parse_wrapper(expansion);
+            let with_all_clowntown = self.with_all_clowntown(expansion);
+            let completions = self.clowntown_completions(expansion);
+            let clowntown_help = self.clowntown_help_command(expansion);
+            let builder = builder_ident(name);
+            let longs = expansion.clowns.iter().map(ClownField::long);
+            quote::quote! {
+                #parse_wrapper
+                #with_all_clowntown
+                #completions
+                #clowntown_help
+
+                /// Every combination of clowntown flags, as command line arguments.
+                ///
+                /// Yields `2^N` argument vectors for `N` clowntown flags, starting with the empty
+                /// one.
+                pub fn clowntown_combinations()
+                -> impl ::std::iter::Iterator<Item = ::std::vec::Vec<::std::string::String>> {
+                    Self::clowntown_combinations_up_to(usize::MAX)
+                }
+
+                /// The combinations of at most `max` clowntown flags, as command line arguments.
+                pub fn clowntown_combinations_up_to(
+                    max: usize,
+                ) -> impl ::std::iter::Iterator<Item = ::std::vec::Vec<::std::string::String>> {
+                    ::clowncopterize::__private::combinations(&[#(#longs),*], max)
+                }
+
+                /// A builder picking which clowntown flags to turn on.
+                pub fn clowntown_builder() -> #builder #ty_generics {
+                    #builder::new()
+                }
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let select = self.select(expansion);
+        let manifest = manifest_items(name, expansion);
+        let post_parse = self.post_parse(expansion);
+        let merge_from_config = self.merge_from_config(expansion);
+        let apply_masters = self.apply_masters(expansion);
+        let diff = self.diff(expansion);
+        let apply_to = self.apply_to(expansion);
+        let source = self.source(expansion);
+        let explain = self.explain();
+        let provenance = self.provenance(name);
+        let reload = self.reload_clowntown(expansion);
+        let resolver = self.apply_resolver(expansion);
+        let metrics = if cfg!(feature = "metrics") {
+            self.record_metrics(expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let audit = if cfg!(feature = "syslog") {
+            self.syslog_audit(expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let sentry = if cfg!(feature = "sentry") {
+            self.sentry_context(expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let otel = if cfg!(feature = "otel") {
+            self.otel_attributes(expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let prometheus = if cfg!(feature = "prometheus") {
+            self.prometheus_gauges(expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let validate = if self.invariants.is_empty() {
+            proc_macro2::TokenStream::new()
+        } else {
+            self.validate()
+        };
+        let sink = self.record_sink(expansion);
+        let report = if self.report {
+            self.report(expansion)
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        quote::quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                #manifest
+
+                #select
+
+                #parse_wrapper
+                #post_parse
+                #merge_from_config
+                #apply_masters
+                #diff
+                #apply_to
+                #source
+                #explain
+                #provenance
+                #reload
+                #resolver
+                #metrics
+                #sink
+                #prometheus
+                #otel
+                #sentry
+                #audit
+                #validate
+                #report
+            }
+        }
+    }
+
+    /// Emits `clowntown_report`.
+    fn report(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let masters = expansion.masters.iter().map(|master| {
+            let on = master.on(quote::quote!(self));
+            let long = master.long();
+            let count = expansion.clowns_of(master).count();
+            let ids = expansion
+                .clowns_of(master)
+                .map(|clown| clown.ident.to_string());
+            let longs = expansion.clowns_of(master).map(ClownField::long);
+            let idents = expansion.clowns_of(master).map(ClownField::place);
+            let values = expansion.clowns_of(master).map(|clown| clown.risky);
+            quote::quote! {
+                if #on {
+                    let flags: [(&str, &str, bool); #count] =
+                        [#((#ids, #longs, self.#idents == #values)),*];
+                    let (explicit, implicit): (::std::vec::Vec<_>, ::std::vec::Vec<_>) =
+                        flags.into_iter().partition(|(id, _, _)| {
+                            ::std::matches!(
+                                matches.value_source(id),
+                                ::std::option::Option::Some(
+                                    ::clap::parser::ValueSource::CommandLine
+                                        | ::clap::parser::ValueSource::EnvVariable
+                                )
+                            )
+                        });
+                    let forced: ::std::vec::Vec<&str> = implicit
+                        .into_iter()
+                        .filter_map(|(_, long, on)| on.then_some(long))
+                        .collect();
+                    let explicit: ::std::vec::Vec<&str> =
+                        explicit.into_iter().map(|(_, long, _)| long).collect();
+                    let list = |longs: ::std::vec::Vec<&str>| {
+                        if longs.is_empty() {
+                            "none".to_string()
+                        } else {
+                            longs.join(", ")
+                        }
+                    };
+                    lines.push(::std::format!("clowntown: {} forced on: {}", #long, list(forced)));
+                    lines.push(::std::format!(
+                        "clowntown: {} left alone, set explicitly: {}",
+                        #long,
+                        list(explicit)
+                    ));
+                }
+            }
+        });
+        let shadow = expansion.shadowed().map(|(shadowed, shadow_long, master)| {
+            let long = master.long();
+            let idents = expansion.clowns_of(master).map(ClownField::place);
+            let values = expansion.clowns_of(master).map(|clown| clown.risky);
+            let longs = expansion.clowns_of(master).map(ClownField::long);
+            quote::quote! {
+                if #shadowed {
+                    let flags: ::std::vec::Vec<&str> = [#((self.#idents != #values, #longs)),*]
+                        .into_iter()
+                        .filter_map(|(off, long): (bool, &str)| off.then_some(long))
+                        .collect();
+                    lines.push(::std::format!(
+                        "clowntown: {} would force on: {} (shadowed by {})",
+                        #long,
+                        if flags.is_empty() { "none".to_string() } else { flags.join(", ") },
+                        #shadow_long
+                    ));
+                }
+            }
+        });
+        quote::quote! {
+            /// For each master flag that is on, a line listing the clowntown flags it forced on,
+            /// and one listing those it left alone because they were set explicitly, on the
+            /// command line or from the environment, given the matches of this struct's command.
+            /// With the shadow flag, a line listing what the master would have forced on.
+            ///
+            /// The `clowntown_parse` constructors print it on stderr.
+            pub fn clowntown_report(
+                &self,
+                matches: &::clap::ArgMatches,
+            ) -> ::std::vec::Vec<::std::string::String> {
+                let mut lines = ::std::vec::Vec::new();
+                #(#masters)*
+                #shadow
+                lines
+            }
+        }
+    }
+
+    /// Emits `clowntown_select`, resolving glob patterns against the compile-time flag list.
+    fn select(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let indices = 0..expansion.clowns.len();
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let longs = expansion.clowns.iter().map(ClownField::long);
+        quote::quote! {
+            /// Turns on the clowntown flags matching the glob `patterns`, by field name
+            /// (`clowntown_db_*`) or long flag (`--clowntown-db-*`).
+            ///
+            /// A pattern matching no flag is an error, and then no flag is turned on.
+            #[allow(unused_variables)]
+            pub fn clowntown_select<S>(&mut self, patterns: &[S]) -> ::std::result::Result<(), ::clap::Error>
+            where
+                S: ::std::convert::AsRef<str>,
+            {
+                let patterns: ::std::vec::Vec<::std::string::String> = patterns
+                    .iter()
+                    .map(|pattern| ::std::string::ToString::to_string(pattern.as_ref()))
+                    .collect();
+                let selected = ::clowncopterize::__private::glob_select(
+                    &patterns,
+                    Self::CLOWNTOWN_FLAGS,
+                    &[#(#longs),*],
+                )
+                .map_err(|message| {
+                    ::clap::Error::raw(::clap::error::ErrorKind::InvalidValue, message)
+                })?;
+                #(
+                    if selected[#indices] {
+                        self.#idents = #values;
+                    }
+                )*
+                ::std::result::Result::Ok(())
+            }
+        }
+    }
+
+    /// Emits the `ValueEnum` the `mode` clowncopterizer takes.
+    fn mode_enum(&self, ast: &syn::ItemStruct, mode: &Ident) -> proc_macro2::TokenStream {
+        let vis = &ast.vis;
+        let doc = format!(
+            "What the `clowncopterizer` of `{}` does with its clowntown flags.",
+            ast.ident
+        );
+        quote::quote! {
+            #[doc = #doc]
+            #[derive(
+                ::std::clone::Clone,
+                ::std::marker::Copy,
+                ::std::fmt::Debug,
+                ::std::default::Default,
+                ::std::cmp::PartialEq,
+                ::std::cmp::Eq,
+                ::clap::ValueEnum
+            )]
+            #vis enum #mode {
+                /// Leaves them alone.
+                #[default]
+                Off,
+                /// Leaves them alone, but records the intent to turn them on.
+                DryRun,
+                /// Turns them all on.
+                All,
+            }
+
+            impl #mode {
+                /// Whether the clowntown flags are turned on.
+                #vis fn is_on(self) -> bool {
+                    self == Self::All
+                }
+
+                /// Whether the intent to turn the clowntown flags on is only recorded.
+                #vis fn is_dry_run(self) -> bool {
+                    self == Self::DryRun
+                }
+            }
+        }
+    }
+
+    /// Emits `apply_masters`, doing what the `default_value_if` wiring does for clap.
+    fn apply_masters(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let masters = expansion.masters.iter().map(|master| {
+            if master.shape == MasterShape::Select {
+                let ident = &master.ident;
+                let select = expansion.select_values(master, quote::quote!(&self.#ident));
+                let indices = 0..expansion.clowns_of(master).count();
+                let places = expansion.clowns_of(master).map(ClownField::place);
+                let values = expansion.clowns_of(master).map(|clown| clown.risky);
+                // invalid values set by hand select nothing, like clap would have refused them.
+                return quote::quote! {
+                    if let ::std::result::Result::Ok(selected) = #select {
+                        #(
+                            if selected[#indices] {
+                                self.#places = #values;
+                            }
+                        )*
+                    }
+                };
+            }
+            if master.shape == MasterShape::Groups {
+                let ident = &master.ident;
+                let clowns = expansion.clowns_of(master).map(|clown| {
+                    let clown_ident = clown.place();
+                    let value = clown.risky;
+                    let groups = clown.groups.as_slice();
+                    quote::quote! {
+                        if self.#ident.iter().any(|group| {
+                            ::std::matches!(group.as_str(), "all" #(| #groups)*)
+                        }) {
+                            self.#clown_ident = #value;
+                        }
+                    }
+                });
+                return quote::quote!(#(#clowns)*);
+            }
+            let on = master.on(quote::quote!(self));
+            let idents = expansion.clowns_of(master).map(ClownField::place);
+            let values = expansion.clowns_of(master).map(|clown| clown.risky);
+            quote::quote! {
+                if #on {
+                    #( self.#idents = #values; )*
+                }
+            }
+        });
+        let unclowncopterizer = expansion.unclowncopterizer.iter().map(|unclowncopterizer| {
+            let idents = expansion.clowns.iter().map(ClownField::place);
+            let values = expansion.clowns.iter().map(|clown| !clown.risky);
+            quote::quote! {
+                if self.#unclowncopterizer {
+                    #( self.#idents = #values; )*
+                }
+            }
+        });
+        quote::quote! {
+            /// Sets the clowntown flags of the master flags that are on, and forces them all off
+            /// if the unclowncopterizer is.
+            ///
+            /// Clap does this while parsing; this is for structs built some other way, like
+            /// deserialized ones.
+            pub fn apply_masters(&mut self) {
+                #(#masters)*
+                #(#unclowncopterizer)*
+            }
+        }
+    }
+
+    /// Emits `clowntown_source`, behind `clowntown_explain` and the provenance flag.
+    fn source(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let arms = expansion.clowns.iter().enumerate().map(|(index, clown)| {
+            let ident = clown.place();
+            let id = clown.ident.to_string();
+            let long = clown.long();
+            let default = clown.default;
+            let risky = clown.risky;
+            let env = clown.env.clone().unwrap_or_default();
+            let masters = clown.masters.iter().map(|master| {
+                let master_on = expansion.master_on(quote::quote!(self), master);
+                let master_long = self.master_long(master, expansion.casing);
+                quote::quote! {
+                    if #master_on && self.#ident == #risky {
+                        return (#risky, "master", #master_long);
+                    }
+                }
+            });
+            let unclowncopterizer = expansion.unclowncopterizer.iter().map(|unclowncopterizer| {
+                let unclowncopterizer_long =
+                    long_flag(&unclowncopterizer.to_string(), expansion.casing);
+                quote::quote! {
+                    if self.#unclowncopterizer && self.#ident != #risky {
+                        return (!#risky, "unclowncopterizer", #unclowncopterizer_long);
+                    }
+                }
+            });
+            quote::quote! {
+                #index => {
+                    match matches.value_source(#id) {
+                        ::std::option::Option::Some(::clap::parser::ValueSource::CommandLine) => {
+                            return (self.#ident, "command_line", #long);
+                        }
+                        ::std::option::Option::Some(::clap::parser::ValueSource::EnvVariable) => {
+                            return (self.#ident, "env", #env);
+                        }
+                        _ => {}
+                    }
+                    #(#masters)*
+                    #(#unclowncopterizer)*
+                    if self.#ident == #default {
+                        (self.#ident, "default", "")
+                    } else {
+                        (self.#ident, "post_parse", "")
+                    }
+                }
+            }
+        });
+        quote::quote! {
+            /// The value of the clowntown flag at `index`, where it comes from, and through what:
+            /// the long flag, environment variable or master.
+            #[allow(unreachable_code)]
+            fn clowntown_source(
+                &self,
+                matches: &::clap::ArgMatches,
+                index: usize,
+            ) -> (bool, &'static str, &'static str) {
+                match index {
+                    #(#arms)*
+                    _ => ::std::unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// Emits `clowntown_provenance`.
+    fn provenance(&self, name: &Ident) -> proc_macro2::TokenStream {
+        let name = json_str(&name.to_string());
+        quote::quote! {
+            /// A JSON document of every clowntown flag's value and where it comes from, given the
+            /// matches of this struct's command: `default`, `command_line`, `env`, `master`,
+            /// `unclowncopterizer` or `post_parse`, and the flag, variable or master it came
+            /// through, if any.
+            pub fn clowntown_provenance(&self, matches: &::clap::ArgMatches) -> ::std::string::String {
+                let flags: ::std::vec::Vec<_> = Self::clowntown_manifest()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, flag)| {
+                        let (value, source, through) = self.clowntown_source(matches, index);
+                        let through = if through.is_empty() {
+                            "null".to_string()
+                        } else {
+                            ::clowncopterize::__private::json_str(through)
+                        };
+                        ::std::format!(
+                            "{{\"field\":{},\"long\":{},\"value\":{},\"source\":\"{}\",\"through\":{}}}",
+                            ::clowncopterize::__private::json_str(flag.name),
+                            ::clowncopterize::__private::json_str(flag.long),
+                            value,
+                            source,
+                            through
+                        )
+                    })
+                    .collect();
+                ::std::format!("{{\"struct\":{},\"flags\":[{}]}}", #name, flags.join(","))
+            }
+        }
+    }
+
+    /// Emits `clowntown_explain`.
+    fn explain(&self) -> proc_macro2::TokenStream {
+        quote::quote! {
+            /// Why the clowntown flag `flag`, given as a field name or a long flag, has its value,
+            /// e.g. "enabled because --clowncopterize was passed", given the matches of this
+            /// struct's command.
+            pub fn clowntown_explain(
+                &self,
+                matches: &::clap::ArgMatches,
+                flag: &str,
+            ) -> ::std::result::Result<::std::string::String, ::std::string::String> {
+                let index = ::clowncopterize::__private::flag_index(flag, Self::CLOWNTOWN_FLAGS)?;
+                let (value, source, through) = self.clowntown_source(matches, index);
+                let state = if value { "enabled" } else { "disabled" };
+                ::std::result::Result::Ok(match (source, through) {
+                    ("command_line", long) => ::std::format!("{}, explicitly set via {}", state, long),
+                    ("env", "") => ::std::format!("{}, from the environment", state),
+                    ("env", env) => ::std::format!("{}, from env {}", state, env),
+                    ("master" | "unclowncopterizer", long) => {
+                        ::std::format!("{} because {} was passed", state, long)
+                    }
+                    ("default", _) => ::std::format!("{}, its default", state),
+                    _ => ::std::format!(
+                        "{} after parsing, e.g. by a flag file, `clowntown_select` or a resolver",
+                        state
+                    ),
+                })
+            }
+        }
+    }
+
+    /// Emits `clowntown_diff`.
+    fn diff(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let changes = expansion.clowns.iter().enumerate().map(|(index, clown)| {
+            let ident = clown.place();
+            let default = clown.default;
+            let risky = clown.risky;
+            // the masters set the risky value, the unclowncopterizer the safe one.
+            let mut causes: Vec<_> = clown
+                .masters
+                .iter()
+                .map(|master| {
+                    let master_on = expansion.master_on(quote::quote!(self), master);
+                    let long = self.master_long(master, expansion.casing);
+                    quote::quote!((#master_on && self.#ident == #risky, #long))
+                })
+                .collect();
+            if let Some(unclowncopterizer) = &expansion.unclowncopterizer {
+                let long = long_flag(&unclowncopterizer.to_string(), expansion.casing);
+                causes.push(quote::quote!((self.#unclowncopterizer && self.#ident != #risky, #long)));
+            }
+            let count = causes.len();
+            quote::quote! {
+                if self.#ident != #default {
+                    let causes: [(bool, &'static str); #count] = [#(#causes),*];
+                    changes.push(::clowncopterize::ClowntownChange {
+                        flag: Self::clowntown_manifest()[#index],
+                        default: #default,
+                        value: self.#ident,
+                        cause: causes
+                            .into_iter()
+                            .find_map(|(set, long)| set.then_some(::clowncopterize::ClowntownCause::Master(long)))
+                            .unwrap_or(::clowncopterize::ClowntownCause::Explicit),
+                    });
+                }
+            }
+        });
+        quote::quote! {
+            /// The clowntown flags whose value differs from their declared default, and why.
+            pub fn clowntown_diff(&self) -> ::std::vec::Vec<::clowncopterize::ClowntownChange> {
+                let mut changes = ::std::vec::Vec::new();
+                #(#changes)*
+                changes
+            }
+        }
+    }
+
+    /// Emits `apply_clowntown_to`.
+    fn apply_to(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let names = expansion.clowns.iter().map(|clown| clown.ident.to_string());
+        let nested_idents = expansion.nested.iter().map(|(ident, _)| ident);
+        quote::quote! {
+            /// Copies the values of the clowntown flags, nested structs' ones included, into the
+            /// flags of the same name of `other`, leaving the ones it doesn't have.
+            pub fn apply_clowntown_to(&self, other: &mut impl ::clowncopterize::Clowncopterized) {
+                #(
+                    ::clowncopterize::Clowncopterized::set_clowntown_flag(other, #names, self.#idents);
+                )*
+                #(
+                    self.#nested_idents.apply_clowntown_to(other);
+                )*
+            }
+        }
+    }
+
+    /// Emits `record_clowntown_metrics`, counting the masters and clowntown flags in use.
+    fn record_metrics(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let masters = expansion
+            .masters
+            .iter()
+            .map(|master| master.on(quote::quote!(self)));
+        let master_longs = expansion.masters.iter().map(Master::long);
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let names = expansion.clowns.iter().map(|clown| clown.ident.to_string());
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let shadow = expansion.shadowed().map(|(shadowed, _, master)| {
+            let long = master.long();
+            quote::quote! {
+                if #shadowed {
+                    ::clowncopterize::__private::metrics::counter!(
+                        "clowntown.master_shadowed",
+                        "master" => #long
+                    )
+                    .increment(1);
+                }
+            }
+        });
+        quote::quote! {
+            /// Increments the `clowntown.master_used` counter for each master flag that is on,
+            /// `clowntown.master_shadowed` when the shadow flag is, and `clowntown.flag_enabled`
+            /// for each clowntown flag that is, through the `metrics` facade.
+            pub fn record_clowntown_metrics(&self) {
+                #shadow
+                #(
+                    if #masters {
+                        ::clowncopterize::__private::metrics::counter!(
+                            "clowntown.master_used",
+                            "master" => #master_longs
+                        )
+                        .increment(1);
+                    }
+                )*
+                #(
+                    if self.#idents == #values {
+                        ::clowncopterize::__private::metrics::counter!(
+                            "clowntown.flag_enabled",
+                            "flag" => #names
+                        )
+                        .increment(1);
+                    }
+                )*
+            }
+        }
+    }
+
+    /// Emits `record_clowntown_to`, reporting the masters and clowntown flags in use to a sink.
+    fn record_sink(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let masters = expansion
+            .masters
+            .iter()
+            .map(|master| master.on(quote::quote!(self)));
+        let master_longs = expansion.masters.iter().map(Master::long);
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let indices = 0..expansion.clowns.len();
+        let shadow = expansion.shadowed().map(|(shadowed, _, master)| {
+            let long = master.long();
+            quote::quote! {
+                if #shadowed {
+                    sink.master_shadowed(#long);
+                }
+            }
+        });
+        quote::quote! {
+            /// Tells `sink` about each master flag that is on, or shadowed, and each clowntown
+            /// flag that is on.
+            pub fn record_clowntown_to(&self, sink: &impl ::clowncopterize::ClowntownSink) {
+                #shadow
+                #(
+                    if #masters {
+                        sink.master_used(#master_longs);
+                    }
+                )*
+                #(
+                    if self.#idents == #values {
+                        sink.flag_enabled(&Self::clowntown_manifest()[#indices]);
+                    }
+                )*
+            }
+        }
+    }
+
+    /// Emits `register_clowntown_gauges` and `update_clowntown_gauges`.
+    fn prometheus_gauges(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let names = expansion.clowns.iter().map(|clown| clown.ident.to_string());
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        quote::quote! {
+            /// Registers the `clowntown_flag_enabled` gauge with `registry`, labeled by `flag`,
+            /// set to 1 for the clowntown flags that are on and 0 for the others.
+            ///
+            /// Call `update_clowntown_gauges` with the returned gauge when the flags change.
+            pub fn register_clowntown_gauges(
+                &self,
+                registry: &::clowncopterize::__private::prometheus::Registry,
+            ) -> ::clowncopterize::__private::prometheus::Result<
+                ::clowncopterize::__private::prometheus::IntGaugeVec,
+            > {
+                let gauges = ::clowncopterize::__private::prometheus::IntGaugeVec::new(
+                    ::clowncopterize::__private::prometheus::Opts::new(
+                        "clowntown_flag_enabled",
+                        "Whether a risky clowntown flag is enabled",
+                    ),
+                    &["flag"],
+                )?;
+                registry.register(::std::boxed::Box::new(gauges.clone()))?;
+                self.update_clowntown_gauges(&gauges);
+                ::std::result::Result::Ok(gauges)
+            }
+
+            /// Sets the gauge returned by `register_clowntown_gauges` to the current flags.
+            pub fn update_clowntown_gauges(
+                &self,
+                gauges: &::clowncopterize::__private::prometheus::IntGaugeVec,
+            ) {
+                #(
+                    gauges
+                        .with_label_values(&[#names])
+                        .set((self.#idents == #values) as i64);
+                )*
+            }
+        }
+    }
+
+    /// Emits `audit_clowntown`.
+    fn syslog_audit(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let masters = expansion
+            .masters
+            .iter()
+            .map(|master| master.on(quote::quote!(self)));
+        let master_longs = expansion.masters.iter().map(Master::long);
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let longs = expansion.clowns.iter().map(ClownField::long);
+        quote::quote! {
+            /// When a master flag is on, writes an audit record to the system log with the user,
+            /// a hash of `argv`, the masters and the enabled clowntown flags.
+            pub fn audit_clowntown(&self, argv: &[::std::ffi::OsString]) {
+                let masters: ::std::vec::Vec<&str> = [#((#masters, #master_longs)),*]
+                    .into_iter()
+                    .filter_map(|(on, long): (bool, &str)| on.then_some(long))
+                    .collect();
+                if masters.is_empty() {
+                    return;
+                }
+                let enabled: ::std::vec::Vec<&str> = [#((self.#idents == #values, #longs)),*]
+                    .into_iter()
+                    .filter_map(|(on, long): (bool, &str)| on.then_some(long))
+                    .collect();
+                ::clowncopterize::__private::syslog_audit(argv, &masters, &enabled);
+            }
+        }
+    }
+
+    /// Emits `record_clowntown_sentry`.
+    fn sentry_context(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let masters = expansion
+            .masters
+            .iter()
+            .map(|master| master.on(quote::quote!(self)));
+        let master_longs = expansion.masters.iter().map(Master::long);
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let longs = expansion.clowns.iter().map(ClownField::long);
+        quote::quote! {
+            /// When a master flag is on, sets a `clowntown` Sentry context listing the masters and
+            /// the enabled clowntown flags, and leaves a breadcrumb, so crash reports from risky
+            /// runs carry their configuration.
+            pub fn record_clowntown_sentry(&self) {
+                use ::clowncopterize::__private::sentry_core::protocol::{Context, Map, Value};
+
+                let masters: ::std::vec::Vec<&str> = [#((#masters, #master_longs)),*]
+                    .into_iter()
+                    .filter_map(|(on, long): (bool, &str)| on.then_some(long))
+                    .collect();
+                if masters.is_empty() {
+                    return;
+                }
+                let enabled: ::std::vec::Vec<&str> = [#((self.#idents == #values, #longs)),*]
+                    .into_iter()
+                    .filter_map(|(on, long): (bool, &str)| on.then_some(long))
+                    .collect();
+                let message = ::std::format!(
+                    "{} enabled {}",
+                    masters.join(", "),
+                    enabled.join(", ")
+                );
+                let mut context = Map::new();
+                context.insert("masters".to_string(), Value::from(masters));
+                context.insert("enabled_flags".to_string(), Value::from(enabled));
+                ::clowncopterize::__private::sentry_core::configure_scope(|scope| {
+                    scope.set_context("clowntown", Context::Other(context));
+                });
+                ::clowncopterize::__private::sentry_core::add_breadcrumb(
+                    ::clowncopterize::__private::sentry_core::protocol::Breadcrumb {
+                        category: ::std::option::Option::Some("clowntown".to_string()),
+                        level: ::clowncopterize::__private::sentry_core::protocol::Level::Warning,
+                        message: ::std::option::Option::Some(message),
+                        ..::std::default::Default::default()
+                    },
+                );
+            }
+        }
+    }
+
+    /// Emits `clowntown_otel_attributes` and `record_clowntown_span`.
+    fn otel_attributes(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let keys = expansion
+            .clowns
+            .iter()
+            .map(|clown| format!("clowntown.{}", clown.ident));
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        quote::quote! {
+            /// The clowntown flags as OpenTelemetry attributes, `clowntown.<field>` set to whether
+            /// the flag is on, e.g. for the resource of the tracer or meter provider.
+            pub fn clowntown_otel_attributes(
+                &self,
+            ) -> ::std::vec::Vec<::clowncopterize::__private::opentelemetry::KeyValue> {
+                ::std::vec![
+                    #(
+                        ::clowncopterize::__private::opentelemetry::KeyValue::new(
+                            #keys,
+                            self.#idents == #values,
+                        )
+                    ),*
+                ]
+            }
+
+            /// Sets the `clowntown_otel_attributes` on `span`.
+            pub fn record_clowntown_span<S>(&self, span: &mut S)
+            where
+                S: ::clowncopterize::__private::opentelemetry::trace::Span + ?::std::marker::Sized,
+            {
+                span.set_attributes(self.clowntown_otel_attributes());
+            }
+        }
+    }
+
+    /// Emits `apply_resolver` and `apply_resolver_async`.
+    fn apply_resolver(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let decide = |resolve: proc_macro2::TokenStream, suffix: proc_macro2::TokenStream| {
+            let clowns = expansion.clowns.iter().map(|clown| {
+                let ident = clown.place();
+                let name = clown.ident.to_string();
+                let risky = clown.risky;
+                quote::quote! {
+                    match #resolve(resolver, #name, self.#ident == #risky) #suffix {
+                        ::clowncopterize::ClowntownDecision::Keep => {}
+                        ::clowncopterize::ClowntownDecision::Veto => self.#ident = !#risky,
+                        ::clowncopterize::ClowntownDecision::Force => self.#ident = #risky,
+                    }
+                }
+            });
+            quote::quote!(#(#clowns)*)
+        };
+        let sync = decide(
+            quote::quote!(::clowncopterize::ClowntownResolver::resolve),
+            proc_macro2::TokenStream::new(),
+        );
+        let async_ = decide(
+            quote::quote!(::clowncopterize::AsyncClowntownResolver::resolve),
+            quote::quote!(.await),
+        );
+        let validate = if self.invariants.is_empty() {
+            quote::quote!(::std::result::Result::Ok(()))
+        } else {
+            quote::quote!(self.validate())
+        };
+        quote::quote! {
+            /// Lets `resolver` veto or force each clowntown flag, then checks the invariants.
+            pub fn apply_resolver<R>(&mut self, resolver: &R) -> ::std::result::Result<(), ::clap::Error>
+            where
+                R: ::clowncopterize::ClowntownResolver + ?::std::marker::Sized,
+            {
+                #sync
+                #validate
+            }
+
+            /// Lets `resolver` veto or force each clowntown flag, then checks the invariants.
+            pub async fn apply_resolver_async<R>(
+                &mut self,
+                resolver: &R,
+            ) -> ::std::result::Result<(), ::clap::Error>
+            where
+                R: ::clowncopterize::AsyncClowntownResolver + ?::std::marker::Sized,
+            {
+                #async_
+                #validate
+            }
+        }
+    }
+
+    /// Emits `reload_clowntown`, and `watch_clowntown` with the `notify` feature.
+    fn reload_clowntown(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let idents: Vec<_> = expansion.clowns.iter().map(ClownField::place).collect();
+        let indices = 0..expansion.clowns.len();
+        let restore_indices = 0..expansion.clowns.len();
+        let validate = if self.invariants.is_empty() {
+            proc_macro2::TokenStream::new()
+        } else {
+            quote::quote! {
+                if let ::std::result::Result::Err(err) = self.validate() {
+                    #( self.#idents = previous[#restore_indices]; )*
+                    return ::std::result::Result::Err(::std::io::Error::new(
+                        ::std::io::ErrorKind::InvalidData,
+                        ::std::format!("{}: {}", path.display(), err.to_string().trim_end()),
+                    ));
+                }
+            }
+        };
+        let watch = if cfg!(feature = "notify") {
+            quote::quote! {
+                /// Reloads the clowntown flags of `cli` from the flag file at `path` every time it
+                /// changes, until the returned watcher is dropped.
+                ///
+                /// Failed reloads are reported on stderr and leave the flags untouched.
+                pub fn watch_clowntown(
+                    cli: ::std::sync::Arc<::std::sync::Mutex<Self>>,
+                    path: impl ::std::convert::Into<::std::path::PathBuf>,
+                ) -> ::clowncopterize::__private::notify::Result<
+                    ::clowncopterize::__private::notify::RecommendedWatcher,
+                >
+                where
+                    Self: ::std::marker::Send + 'static,
+                {
+                    let path = path.into();
+                    ::clowncopterize::__private::watch(path.clone(), move || {
+                        let mut cli = cli.lock().unwrap_or_else(::std::sync::PoisonError::into_inner);
+                        if let ::std::result::Result::Err(err) = cli.reload_clowntown(&path) {
+                            ::std::eprintln!("warning: failed to reload clowntown flags: {}", err);
+                        }
+                    })
+                }
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        quote::quote! {
+            /// Updates the clowntown flags from the flag file at `path`.
+            ///
+            /// Each line is `flag = true`, `flag = false` or a bare `flag` meaning true, by field
+            /// name or long flag, with `#` comments. Flags the file doesn't mention keep their
+            /// value. Either every flag is updated or, if the file is invalid, none is.
+            pub fn reload_clowntown(&mut self, path: &::std::path::Path) -> ::std::io::Result<()> {
+                let contents = ::std::fs::read_to_string(path)?;
+                let updates =
+                    ::clowncopterize::__private::parse_flag_file(&contents, Self::CLOWNTOWN_FLAGS)
+                        .map_err(|message| {
+                            ::std::io::Error::new(
+                                ::std::io::ErrorKind::InvalidData,
+                                ::std::format!("{}: {}", path.display(), message),
+                            )
+                        })?;
+                #[allow(unused_variables)]
+                let previous: &[bool] = &[#(self.#idents),*];
+                for (index, value) in updates {
+                    match index {
+                        #( #indices => self.#idents = value, )*
+                        _ => ::std::unreachable!(),
+                    }
+                }
+                #validate
+                ::std::result::Result::Ok(())
+            }
+
+            #watch
+        }
+    }
+
+    /// Emits `merge_from_config`, applying `ClowntownOverrides` below the command line.
+    fn merge_from_config(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let unclowncopterizer = expansion.unclowncopterizer.iter();
+        let arms = expansion.clowns.iter().enumerate().map(|(index, clown)| {
+            let ident = clown.place();
+            let default = clown.default;
+            let masters = clown
+                .masters
+                .iter()
+                .map(|master| expansion.master_on(quote::quote!(self), master));
+            quote::quote! {
+                #index => {
+                    if !(false #(|| #masters)*) && self.#ident == #default {
+                        self.#ident = value;
+                    }
+                }
+            }
+        });
+        quote::quote! {
+            /// Sets the clowntown flags from `config`, e.g. read from a configuration file.
+            ///
+            /// The command line takes precedence: flags set away from their default, or by a
+            /// master flag, are left alone. Unknown flags are an error.
+            pub fn merge_from_config<C>(&mut self, config: C) -> ::std::result::Result<(), ::clap::Error>
+            where
+                C: ::std::convert::Into<::clowncopterize::ClowntownOverrides>,
+            {
+                let config = config.into();
+                let mut indices = ::std::vec::Vec::new();
+                for (flag, value) in config.iter() {
+                    let index = ::clowncopterize::__private::flag_index(flag, Self::CLOWNTOWN_FLAGS)
+                        .map_err(|message| {
+                            ::clap::Error::raw(::clap::error::ErrorKind::UnknownArgument, message)
+                        })?;
+                    indices.push((index, value));
+                }
+                if false #(|| self.#unclowncopterizer)* {
+                    return ::std::result::Result::Ok(());
+                }
+                for (index, value) in indices {
+                    match index {
+                        #(#arms)*
+                        _ => ::std::unreachable!(),
+                    }
+                }
+                ::std::result::Result::Ok(())
+            }
+        }
+    }
+
+    /// Emits `validate`, checking the declared invariants.
+    fn validate(&self) -> proc_macro2::TokenStream {
+        let checks = self.invariants.iter().map(|(source, expr)| {
+            let expr = expr.to_tokens();
+            let message = format!("clowntown invariant violated: {}", source.value());
+            quote::quote! {
+                if !#expr {
+                    return ::std::result::Result::Err(::clap::Error::raw(
+                        ::clap::error::ErrorKind::ArgumentConflict,
+                        #message,
+                    ));
+                }
+            }
+        });
+        quote::quote! {
+            /// Checks the clowntown invariants declared on the struct.
+            pub fn validate(&self) -> ::std::result::Result<(), ::clap::Error> {
+                #(#checks)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    }
+
+    /// Emits the `clowntown_parse` family of constructors.
+    ///
+    /// They mirror their `clap::Parser` counterparts, and then run the checks and fixups clap
+    /// can't express on its own, from `clowntown_post_parse`.
+    fn parse_wrapper(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let activation = self.subcommand().filter(|_| {
+            expansion
+                .masters
+                .iter()
+                .any(|master| master.ident == self.clowncopterizer && master.injected)
+        });
+        let activate = activation.as_ref().map(|name| {
+            let master = self.master_long(&self.clowncopterizer, expansion.casing);
+            quote::quote! {
+                let args = ::clowncopterize::__private::activation_args(args, #name, #master);
+            }
+        });
+        // the audit record and the clowntown help need the arguments, which clap consumes.
+        let collect_args = if cfg!(feature = "syslog")
+            || expansion.clowntown_help.is_some()
+            || expansion.has_subcommand
+            || activation.is_some()
+        {
+            quote::quote! {
+                let args: ::std::vec::Vec<::std::ffi::OsString> =
+                    itr.into_iter().map(::std::convert::Into::into).collect();
+                #activate
+                let itr = args.clone();
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let audit = if cfg!(feature = "syslog") {
+            quote::quote! {
+                cli.audit_clowntown(&args);
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        // like `--help`, checked before clap would complain about missing arguments.
+        let clowntown_help = expansion.clowntown_help.as_ref().map(|clowntown_help| {
+            let long = long_flag(&clowntown_help.to_string(), expansion.casing);
+            let display = display_error(quote::quote!(
+                Self::clowntown_help_command().render_long_help()
+            ));
+            quote::quote! {
+                if args.iter().skip(1).take_while(|arg| *arg != "--").any(|arg| arg == #long) {
+                    return ::std::result::Result::Err(#display);
+                }
+            }
+        });
+        let externals = self.externals.iter().map(|(prefix, path)| {
+            let prefix = prefix.as_ref().unwrap_or(&self.prefixes[0]);
+            // like the declared flags: the unclowncopterizer first, so that it wins.
+            let mut wiring: Vec<_> = expansion
+                .unclowncopterizer
+                .iter()
+                .flat_map(|un| {
+                    let un = un.to_string();
+                    self.master_predicates(&un, &[], false)
+                        .into_iter()
+                        .map(move |(predicate, on)| {
+                            let value = (!on).to_string();
+                            quote::quote!(.default_value_if(#un, #predicate, #value))
+                        })
+                })
+                .collect();
+            let master = &self.clowncopterizer;
+            let declared = expansion
+                .masters
+                .iter()
+                .any(|known| known.ident == master && !known.injected);
+            wiring.extend(self.master_predicates(master, &[], declared).into_iter().map(
+                |(predicate, on)| {
+                    let value = on.to_string();
+                    quote::quote!(.default_value_if(#master, #predicate, #value))
+                },
+            ));
+            quote::quote! {
+                let external = <#path as ::clap::Args>::augment_args(::clap::Command::new("external"));
+                for arg in external.get_arguments() {
+                    let id = arg.get_id().as_str();
+                    if id.starts_with(#prefix)
+                        && ::std::matches!(arg.get_action(), ::clap::ArgAction::SetTrue)
+                        && command.get_arguments().any(|arg| arg.get_id() == id)
+                    {
+                        command = command.mut_arg(id, |arg| arg #(#wiring)*);
+                    }
+                }
+            }
+        });
+        // plain clap only takes the exact names, resolved in the post-parse steps.
+        let selects = expansion
+            .masters
+            .iter()
+            .filter(|master| master.shape == MasterShape::Select)
+            .map(|master| {
+                let id = master.ident.to_string();
+                let check = expansion.select_values(
+                    master,
+                    quote::quote!(::std::slice::from_ref(&value)),
+                );
+                let names = expansion.clowns_of(master).map(|clown| &clown.long);
+                quote::quote! {
+                    {
+                        // globs can't be possible values, but the names make better
+                        // suggestions.
+                        #[derive(Clone)]
+                        struct ClowntownSelect;
+
+                        impl ::clap::builder::TypedValueParser for ClowntownSelect {
+                            type Value = ::std::string::String;
+
+                            fn parse_ref(
+                                &self,
+                                _cmd: &::clap::Command,
+                                _arg: ::std::option::Option<&::clap::Arg>,
+                                value: &::std::ffi::OsStr,
+                            ) -> ::std::result::Result<::std::string::String, ::clap::Error> {
+                                let value = value.to_string_lossy().into_owned();
+                                #check.map(|_| value).map_err(|message| {
+                                    ::clap::Error::raw(
+                                        ::clap::error::ErrorKind::InvalidValue,
+                                        ::std::format!("{}\n", message),
+                                    )
+                                })
+                            }
+
+                            fn possible_values(
+                                &self,
+                            ) -> ::std::option::Option<
+                                ::std::boxed::Box<dyn ::std::iter::Iterator<Item = ::clap::builder::PossibleValue> + '_>,
+                            > {
+                                ::std::option::Option::Some(::std::boxed::Box::new(
+                                    ["all", "none" #(, #names)*]
+                                        .into_iter()
+                                        .map(::clap::builder::PossibleValue::new),
+                                ))
+                            }
+                        }
+
+                        command = command.mut_arg(#id, |arg| arg.value_parser(ClowntownSelect));
+                    }
+                }
+            });
+        let (reparse, subcommand_args) = if expansion.has_subcommand {
+            self.subcommand_args(expansion)
+        } else {
+            Default::default()
+        };
+        let activation_subcommand = activation.map(|name| {
+            let about = format!(
+                "Runs with {} on, as `{} -- <ARGS>...`",
+                self.master_long(&self.clowncopterizer, expansion.casing),
+                name
+            );
+            quote::quote! {
+                command = command.subcommand(
+                    ::clap::Command::new(#name).about(#about).arg(
+                        ::clap::Arg::new("args")
+                            .value_name("ARGS")
+                            .num_args(0..)
+                            .trailing_var_arg(true)
+                            .allow_hyphen_values(true),
+                    ),
+                );
+            }
+        });
+        quote::quote! {
+            #subcommand_args
+
+            /// The clap command, plus the wiring of the `external` flags, the glob patterns of
+            /// `select` and the activation subcommand if any.
+            pub fn clowntown_command() -> ::clap::Command {
+                #[allow(unused_mut)]
+                let mut command = <Self as ::clap::CommandFactory>::command();
+                #(#externals)*
+                #(#selects)*
+                #activation_subcommand
+                command
+            }
+
+            /// Parse from `std::env::args_os()`, exit on error.
+            ///
+            /// Same as [`clap::Parser::parse`], plus the clowncopterize post-parse steps.
+            pub fn clowntown_parse() -> Self {
+                Self::clowntown_try_parse().unwrap_or_else(|err| err.exit())
+            }
+
+            /// Parse from `std::env::args_os()`, return Err on error.
+            ///
+            /// Same as [`clap::Parser::try_parse`], plus the clowncopterize post-parse steps.
+            pub fn clowntown_try_parse() -> ::std::result::Result<Self, ::clap::Error> {
+                Self::clowntown_try_parse_from(::std::env::args_os())
+            }
+
+            /// Parse from iterator, exit on error.
+            ///
+            /// Same as [`clap::Parser::parse_from`], plus the clowncopterize post-parse steps.
+            pub fn clowntown_parse_from<I, T>(itr: I) -> Self
+            where
+                I: ::std::iter::IntoIterator<Item = T>,
+                T: ::std::convert::Into<::std::ffi::OsString> + ::std::clone::Clone,
+            {
+                Self::clowntown_try_parse_from(itr).unwrap_or_else(|err| err.exit())
+            }
+
+            /// Parse from iterator, return Err on error.
+            ///
+            /// Same as [`clap::Parser::try_parse_from`], plus the clowncopterize post-parse steps.
+            pub fn clowntown_try_parse_from<I, T>(itr: I) -> ::std::result::Result<Self, ::clap::Error>
+            where
+                I: ::std::iter::IntoIterator<Item = T>,
+                T: ::std::convert::Into<::std::ffi::OsString> + ::std::clone::Clone,
+            {
+                #collect_args
+                #clowntown_help
+                let mut command = Self::clowntown_command();
+                #[allow(unused_mut)]
+                let mut matches = command.try_get_matches_from_mut(itr)?;
+                #reparse
+                let mut cli = <Self as ::clap::FromArgMatches>::from_arg_matches(&matches)
+                    .map_err(|err| err.format(&mut command))?;
+                cli.clowntown_post_parse(&matches)
+                    .map_err(|err| err.format(&mut command))?;
+                #audit
+                ::std::result::Result::Ok(cli)
+            }
+
+            /// Parse from `std::env::args_os()`, then let `resolver` veto or force the clowntown
+            /// flags, exit on error.
+            pub fn clowntown_parse_with<R>(resolver: &R) -> Self
+            where
+                R: ::clowncopterize::ClowntownResolver + ?::std::marker::Sized,
+            {
+                Self::clowntown_try_parse_from_with(::std::env::args_os(), resolver)
+                    .unwrap_or_else(|err| err.exit())
+            }
+
+            /// Parse from iterator, then let `resolver` veto or force the clowntown flags, return
+            /// Err on error.
+            pub fn clowntown_try_parse_from_with<I, T, R>(
+                itr: I,
+                resolver: &R,
+            ) -> ::std::result::Result<Self, ::clap::Error>
+            where
+                I: ::std::iter::IntoIterator<Item = T>,
+                T: ::std::convert::Into<::std::ffi::OsString> + ::std::clone::Clone,
+                R: ::clowncopterize::ClowntownResolver + ?::std::marker::Sized,
+            {
+                let mut cli = Self::clowntown_try_parse_from(itr)?;
+                cli.apply_resolver(resolver)
+                    .map_err(|err| err.format(&mut Self::clowntown_command()))?;
+                ::std::result::Result::Ok(cli)
+            }
+        }
+    }
+
+    /// Emits the parse wrapper's second pass, with the global masters given before a subcommand
+    /// passed to it too, and `clowntown_subcommand_args` deciding on it.
+    fn subcommand_args(
+        &self,
+        expansion: &Expansion,
+    ) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        // masters taking a value aren't repeated, they must be given after the subcommand.
+        let masters: Vec<_> = expansion
+            .masters
+            .iter()
+            .filter(|master| !self.master_takes_value(&master.ident.to_string()))
+            .collect();
+        let ids = masters.iter().map(|master| master.ident.to_string());
+        let longs = masters.iter().map(|master| master.long());
+        let count = masters.len();
+        let reparse = quote::quote! {
+            if let ::std::option::Option::Some(args) =
+                Self::clowntown_subcommand_args(&command, &matches, &args)
+            {
+                matches = command.try_get_matches_from_mut(args)?;
+            }
+        };
+        let subcommand_args = quote::quote! {
+            /// The arguments with the global masters given before the subcommand repeated after
+            /// it, if any.
+            ///
+            /// clap resolves the defaults of a subcommand before propagating the global flags into
+            /// it, so `prog --clowncopterize run` wouldn't reach the flags of `run` otherwise.
+            fn clowntown_subcommand_args(
+                command: &::clap::Command,
+                matches: &::clap::ArgMatches,
+                args: &[::std::ffi::OsString],
+            ) -> ::std::option::Option<::std::vec::Vec<::std::ffi::OsString>> {
+                let (name, _) = matches.subcommand()?;
+                let subcommand = command.find_subcommand(name)?;
+                let names: ::std::vec::Vec<&str> = ::std::iter::once(subcommand.get_name())
+                    .chain(subcommand.get_all_aliases())
+                    .collect();
+                let value_flags: ::std::vec::Vec<::std::string::String> = command
+                    .get_arguments()
+                    .filter(|arg| arg.get_action().takes_values())
+                    .flat_map(|arg| {
+                        arg.get_long()
+                            .map(|long| ::std::format!("--{}", long))
+                            .into_iter()
+                            .chain(arg.get_short().map(|short| ::std::format!("-{}", short)))
+                    })
+                    .collect();
+                let index = ::clowncopterize::__private::subcommand_index(args, &names, &value_flags)?;
+                let candidates: [(&str, &str); #count] = [#((#ids, #longs)),*];
+                let masters: ::std::vec::Vec<&str> = candidates
+                    .into_iter()
+                    .filter(|(id, long)| {
+                        command
+                            .get_arguments()
+                            .any(|arg| arg.get_id() == *id && arg.is_global_set())
+                            && args[1..index].iter().any(|arg| arg == *long)
+                            && !args[index..].iter().any(|arg| arg == *long)
+                    })
+                    .map(|(_, long)| long)
+                    .collect();
+                if masters.is_empty() {
+                    return ::std::option::Option::None;
+                }
+                let mut args = args.to_vec();
+                for master in masters {
+                    args.insert(index + 1, master.into());
+                }
+                ::std::option::Option::Some(args)
+            }
+        };
+        (reparse, subcommand_args)
+    }
+
+    /// Emits `clowntown_post_parse`, which the parse wrapper calls.
+    fn post_parse(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let steps = self.post_parse_steps(expansion);
+        quote::quote! {
+            /// Checks and fixups applied after clap is done parsing, given the matches of this
+            /// struct's command.
+            ///
+            /// The `clowntown_parse` constructors call it. Call it for structs parsed as part of
+            /// another command, e.g. the applets of a multicall binary.
+            #[allow(unused_variables)]
+            pub fn clowntown_post_parse(
+                &mut self,
+                matches: &::clap::ArgMatches,
+            ) -> ::std::result::Result<(), ::clap::Error> {
+                #(#steps)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    }
+
+    /// Emits `clowntown_completions`, when the struct injects a completions flag.
+    fn clowntown_completions(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        if expansion.completions.is_none() {
+            return proc_macro2::TokenStream::new();
+        }
+        quote::quote! {
+            /// Writes the completions for `shell` to `out`.
+            pub fn clowntown_completions(
+                shell: ::clowncopterize::__private::clap_complete::Shell,
+                out: &mut dyn ::std::io::Write,
+            ) {
+                let mut command = Self::clowntown_command();
+                let name = command.get_name().to_string();
+                ::clowncopterize::__private::clap_complete::generate(shell, &mut command, name, out);
+            }
+        }
+    }
+
+    /// Emits `clowntown_help_command`, when the struct injects a clowntown help flag.
+    fn clowntown_help_command(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        if expansion.clowntown_help.is_none() {
+            return proc_macro2::TokenStream::new();
+        }
+        let shown = expansion
+            .clowns
+            .iter()
+            .map(|clown| &clown.ident)
+            .chain(expansion.masters.iter().map(|master| &master.ident))
+            .chain(&expansion.unclowncopterizer)
+            .map(Ident::to_string);
+        quote::quote! {
+            /// The command, with everything but the clowntown flags and their masters hidden.
+            pub fn clowntown_help_command() -> ::clap::Command {
+                let shown: &[&str] = &[#(#shown),*];
+                let mut command = Self::clowntown_command()
+                    .disable_help_flag(true)
+                    .disable_version_flag(true)
+                    .disable_help_subcommand(true)
+                    .mut_subcommands(|subcommand| subcommand.hide(true));
+                let hidden: ::std::vec::Vec<_> = command
+                    .get_arguments()
+                    .map(|arg| arg.get_id().clone())
+                    .filter(|id| !shown.contains(&id.as_str()))
+                    .collect();
+                for id in hidden {
+                    command = command.mut_arg(id, |arg| arg.hide(true));
+                }
+                command
+            }
+        }
+    }
+
+    /// Emits `with_all_clowntown`, a test constructor turning every clowntown flag on.
+    fn with_all_clowntown(&self, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        quote::quote! {
+            /// Parse from iterator, then turn every clowntown flag on, panic on error.
+            ///
+            /// Meant for tests exercising full clowntown mode: like [`clap::Parser::parse_from`],
+            /// the first item is the binary name.
+            pub fn with_all_clowntown<I, T>(itr: I) -> Self
+            where
+                I: ::std::iter::IntoIterator<Item = T>,
+                T: ::std::convert::Into<::std::ffi::OsString> + ::std::clone::Clone,
+            {
+                let mut cli = Self::clowntown_try_parse_from(itr)
+                    .unwrap_or_else(|err| ::std::panic!("{}", err));
+                #( cli.#idents = #values; )*
+                cli
+            }
+        }
+    }
+
+    /// Emits the builder returned by `clowntown_builder`.
+    fn builder(&self, ast: &syn::ItemStruct, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let vis = &ast.vis;
+        let builder = builder_ident(name);
+        let generics = &ast.generics;
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        let indices = 0..expansion.clowns.len();
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let values = expansion.clowns.iter().map(|clown| clown.risky);
+        let longs: Vec<_> = expansion.clowns.iter().map(ClownField::long).collect();
+        let count = expansion.clowns.len();
+        let doc = format!(
+            "Builds a [`{}`] with a chosen set of clowntown flags.",
+            name
+        );
+        quote::quote! {
+            #[doc = #doc]
+            #vis struct #builder #generics #where_clause {
+                args: ::std::vec::Vec<::std::ffi::OsString>,
+                enabled: [bool; #count],
+                marker: ::std::marker::PhantomData<fn() -> #name #ty_generics>,
+            }
+
+            impl #impl_generics #builder #ty_generics #where_clause {
+                fn new() -> Self {
+                    Self {
+                        args: ::std::vec::Vec::new(),
+                        enabled: [false; #count],
+                        marker: ::std::marker::PhantomData,
+                    }
+                }
+
+                /// Turns a clowntown flag on, by field name or long flag.
+                ///
+                /// # Panics
+                ///
+                /// If `flag` isn't one of the clowntown flags.
+                pub fn enable(mut self, flag: &str) -> Self {
+                    let index = ::clowncopterize::__private::flag_index(
+                        flag,
+                        <#name #ty_generics>::CLOWNTOWN_FLAGS,
+                    )
+                    .unwrap_or_else(|message| ::std::panic!("{}", message));
+                    self.enabled[index] = true;
+                    self
+                }
+
+                /// Turns every clowntown flag on.
+                pub fn enable_all(mut self) -> Self {
+                    self.enabled = [true; #count];
+                    self
+                }
+
+                /// Adds a non-clowntown argument, passed along when building the struct.
+                pub fn arg(mut self, arg: impl ::std::convert::Into<::std::ffi::OsString>) -> Self {
+                    self.args.push(arg.into());
+                    self
+                }
+
+                /// The long flags of the enabled clowntown flags, as command line arguments.
+                pub fn build_args(&self) -> ::std::vec::Vec<::std::string::String> {
+                    let longs: [&str; #count] = [#(#longs),*];
+                    longs
+                        .iter()
+                        .zip(self.enabled)
+                        .filter_map(|(long, enabled)| enabled.then(|| long.to_string()))
+                        .collect()
+                }
+
+                /// Parses the added arguments, then turns the enabled clowntown flags on.
+                pub fn build(self) -> ::std::result::Result<#name #ty_generics, ::clap::Error> {
+                    let command = <#name #ty_generics as ::clap::CommandFactory>::command();
+                    let bin = ::std::ffi::OsString::from(command.get_name());
+                    let mut cli =
+                        <#name #ty_generics>::clowntown_try_parse_from(::std::iter::once(bin).chain(self.args))?;
+                    #(
+                        if self.enabled[#indices] {
+                            cli.#idents = #values;
+                        }
+                    )*
+                    ::std::result::Result::Ok(cli)
+                }
+            }
+        }
+    }
+
+    /// Emits a figment `Provider` impl exposing the clowntown flags set from the command line.
+    ///
+    /// Flags left at their default are omitted so lower layers can still set them, but a master
+    /// flag, or the unclowncopterizer, provides every flag it sets.
+    fn figment_provider(
+        &self,
+        ast: &syn::ItemStruct,
+        expansion: &Expansion,
+    ) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        let unclowncopterizer = expansion.unclowncopterizer.iter();
+        let entries = expansion.clowns.iter().map(|clown| {
+            let ident = clown.place();
+            let key = clown.ident.to_string();
+            let default = clown.default;
+            let masters = clown
+                .masters
+                .iter()
+                .map(|master| expansion.master_on(quote::quote!(self), master));
+            quote::quote! {
+                if forced #(|| #masters)* || self.#ident != #default {
+                    dict.insert(#key.into(), figment::value::Value::from(self.#ident));
+                }
+            }
+        });
+        quote::quote! {
+            impl #impl_generics ::clowncopterize::__private::figment::Provider for #name #ty_generics #where_clause {
+                fn metadata(&self) -> ::clowncopterize::__private::figment::Metadata {
+                    ::clowncopterize::__private::figment::Metadata::named("clowntown command line flags")
+                }
+
+                fn data(
+                    &self,
+                ) -> ::std::result::Result<
+                    ::clowncopterize::__private::figment::value::Map<
+                        ::clowncopterize::__private::figment::Profile,
+                        ::clowncopterize::__private::figment::value::Dict,
+                    >,
+                    ::clowncopterize::__private::figment::Error,
+                > {
+                    use ::clowncopterize::__private::figment;
+                    let forced = false #(|| self.#unclowncopterizer)*;
+                    let mut dict = figment::value::Dict::new();
+                    #(#entries)*
+                    ::std::result::Result::Ok(figment::Profile::Default.collect(dict))
+                }
+            }
+        }
+    }
+
+    /// Emits a proptest `Arbitrary` impl picking a random set of clowntown flags.
+    fn arbitrary(&self, ast: &syn::ItemStruct) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        quote::quote! {
+            impl ::clowncopterize::__private::proptest::arbitrary::Arbitrary for #name {
+                type Parameters = ();
+                type Strategy = ::clowncopterize::__private::proptest::strategy::BoxedStrategy<Self>;
+
+                fn arbitrary_with(_: ()) -> Self::Strategy {
+                    use ::clowncopterize::__private::proptest::strategy::Strategy;
+                    ::clowncopterize::__private::proptest::collection::vec(
+                        ::clowncopterize::__private::proptest::arbitrary::any::<bool>(),
+                        Self::CLOWNTOWN_FLAGS.len(),
+                    )
+                    .prop_map(|enabled| {
+                        let mut builder = Self::clowntown_builder();
+                        for (flag, enabled) in Self::CLOWNTOWN_FLAGS.iter().zip(enabled) {
+                            if enabled {
+                                builder = builder.enable(flag);
+                            }
+                        }
+                        builder.build().unwrap_or_else(|err| ::std::panic!("{}", err))
+                    })
+                    .boxed()
+                }
+            }
+        }
+    }
+
+    /// The statements of `clowntown_post_parse`, run in order.
+    fn post_parse_steps(&self, expansion: &Expansion) -> Vec<proc_macro2::TokenStream> {
+        let mut steps = Vec::new();
+        if let Some(completions) = &expansion.completions {
+            let display = display_error(quote::quote!(
+                ::std::string::String::from_utf8_lossy(&script).into_owned()
+            ));
+            steps.push(quote::quote! {
+                if let ::std::option::Option::Some(shell) = self.#completions {
+                    let mut script = ::std::vec::Vec::new();
+                    Self::clowntown_completions(shell, &mut script);
+                    return ::std::result::Result::Err(#display);
+                }
+            });
+        }
+        if self.panic_in_tests {
+            let masters = expansion
+                .masters
+                .iter()
+                .map(|master| master.on(quote::quote!(self)));
+            let longs = expansion.masters.iter().map(Master::long);
+            // `cfg(test)` of the crate using the macro, as the code is expanded there.
+            steps.push(quote::quote! {
+                #[cfg(test)]
+                for (on, long) in [#((#masters, #longs)),*] {
+                    if on {
+                        ::std::panic!(
+                            "'{}' is set in a test: tests must set the clowntown flags they need one by one",
+                            long
+                        );
+                    }
+                }
+            });
+        }
+        for clown in expansion.clowns.iter() {
+            let Some(note) = &clown.deprecated else {
+                continue;
+            };
+            let id = clown.ident.to_string();
+            let message = format!("warning: `{}` is deprecated: {}", clown.long(), note);
+            steps.push(quote::quote! {
+                if matches.value_source(#id) == ::std::option::Option::Some(::clap::parser::ValueSource::CommandLine) {
+                    ::std::eprintln!("{}", #message);
+                }
+            });
+        }
+        if !self.deny_env.is_empty() {
+            let masters = expansion
+                .masters
+                .iter()
+                .map(|master| master.on(quote::quote!(self)));
+            let longs = expansion.masters.iter().map(Master::long);
+            let denied = self.deny_env.iter().map(|deny| match deny.split_once('=') {
+                Some((name, value)) => {
+                    quote::quote!((#name, ::std::option::Option::Some(#value), #deny))
+                }
+                None => {
+                    let condition = format!("{} is set", deny);
+                    quote::quote!((#deny, ::std::option::Option::None, #condition))
+                }
+            });
+            steps.push(quote::quote! {
+                let denied: &[(&str, ::std::option::Option<&str>, &str)] = &[#(#denied),*];
+                for (on, long) in [#((#masters, #longs)),*] {
+                    if !on {
+                        continue;
+                    }
+                    for (name, expected, condition) in denied {
+                        let ::std::option::Option::Some(actual) = ::std::env::var_os(name) else {
+                            continue;
+                        };
+                        if expected.is_none_or(|expected| actual == expected) {
+                            return ::std::result::Result::Err(::clap::Error::raw(
+                                ::clap::error::ErrorKind::ArgumentConflict,
+                                ::std::format!("'{}' is refused when {}\n", long, condition),
+                            ));
+                        }
+                    }
+                }
+            });
+        }
+        if let Some(sentinel) = &self.sentinel {
+            let masters = expansion
+                .masters
+                .iter()
+                .map(|master| master.on(quote::quote!(self)));
+            let longs = expansion.masters.iter().map(Master::long);
+            steps.push(quote::quote! {
+                for (on, long) in [#((#masters, #longs)),*] {
+                    if on && !::std::path::Path::new(#sentinel).exists() {
+                        return ::std::result::Result::Err(::clap::Error::raw(
+                            ::clap::error::ErrorKind::ArgumentConflict,
+                            ::std::format!("'{}' is refused: {} does not exist on this host\n", long, #sentinel),
+                        ));
+                    }
+                }
+            });
+        }
+        if let Some(guard) = &self.guard {
+            let env = match &self.guard_env {
+                Some(env) => quote::quote!(::std::option::Option::Some(#env)),
+                None => quote::quote!(::std::option::Option::None),
+            };
+            let checks = expansion.masters.iter().map(|master| {
+                let on = master.on(quote::quote!(self));
+                let long = master.long();
+                let flags = expansion.clowns_of(master).map(|clown| clown.ident.to_string());
+                quote::quote! {
+                    if #on {
+                        let context = ::clowncopterize::ClowntownGuardContext::new(#long, &[#(#flags),*], #env);
+                        if let ::std::result::Result::Err(reason) = #guard(&context) {
+                            return ::std::result::Result::Err(::clap::Error::raw(
+                                ::clap::error::ErrorKind::ArgumentConflict,
+                                ::std::format!("'{}' is refused: {}\n", #long, reason),
+                            ));
+                        }
+                    }
+                }
+            });
+            steps.push(quote::quote! { #(#checks)* });
+        }
+        // clap wires the exact names given to a `select` master, but not the globs
+        // `clowntown_command` lets through.
+        for master in expansion
+            .masters
+            .iter()
+            .filter(|master| master.shape == MasterShape::Select)
+        {
+            let ident = &master.ident;
+            let select = expansion.select_values(master, quote::quote!(&self.#ident));
+            let ids: Vec<_> = expansion
+                .clowns_of(master)
+                .map(|clown| clown.ident.to_string())
+                .collect();
+            let indices = 0..ids.len();
+            let places = expansion.clowns_of(master).map(ClownField::place);
+            let values = expansion.clowns_of(master).map(|clown| clown.risky);
+            steps.push(quote::quote! {
+                {
+                    let selected = #select.map_err(|message| {
+                        ::clap::Error::raw(::clap::error::ErrorKind::InvalidValue, message)
+                    })?;
+                    let explicit = |id: &str| {
+                        ::std::matches!(
+                            matches.value_source(id),
+                            ::std::option::Option::Some(
+                                ::clap::parser::ValueSource::CommandLine
+                                    | ::clap::parser::ValueSource::EnvVariable
+                            )
+                        )
+                    };
+                    #(
+                        if selected[#indices] && !explicit(#ids) {
+                            self.#places = #values;
+                        }
+                    )*
+                }
+            });
+        }
+        if self.env_filter && !expansion.clowns.is_empty() {
+            let count = expansion.clowns.len();
+            let filters = expansion.clowns.iter().enumerate().map(|(index, clown)| {
+                let ident = clown.place();
+                let id = clown.ident.to_string();
+                let safe = !clown.risky;
+                let masters = clown
+                    .masters
+                    .iter()
+                    .map(|master| expansion.master_on(quote::quote!(self), master));
+                quote::quote! {
+                    if (false #(|| #masters)*) && !explicit(#id) && (!allowed[#index] || blocked[#index]) {
+                        self.#ident = #safe;
+                    }
+                }
+            });
+            let longs = expansion.clowns.iter().map(ClownField::long);
+            steps.push(quote::quote! {
+                let select = |name: &str, all: bool| -> ::std::result::Result<::std::vec::Vec<bool>, ::clap::Error> {
+                    let ::std::result::Result::Ok(value) = ::std::env::var(name) else {
+                        return ::std::result::Result::Ok(::std::vec![all; #count]);
+                    };
+                    let patterns: ::std::vec::Vec<::std::string::String> = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|pattern| !pattern.is_empty())
+                        .map(::std::string::String::from)
+                        .collect();
+                    ::clowncopterize::__private::glob_select(&patterns, Self::CLOWNTOWN_FLAGS, &[#(#longs),*]).map_err(|message| {
+                        ::clap::Error::raw(
+                            ::clap::error::ErrorKind::InvalidValue,
+                            ::std::format!("{}: {}\n", name, message),
+                        )
+                    })
+                };
+                let allowed = select("CLOWNTOWN_ALLOW", true)?;
+                let blocked = select("CLOWNTOWN_BLOCK", false)?;
+                let explicit = |id: &str| {
+                    ::std::matches!(
+                        matches.value_source(id),
+                        ::std::option::Option::Some(
+                            ::clap::parser::ValueSource::CommandLine
+                                | ::clap::parser::ValueSource::EnvVariable
+                        )
+                    )
+                };
+                #(#filters)*
+            });
+        }
+        if let Some(unclowncopterizer) = &expansion.unclowncopterizer {
+            // defaults are taken care of by clap, but explicit and env values need forcing off.
+            let idents = expansion.clowns.iter().map(ClownField::place);
+            let values = expansion.clowns.iter().map(|clown| !clown.risky);
+            steps.push(quote::quote! {
+                if self.#unclowncopterizer {
+                    #( self.#idents = #values; )*
+                }
+            });
+        }
+        if let Some(max) = self.runtime_max {
+            let masters = expansion
+                .masters
+                .iter()
+                .map(|master| master.on(quote::quote!(self)));
+            let master = self.master_long(&self.clowncopterizer, expansion.casing);
+            let idents = expansion.clowns.iter().map(ClownField::place);
+            let values = expansion.clowns.iter().map(|clown| clown.risky);
+            let longs = expansion.clowns.iter().map(ClownField::long);
+            steps.push(quote::quote! {
+                if !(false #(|| #masters)*) {
+                    let enabled: ::std::vec::Vec<&str> = [#((self.#idents == #values, #longs)),*]
+                        .into_iter()
+                        .filter_map(|(enabled, long): (bool, &str)| enabled.then_some(long))
+                        .collect();
+                    if enabled.len() > #max {
+                        return ::std::result::Result::Err(::clap::Error::raw(
+                            ::clap::error::ErrorKind::ArgumentConflict,
+                            ::std::format!(
+                                "at most {} clowntown flags can be enabled at once, got {}: {}; use '{}' to enable them all on purpose",
+                                #max,
+                                enabled.len(),
+                                enabled.join(", "),
+                                #master,
+                            ),
+                        ));
+                    }
+                }
+            });
+        }
+        if !self.invariants.is_empty() {
+            steps.push(quote::quote! {
+                self.validate()?;
+            });
+        }
+        if let Some(provenance) = &expansion.provenance {
+            let display = display_error(quote::quote!(::std::format!(
+                "{}\n",
+                self.clowntown_provenance(matches)
+            )));
+            steps.push(quote::quote! {
+                if self.#provenance {
+                    return ::std::result::Result::Err(#display);
+                }
+            });
+        }
+        if self.report {
+            steps.push(quote::quote! {
+                for line in self.clowntown_report(matches) {
+                    ::std::eprintln!("{}", line);
+                }
+            });
+        }
+        if cfg!(feature = "metrics") {
+            steps.push(quote::quote! {
+                self.record_clowntown_metrics();
+            });
+        }
+        if let Some(sink) = &self.sink {
+            steps.push(quote::quote! {
+                self.record_clowntown_to(&#sink);
+            });
+        }
+        if cfg!(feature = "sentry") {
+            steps.push(quote::quote! {
+                self.record_clowntown_sentry();
+            });
+        }
+        steps
+    }
+
+    /// Emits a `#[cfg(test)]` module checking the master flag against every matched flag.
+    fn generate_tests(
+        &self,
+        ast: &syn::ItemStruct,
+        expansion: &Expansion,
+    ) -> proc_macro2::TokenStream {
+        let name = &ast.ident;
+        let module = Ident::new(
+            &format!("clowncopterize_{}_tests", snake_case(&name.to_string())),
+            Span::call_site(),
+        );
+        let masters_on: Vec<_> = expansion
+            .masters
+            .iter()
+            .map(|master| master.on(quote::quote!(cli)))
+            .collect();
+        let idents = expansion.clowns.iter().map(ClownField::place);
+        let longs = expansion.clowns.iter().map(ClownField::long);
+        let defaults = expansion.clowns.iter().map(|clown| clown.default);
+        // masters taking a value can't be given without one.
+        let master_tests = expansion
+            .masters
+            .iter()
+            .filter(|master| !self.master_takes_value(&master.ident.to_string()))
+            .map(|master| {
+                let test = Ident::new(&format!("{}_sets_all", master.ident), Span::call_site());
+                let master_on = master.on(quote::quote!(cli));
+                let master_long = master.long();
+                let idents = expansion.clowns_of(master).map(ClownField::place);
+                let longs = expansion.clowns_of(master).map(ClownField::long);
+                let values = expansion.clowns_of(master).map(|clown| clown.risky);
+                let companions = expansion.master_companions();
+                quote::quote! {
+                    #[test]
+                    fn #test() {
+                        let cli = #name::try_parse_from(["prog", #master_long #(, #companions)*]).unwrap();
+                        assert!(#master_on);
+                        #(
+                            assert_eq!(cli.#idents, #values, "{} should be set by {}", #longs, #master_long);
+                        )*
+                    }
+                }
+            });
+        quote::quote! {
+            #[cfg(test)]
+            mod #module {
+                use super::*;
+                use ::clap::{CommandFactory, Parser};
+
+                #[test]
+                fn command_is_valid() {
+                    #name::command().debug_assert();
+                }
+
+                #[test]
+                fn defaults_are_safe() {
+                    let cli = #name::try_parse_from(["prog"]).unwrap();
+                    #( assert!(!#masters_on); )*
+                    #(
+                        assert_eq!(cli.#idents, #defaults, "{} should default to {}", #longs, #defaults);
+                    )*
+                }
+
+                #(#master_tests)*
+            }
+        }
+    }
+
+    /// Emits the `macro_rules!` backing `#[clowncopterize_test]` for this struct.
+    ///
+    /// The test attribute can't see the struct's fields, so it invokes this macro with the
+    /// test function name and the struct type instead.
+    fn test_macro(&self, ast: &syn::ItemStruct, expansion: &Expansion) -> proc_macro2::TokenStream {
+        let macro_name = test_macro_ident(&ast.ident);
+        let tests = expansion.clowns.iter().map(|clown| {
+            let ident = &clown.ident;
+            let place = clown.place();
+            let long = clown.long();
+            let master_on = expansion.master_on(quote::quote!(cli), clown.master());
+            let master = self.master_long(clown.master(), expansion.casing);
+            let value = clown.risky;
+            let others: Vec<_> = expansion
+                .clowns
+                .iter()
+                .filter(|other| other.ident != clown.ident)
+                .collect();
+            let other_idents = others.iter().map(|other| other.place());
+            let other_values = others.iter().map(|other| other.default);
+            let companions = expansion.master_companions();
+            let master_check = (!self.master_takes_value(clown.master())).then(|| {
+                quote::quote! {
+                    let cli = <$ty as ::clap::Parser>::try_parse_from(["prog", #master #(, #companions)*]).unwrap();
+                    assert_eq!(cli.#place, #value, "{} should be set by {}", #long, #master);
+                    super::$test(&cli);
+                }
+            });
+            // locked flags can't be given without their master.
+            let alone_check = (!self.locked).then(|| {
+                quote::quote! {
+                    let cli = <$ty as ::clap::Parser>::try_parse_from(["prog", #long]).unwrap();
+                    assert_eq!(cli.#place, #value, "{} should be set", #long);
+                    assert!(!#master_on, "{} should not be set by {}", #master, #long);
+                    #(
+                        assert_eq!(cli.#other_idents, #other_values, "{} should only set itself", #long);
+                    )*
+                    super::$test(&cli);
+                }
+            });
+            quote::quote! {
+                #[test]
+                fn #ident() {
+                    #alone_check
+                    #master_check
+                }
+            }
+        });
+        quote::quote! {
+            #[allow(unused_macros)]
+            macro_rules! #macro_name {
+                ($test:ident, $ty:ty) => {
+                    #[cfg(test)]
+                    mod $test {
+                        use super::*;
+
+                        #(#tests)*
+                    }
+                };
+            }
+        }
+    }
+
+    fn clowncopterize_struct(
+        &self,
+        mut ast: syn::ItemStruct,
+        expansion: &mut Expansion,
+    ) -> syn::ItemStruct {
+        take_stray_helpers(&mut ast, expansion);
+        let command_groups = CommandGroup::from_attrs(&ast.attrs);
+        let typed_builder = derives(&ast, "TypedBuilder");
+        if let syn::Fields::Named(ref mut fields) = ast.fields {
+            // the masters among them are declared rather than injected.
+            let declared: Vec<String> = fields
+                .named
+                .iter()
+                .filter_map(|field| field.ident.as_ref().map(Ident::to_string))
+                .collect();
+            // iterate over each fields and modify any fields that start with `clowntown` and is a boolean.
+            fields.named =
+                syn::punctuated::Punctuated::from_iter(fields.named.iter_mut().map(|field| {
+                    let Some(ident) = field.ident.clone() else {
+                        return field.clone();
+                    };
+                    let is_bool =
+                        matches!(&field.ty, syn::Type::Path(ty) if ty.path.is_ident("bool"));
+                    // `ClownFlag` fields are clowntown flags whatever their name.
+                    let typed = is_clown_flag(&field.ty);
+                    if !(is_bool && self.prefix_of(&ident.to_string()).is_some() || typed)
+                        && take_nested(field, expansion)
+                    {
+                        expansion.note(
+                            ident.span(),
+                            format!("reporting the clowntown flags of nested `{}`", ident),
+                        );
+                        expansion.nested.push((ident, field.ty.clone()));
+                        return field.clone();
+                    }
+                    if !typed && self.prefix_of(&ident.to_string()).is_none() {
+                        expansion.note(
+                            ident.span(),
+                            format!(
+                                "skipped `{}`: name does not start with `{}`",
+                                ident,
+                                self.prefixes.join("` or `")
+                            ),
+                        );
+                        return field.clone();
+                    }
+                    if is_bool || typed {
+                        let clown =
+                            self.clown_field(field, ident, typed, &command_groups, expansion);
+                        if clown.masters.is_empty() {
+                            expansion.never_mass_enable.push(clown.ident);
+                            return field.clone();
+                        }
+                        if let Some(unclowncopterizer) = self.unclowncopterizer() {
+                            self.clowncopterize_field(
+                                field,
+                                &unclowncopterizer,
+                                !clown.risky,
+                                false,
+                            );
+                        }
+                        // a master setting the declared default would be a no-op.
+                        for master in clown.masters.iter().filter(|_| !clown.is_redundant()) {
+                            if (self.repeatable || self.select || self.mode.is_some())
+                                && *master == self.clowncopterizer
+                            {
+                                self.clowncopterize_field_by_value(field, &clown);
+                            } else {
+                                let declared = declared.contains(master);
+                                self.clowncopterize_field(field, master, clown.risky, declared);
+                            }
+                        }
+                        let field = field.clone();
+                        expansion.clowns.push(clown);
+                        return field;
+                    }
+                    expansion.note(
+                        ident.span(),
+                        format!("skipped `{}`: type is not `bool` or `ClownFlag`", ident),
+                    );
+                    field.clone()
+                }));
+            // the user's own field, which is then declared rather than injected.
+            if let Some(inject) = &self.inject {
+                fields.named.push(inject.clone());
+            }
+            // everything pushed from here on is injected.
+            let first_injected = fields.named.len();
+            expansion.first_injected = first_injected;
+            // Flags default to the clowncopterizer master, unless bound to another one. Masters
+            // the struct doesn't declare itself are injected.
+            let mut names: Vec<&String> = Vec::new();
+            if !self.externals.is_empty()
+                || expansion
+                    .clowns
+                    .iter()
+                    .any(|clown| clown.master() == self.clowncopterizer)
+            {
+                names.push(&self.clowncopterizer);
+            }
+            for master in expansion.clowns.iter().flat_map(|clown| &clown.masters) {
+                if !names.contains(&master) {
+                    names.push(master);
+                }
+            }
+            let mut masters = Vec::new();
+            for name in names {
+                let ident = Ident::new(name, Span::call_site());
+                let declared = fields
+                    .named
+                    .iter()
+                    .any(|field| field.ident.as_ref() == Some(&ident));
+                masters.push(Master {
+                    long: self.master_long(name, expansion.casing)[2..].to_string(),
+                    ident,
+                    injected: !declared,
+                    shape: if declared {
+                        MasterShape::Bool
+                    } else if self.repeatable && name == &self.clowncopterizer {
+                        MasterShape::Groups
+                    } else if self.select && name == &self.clowncopterizer {
+                        MasterShape::Select
+                    } else if self.mode.is_some() && name == &self.clowncopterizer {
+                        MasterShape::Mode
+                    } else if self.action == MasterAction::Count {
+                        MasterShape::Count
+                    } else {
+                        MasterShape::Bool
+                    },
+                });
+            }
+            let ack_flag = self
+                .ack_flag()
+                .filter(|_| !masters.is_empty())
+                .map(|ack| Ident::new(&ack, Span::call_site()));
+            let reason = self
+                .reason()
+                .filter(|_| !masters.is_empty())
+                .map(|reason| Ident::new(&reason, Span::call_site()));
+            // the flags the masters require, each requiring the others too.
+            let companions: Vec<String> = ack_flag
+                .iter()
+                .chain(&reason)
+                .map(Ident::to_string)
+                .collect();
+            let requires_others = |companion: &Ident| {
+                let others = companions
+                    .iter()
+                    .filter(|other| companion != other.as_str());
+                quote::quote!(#(, requires = #others)*)
+            };
+            let solitary: Vec<_> = expansion
+                .never_mass_enable
+                .iter()
+                .map(|ident| ident.to_string())
+                .collect();
+            let conflicts = if solitary.is_empty() {
+                None
+            } else {
+                Some(quote::quote!(conflicts_with_all = [#(#solitary),*]))
+            };
+            for master in masters.iter().filter(|master| !master.injected) {
+                if let Some(conflicts) = &conflicts {
+                    let field = fields
+                        .named
+                        .iter_mut()
+                        .find(|field| field.ident.as_ref() == Some(&master.ident))
+                        .unwrap();
+                    extend_arg(field, conflicts.clone());
+                }
+                if let Some(token) = self.master_value_args(&master.long()) {
+                    let field = fields
+                        .named
+                        .iter_mut()
+                        .find(|field| field.ident.as_ref() == Some(&master.ident))
+                        .unwrap();
+                    extend_arg(field, token);
+                }
+            }
+            for master in masters.iter().filter(|master| !master.injected) {
+                let field = fields
+                    .named
+                    .iter_mut()
+                    .find(|field| field.ident.as_ref() == Some(&master.ident))
+                    .unwrap();
+                for companion in &companions {
+                    extend_arg(field, quote::quote!(requires = #companion));
+                }
+            }
+            if let Some(ack) = &ack_flag {
+                let ack_long = long_flag(&ack.to_string(), expansion.casing);
+                let longs: Vec<_> = masters.iter().map(Master::long).collect();
+                expansion.note(
+                    ast.ident.span(),
+                    format!("injected `{}` flag (field `{}`)", ack_long, ack),
+                );
+                let doc = format!(
+                    "Acknowledges mass-enabling clowntown flags, required by {}",
+                    longs.join(", ")
+                );
+                let help = self.localized_help(&ack.to_string(), &doc);
+                let heading = self.injected_heading();
+                let global = self.global_arg(expansion);
+                let requires = requires_others(ack);
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    #[doc = #doc]
+                    #[arg(long #help #heading #global #requires)]
+                    #ack: bool
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+            }
+            if let Some(reason) = &reason {
+                let reason_long = long_flag(&reason.to_string(), expansion.casing);
+                let longs: Vec<_> = masters.iter().map(Master::long).collect();
+                expansion.note(
+                    ast.ident.span(),
+                    format!("injected `{}` flag (field `{}`)", reason_long, reason),
+                );
+                let doc = format!(
+                    "Why the clowntown flags are mass-enabled, required by {}",
+                    longs.join(", ")
+                );
+                let help = self.localized_help(&reason.to_string(), &doc);
+                let heading = self.injected_heading();
+                let global = self.global_arg(expansion);
+                let requires = requires_others(reason);
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    #[doc = #doc]
+                    #[arg(
+                        long #help #heading #global #requires,
+                        value_name = "REASON",
+                        value_parser = ::clap::builder::NonEmptyStringValueParser::new()
+                    )]
+                    #reason: Option<String>
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+            }
+            // the master is repeated after the subcommand, along with its args.
+            if self.exclusive && expansion.has_subcommand {
+                expansion.errors.push(syn::Error::new(
+                    ast.ident.span(),
+                    "`exclusive` can't be used with a `#[command(subcommand)]` field, the masters \
+                     must be given along the subcommand",
+                ));
+            }
+            for master in masters.iter().filter(|master| master.injected) {
+                expansion.note(
+                    ast.ident.span(),
+                    format!(
+                        "injected `{}` flag (field `{}`)",
+                        master.long(),
+                        master.ident
+                    ),
+                );
+                let clowncopterizer = &master.ident;
+                let mut doc = if master.ident == self.clowncopterizer && self.toggle {
+                    "Toggles all the clowntown flags from their default".to_string()
+                } else if master.shape == MasterShape::Groups {
+                    "Turns the clowntown flags of a group on, or all of them with `all`".to_string()
+                } else if master.shape == MasterShape::Select {
+                    "Turns the given clowntown flags on, all of them with `all`, or none with `none`"
+                        .to_string()
+                } else if master.shape == MasterShape::Mode {
+                    "Turns all the clowntown flags on, or only records the intent with `dry-run`"
+                        .to_string()
+                } else if master.ident == self.clowncopterizer {
+                    "Turns all the clowntown flags on".to_string()
+                } else {
+                    let longs: Vec<_> = expansion.clowns_of(master).map(ClownField::long).collect();
+                    format!("Turns {} on", longs.join(", "))
+                };
+                if !companions.is_empty() {
+                    let longs: Vec<_> = companions
+                        .iter()
+                        .map(|companion| long_flag(companion, expansion.casing))
+                        .collect();
+                    doc.push_str(&format!(", along with {}", longs.join(" and ")));
+                }
+                let requires = quote::quote!(#(, requires = #companions)*);
+                let token = match self.master_value_args(&master.long()) {
+                    Some(token) => {
+                        doc.push_str(if self.token_key.is_some() {
+                            ", given a valid token"
+                        } else {
+                            ", given a valid value"
+                        });
+                        quote::quote!(, #token)
+                    }
+                    None => proc_macro2::TokenStream::new(),
+                };
+                let custom_long = match &self.long {
+                    Some(long) if master.ident == self.clowncopterizer => quote::quote!(= #long),
+                    _ => proc_macro2::TokenStream::new(),
+                };
+                let long_help = match &self.long_help {
+                    Some(long_help) if master.ident == self.clowncopterizer => {
+                        match &self.localize {
+                            Some(localize) => {
+                                let key = format!("{}_long_help", master.ident);
+                                quote::quote!(, long_help = #localize(#key, #long_help))
+                            }
+                            None => quote::quote!(, long_help = #long_help),
+                        }
+                    }
+                    _ => proc_macro2::TokenStream::new(),
+                };
+                // the subcommand stands for it.
+                let hide = if self.hidden
+                    || (self.subcommand.is_some() && master.ident == self.clowncopterizer)
+                {
+                    quote::quote!(, hide = true)
+                } else {
+                    proc_macro2::TokenStream::new()
+                };
+                let exclusive = if self.exclusive {
+                    quote::quote!(, exclusive = true)
+                } else {
+                    proc_macro2::TokenStream::new()
+                };
+                let (ty, action) = match master.shape {
+                    MasterShape::Bool => (quote::quote!(bool), proc_macro2::TokenStream::new()),
+                    MasterShape::Count => (
+                        quote::quote!(u8),
+                        quote::quote!(, action = ::clap::ArgAction::Count),
+                    ),
+                    MasterShape::Groups => {
+                        let mut groups = vec!["all".to_string()];
+                        for group in expansion.clowns.iter().flat_map(|clown| &clown.groups) {
+                            if !groups.contains(group) {
+                                groups.push(group.clone());
+                            }
+                        }
+                        // clap's derive only recognizes a bare `Vec`.
+                        (
+                            quote::quote!(Vec<String>),
+                            quote::quote! {
+                                , action = ::clap::ArgAction::Append,
+                                value_name = "GROUP",
+                                value_parser = [#(#groups),*]
+                            },
+                        )
+                    }
+                    MasterShape::Mode => {
+                        let mode = self.mode.as_ref().expect("`mode` sets the shape");
+                        (
+                            quote::quote!(#mode),
+                            quote::quote! {
+                                , value_enum,
+                                default_value_t,
+                                num_args = 0..=1,
+                                require_equals = true,
+                                default_missing_value = "all",
+                                value_name = "MODE"
+                            },
+                        )
+                    }
+                    MasterShape::Select => {
+                        let names = expansion.clowns_of(master).map(|clown| &clown.long);
+                        (
+                            quote::quote!(Vec<String>),
+                            quote::quote! {
+                                , action = ::clap::ArgAction::Append,
+                                value_delimiter = ',',
+                                value_name = "FLAGS",
+                                value_parser = ["all", "none" #(, #names)*]
+                            },
+                        )
+                    }
+                };
+                let solitary_conflicts = conflicts
+                    .as_ref()
+                    .map(|conflicts| quote::quote!(, #conflicts));
+                let help = self.localized_help(&master.ident.to_string(), &doc);
+                let heading = self.injected_heading();
+                // so that the subcommands see it, see `clowntown_subcommand_args`.
+                let global = self.global_arg(expansion);
+                // Defaults don't count as being present for clap, so a required group would
+                // still complain after `--clowncopterize`, and an exclusive group would happily
+                // take it along with another member. Joining the groups of the flags it sets
+                // makes the master behave like any other member.
+                let mut groups: Vec<String> = Vec::new();
+                for group in expansion
+                    .clowns_of(master)
+                    .flat_map(|clown| &clown.arg_groups)
+                {
+                    if !groups.contains(group) {
+                        groups.push(group.clone());
+                    }
+                }
+                for group in groups.iter() {
+                    expansion.note(
+                        ast.ident.span(),
+                        format!("`{}` joins arg group `{}`", master.long(), group),
+                    );
+                }
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    #[doc = #doc]
+                    #[arg(long #custom_long #help #long_help #heading #global #hide #exclusive #action #requires #token #(, group = #groups)* #solitary_conflicts)]
+                    #clowncopterizer: #ty
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+            }
+            if let Some(unclowncopterizer) = self
+                .unclowncopterizer()
+                .filter(|_| !expansion.clowns.is_empty())
+            {
+                let unclowncopterizer = Ident::new(&unclowncopterizer, Span::call_site());
+                expansion.note(
+                    ast.ident.span(),
+                    format!(
+                        "injected `{}` flag (field `{}`)",
+                        long_flag(&unclowncopterizer.to_string(), expansion.casing),
+                        unclowncopterizer
+                    ),
+                );
+                let masters = masters.iter().map(|master| master.ident.to_string());
+                let help = self.localized_help(
+                    &unclowncopterizer.to_string(),
+                    "Turns all the clowntown flags off",
+                );
+                let heading = self.injected_heading();
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    /// Turns all the clowntown flags off
+                    #[arg(long #help #heading, conflicts_with_all = [#(#masters),*])]
+                    #unclowncopterizer: bool
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+                expansion.unclowncopterizer = Some(unclowncopterizer);
+            }
+            if let Some(until) = self.until().filter(|_| !masters.is_empty()) {
+                let until = Ident::new(&until, Span::call_site());
+                expansion.note(
+                    ast.ident.span(),
+                    format!(
+                        "injected `{}` flag (field `{}`)",
+                        long_flag(&until.to_string(), expansion.casing),
+                        until
+                    ),
+                );
+                let doc = "Refuses to run past this RFC 3339 time, e.g. 2026-01-31T18:00:00Z";
+                let help = self.localized_help(&until.to_string(), doc);
+                let heading = self.injected_heading();
+                let global = self.global_arg(expansion);
+                // checked by clap, so a stale command fails however the struct is parsed.
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    #[doc = #doc]
+                    #[arg(
+                        long #help #heading #global,
+                        value_name = "DEADLINE",
+                        value_parser = |text: &str| {
+                            let deadline = ::clowncopterize::__private::parse_rfc3339(text)?;
+                            if ::std::time::SystemTime::now() > deadline {
+                                return ::std::result::Result::Err(::std::format!(
+                                    "the deadline {} has passed, refusing to enter clowntown",
+                                    text
+                                ));
+                            }
+                            ::std::result::Result::Ok(deadline)
+                        }
+                    )]
+                    #until: Option<::std::time::SystemTime>
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+            }
+            if let Some(shadow) = self.shadow().filter(|_| {
+                masters
+                    .iter()
+                    .any(|master| master.ident == self.clowncopterizer)
+            }) {
+                let shadow = Ident::new(&shadow, Span::call_site());
+                expansion.note(
+                    ast.ident.span(),
+                    format!(
+                        "injected `{}` flag (field `{}`)",
+                        long_flag(&shadow.to_string(), expansion.casing),
+                        shadow
+                    ),
+                );
+                let doc = format!(
+                    "Records the use of {} without turning anything on",
+                    self.master_long(&self.clowncopterizer, expansion.casing)
+                );
+                let help = self.localized_help(&shadow.to_string(), &doc);
+                let heading = self.injected_heading();
+                let clowncopterizer = &self.clowncopterizer;
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    #[doc = #doc]
+                    #[arg(long #help #heading, conflicts_with = #clowncopterizer)]
+                    #shadow: bool
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+                expansion.shadow = Some(shadow);
+            }
+            if let Some(completions) = self
+                .completions
+                .as_ref()
+                .filter(|_| expansion.is_parser && !expansion.clowns.is_empty())
+            {
+                let completions = Ident::new(completions, Span::call_site());
+                expansion.note(
+                    ast.ident.span(),
+                    format!(
+                        "injected `{}` flag (field `{}`)",
+                        long_flag(&completions.to_string(), expansion.casing),
+                        completions
+                    ),
+                );
+                let help = self.localized_help(
+                    &completions.to_string(),
+                    "Prints shell completions, including the clowntown flags, and exits",
+                );
+                let heading = self.injected_heading();
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    /// Prints shell completions, including the clowntown flags, and exits
+                    #[arg(long #help #heading, value_name = "SHELL")]
+                    #completions: Option<::clowncopterize::__private::clap_complete::Shell>
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+                expansion.completions = Some(completions);
+            }
+            if let Some(provenance) = self
+                .provenance
+                .as_ref()
+                .filter(|_| expansion.is_parser && !expansion.clowns.is_empty())
+            {
+                let provenance = Ident::new(provenance, Span::call_site());
+                expansion.note(
+                    ast.ident.span(),
+                    format!(
+                        "injected `{}` flag (field `{}`)",
+                        long_flag(&provenance.to_string(), expansion.casing),
+                        provenance
+                    ),
+                );
+                let help = self.localized_help(
+                    &provenance.to_string(),
+                    "Prints where the clowntown flags' values come from, as JSON, and exits",
+                );
+                let heading = self.injected_heading();
+                let global = self.global_arg(expansion);
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    /// Prints where the clowntown flags' values come from, as JSON, and exits
+                    #[arg(long #help #heading #global)]
+                    #provenance: bool
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+                expansion.provenance = Some(provenance);
+            }
+            if let Some(clowntown_help) = self
+                .clowntown_help
+                .as_ref()
+                .filter(|_| expansion.is_parser && !expansion.clowns.is_empty())
+            {
+                let clowntown_help = Ident::new(clowntown_help, Span::call_site());
+                expansion.note(
+                    ast.ident.span(),
+                    format!(
+                        "injected `{}` flag (field `{}`)",
+                        long_flag(&clowntown_help.to_string(), expansion.casing),
+                        clowntown_help
+                    ),
+                );
+                let help = self.localized_help(
+                    &clowntown_help.to_string(),
+                    "Prints the help of the clowntown flags only, and exits",
+                );
+                let heading = self.injected_heading();
+                let punctuated_fields: syn::punctuated::Punctuated<
+                    ParsableNamedField,
+                    syn::Token![,],
+                > = syn::parse_quote! {
+                    /// Prints the help of the clowntown flags only, and exits
+                    #[arg(long #help #heading)]
+                    #clowntown_help: bool
+                };
+                for punctuated_field in punctuated_fields {
+                    fields.named.push(punctuated_field.field);
+                }
+                expansion.clowntown_help = Some(clowntown_help);
+            }
+            expansion.mode = self.mode.clone().filter(|_| {
+                masters
+                    .iter()
+                    .any(|master| master.shape == MasterShape::Mode)
+            });
+            expansion.masters = masters;
+            expansion.ack_flag = ack_flag;
+            expansion.reason = reason;
+            let vis = match &self.vis {
+                Some(Vis::Declared(vis)) => Some(vis.clone()),
+                Some(Vis::Inherit) => Some(ast.vis.clone()),
+                None => None,
+            };
+            if let Some(vis) = vis {
+                for field in fields.named.iter_mut().skip(first_injected) {
+                    field.vis = vis.clone();
+                }
+            }
+            // the builder would otherwise want a value for every injected field.
+            let builder_default = typed_builder
+                && !self
+                    .extra_attrs
+                    .iter()
+                    .any(|meta| meta.path().is_ident("builder"));
+            for field in fields.named.iter_mut().skip(first_injected) {
+                if builder_default {
+                    field.attrs.push(syn::parse_quote!(#[builder(default)]));
+                }
+                for meta in &self.extra_attrs {
+                    field.attrs.push(syn::parse_quote!(#[#meta]));
+                }
+            }
+            if expansion.clowns.is_empty() {
+                expansion.note(
+                    ast.ident.span(),
+                    "no clowntown flag matched, nothing injected".to_string(),
+                );
+            }
+        } else {
+            expansion.note(
+                ast.ident.span(),
+                "struct has no named fields, nothing to clowncopterize".to_string(),
+            );
+        }
+        ast
+    }
+
+    /// The name clap derives the long flag of a clowntown field from, before casing.
+    fn long_name(&self, ident: &Ident) -> String {
+        let name = ident.to_string();
+        let rest = self
+            .prefix_of(&name)
+            .and_then(|prefix| name.strip_prefix(prefix));
+        match (&self.strip_prefix, rest) {
+            (Some(prefix), Some(rest)) if rest.starts_with('_') && rest.len() > 1 => {
+                if prefix.is_empty() {
+                    rest[1..].to_string()
+                } else {
+                    format!("{}{}", prefix, rest)
+                }
+            }
+            _ => name,
+        }
+    }
+
+    /// Gathers what we need to know about a matched clowntown flag.
+    fn clown_field(
+        &self,
+        field: &mut syn::Field,
+        ident: Ident,
+        typed: bool,
+        command_groups: &[CommandGroup],
+        expansion: &mut Expansion,
+    ) -> ClownField {
+        let options = FieldOptions::take(field, expansion);
+        let help = doc_string(&field.attrs)
+            .split("\n\n")
+            .next()
+            .unwrap_or_default()
+            .replace('\n', " ");
+        let constraints: Vec<_> = options
+            .requires
+            .iter()
+            .map(|arg| quote::quote!(requires = #arg))
+            .chain(
+                options
+                    .conflicts_with
+                    .iter()
+                    .map(|arg| quote::quote!(conflicts_with = #arg)),
+            )
+            .collect();
+        if !constraints.is_empty() {
+            extend_arg(field, quote::quote!(#(#constraints),*));
+        }
+        if self.hide_flags {
+            extend_arg(field, quote::quote!(hide = true));
+        }
+        let has_heading = attr_metas(&field.attrs, "arg")
+            .iter()
+            .any(|meta| meta.path().is_ident("help_heading"));
+        let heading = match options.severity.as_deref() {
+            Some("high") => Some("Risky (HIGH)"),
+            Some(_) => Some("Risky (low)"),
+            None if self.warning_style => Some(CLOWNTOWN_HEADING),
+            None => None,
+        };
+        if let Some(heading) = heading.filter(|_| !has_heading) {
+            let heading = self.styled(heading);
+            extend_arg(field, quote::quote!(help_heading = #heading));
+        }
+        if self.warning_style && !help.is_empty() {
+            let help = self.styled(&help);
+            extend_arg(field, quote::quote!(help = #help));
+        }
+        let mut tags = Vec::new();
+        if let Some(since) = &options.since {
+            tags.push(("since", since));
+        }
+        if let Some(link) = &options.link {
+            tags.push(("runbook", link));
+        }
+        if let Some(note) = &options.deprecated {
+            tags.push(("deprecated", note));
+            extend_arg(field, quote::quote!(hide_short_help = true));
+        }
+        if !tags.is_empty() {
+            let doc = doc_string(&field.attrs);
+            let doc = if doc.is_empty() {
+                doc
+            } else {
+                format!("{}\n\n", doc)
+            };
+            let long_help = match &self.localize {
+                Some(localize) => {
+                    let template = format!(
+                        "{}{}",
+                        doc.replace('{', "{{").replace('}', "}}"),
+                        vec!["[{}: {}]"; tags.len()].join(" ")
+                    );
+                    let labels = tags.iter().map(|(label, _)| label);
+                    let values = tags.iter().map(|(_, value)| value);
+                    quote::quote!(
+                        ::std::format!(#template, #(#localize(#labels, #labels), #values),*)
+                    )
+                }
+                None => {
+                    let tags: Vec<_> = tags
+                        .iter()
+                        .map(|(label, value)| format!("[{}: {}]", label, value))
+                        .collect();
+                    let long_help = format!("{}{}", doc, tags.join(" "));
+                    quote::quote!(#long_help)
+                }
+            };
+            extend_arg(field, quote::quote!(long_help = #long_help));
+        }
+        let mut masters = vec![
+            options
+                .master
+                .clone()
+                .unwrap_or_else(|| self.clowncopterizer.clone()),
+        ];
+        if let Some(groups) = &self.groups {
+            for group in options
+                .groups
+                .iter()
+                .filter(|group| !groups.contains(group))
+            {
+                expansion.errors.push(syn::Error::new(
+                    ident.span(),
+                    format!(
+                        "`{}` is not one of the declared groups: {}",
+                        group,
+                        groups.join(", ")
+                    ),
+                ));
+            }
+        }
+        masters.extend(
+            options
+                .groups
+                .iter()
+                .map(|group| format!("{}_{}", self.clowncopterizer, group)),
+        );
+        if options.never_mass_enable {
+            if options.master.is_some() || !options.groups.is_empty() {
+                expansion.errors.push(syn::Error::new(
+                    ident.span(),
+                    "`never_mass_enable` flags can't have a `master` or a `group`",
+                ));
+            }
+            masters.clear();
+        } else if self.locked {
+            let master = &masters[0];
+            extend_arg(field, quote::quote!(requires = #master));
+        }
+        let arg_metas = attr_metas(&field.attrs, "arg");
+        let long = match arg_metas.iter().find_map(|meta| meta_str(meta, "long")) {
+            Some(long) => long,
+            None => {
+                let long = expansion.casing.rename(&self.long_name(&ident));
+                if self.strip_prefix.is_some() {
+                    set_long(field, &long);
+                }
+                long
+            }
+        };
+        let default = declared_default(&arg_metas);
+        if typed {
+            // clap's flag actions only store a `bool`, so a `ClownFlag` takes no value instead,
+            // unless declared with an optional one, and gets parsed from `default_value` or
+            // `default_missing_value`, along with the flag's metadata.
+            let name = ident.to_string();
+            let declared = |name: &str| arg_metas.iter().any(|meta| meta.path().is_ident(name));
+            let default_value =
+                (!declared("default_value")).then(|| quote::quote!(default_value = "false",));
+            let num_args = (!declared("num_args")).then(|| quote::quote!(num_args = 0,));
+            let default_missing_value = (!declared("default_missing_value"))
+                .then(|| quote::quote!(default_missing_value = "true",));
+            extend_arg(
+                field,
+                quote::quote! {
+                    action = ::clap::ArgAction::Set,
+                    #num_args
+                    #default_missing_value
+                    #default_value
+                    value_parser = {
+                        let info = Self::clowntown_manifest()
+                            .iter()
+                            .find(|info| info.name == #name);
+                        ::clap::builder::TypedValueParser::map(
+                            ::clap::builder::BoolValueParser::new(),
+                            move |on| ::clowncopterize::ClownFlag::new(on, info),
+                        )
+                    }
+                },
+            );
+        }
+        // a bare `env` reads the field name in SCREAMING_SNAKE_CASE.
+        let env = arg_metas.iter().find_map(|meta| match meta {
+            syn::Meta::Path(path) if path.is_ident("env") => Some(ident.to_string().to_uppercase()),
+            meta => meta_str(meta, "env"),
+        });
+        let mut risky = if self.toggle { !default } else { true } ^ options.invert;
+        if risky == default && self.default_true == DefaultTrue::Invert {
+            risky = !default;
+        }
+        let mut arg_groups: Vec<String> = arg_metas
+            .iter()
+            .filter_map(|meta| meta_str(meta, "group"))
+            .collect();
+        arg_groups.extend(
+            command_groups
+                .iter()
+                .filter(|group| group.args.iter().any(|arg| ident == arg))
+                .map(|group| group.id.clone()),
+        );
+        if !field.attrs.iter().any(|attr| attr.path().is_ident("arg")) {
+            expansion.note(
+                ident.span(),
+                format!(
+                    "matched `{}`, but it has no `#[arg(...)]` attribute to wire",
+                    ident
+                ),
+            );
+        } else if masters.is_empty() {
+            expansion.note(
+                ident.span(),
+                format!("matched `{}`, never mass-enabled", ident),
+            );
+        } else if risky == default {
+            expansion.note(
+                ident.span(),
+                format!("matched `{}`, already {} by default", ident, default),
+            );
+        } else {
+            expansion.note(
+                ident.span(),
+                format!(
+                    "matched `{}`, set to {} by {}",
+                    ident,
+                    risky,
+                    masters
+                        .iter()
+                        .map(|master| format!("`{}`", self.master_long(master, expansion.casing)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            );
+        }
+        ClownField {
+            ident,
+            long,
+            masters,
+            arg_groups,
+            groups: options.groups,
+            typed,
+            default,
+            risky,
+            deprecated: options.deprecated,
+            help,
+            owner: options.owner,
+            expires: options.expires,
+            since: options.since,
+            link: options.link,
+            severity: options.severity,
+            env,
+        }
+    }
+
+    /// Wires the field to the `repeatable` clowncopterizer, on `all` and on each of its groups, to
+    /// the `select` one, on `all` and on its own name, or to the `mode` one, on `all`.
+    fn clowncopterize_field_by_value(&self, ast: &mut syn::Field, clown: &ClownField) {
+        let clowncopterizer = &self.clowncopterizer;
+        let names = if self.select {
+            std::slice::from_ref(&clown.long)
+        } else if self.repeatable {
+            clown.groups.as_slice()
+        } else {
+            &[]
+        };
+        for (predicate, on) in self.master_predicates(clowncopterizer, names, false) {
+            let value = (clown.risky == on).to_string();
+            extend_arg(
+                ast,
+                quote::quote! {
+                    default_value_if(#clowncopterizer, #predicate, #value)
+                },
+            );
+        }
+    }
+
+    fn clowncopterize_field(
+        &self,
+        ast: &mut syn::Field,
+        master: &str,
+        value: bool,
+        declared: bool,
+    ) -> syn::Field {
+        let clowncopterizer = master;
+        for (predicate, on) in self.master_predicates(master, &[], declared) {
+            let value = (value == on).to_string();
+            extend_arg(
+                ast,
+                quote::quote! {
+                    default_value_if(#clowncopterizer, #predicate, #value)
+                },
+            );
+        }
+        ast.clone()
+    }
+
+    /// The `ArgPredicate`s on `master` wiring a flag, in order, the first matching one winning,
+    /// each with whether it turns the flag on or keeps it off. `names` are its groups, or its own
+    /// name with `select`, that the `clowncopterizer` takes besides `all`; `declared` tells a
+    /// master of the struct from an injected one.
+    fn master_predicates(
+        &self,
+        master: &str,
+        names: &[String],
+        declared: bool,
+    ) -> Vec<(proc_macro2::TokenStream, bool)> {
+        if (self.repeatable || self.select || self.mode.is_some()) && master == self.clowncopterizer
+        {
+            return std::iter::once("all")
+                .chain(names.iter().map(String::as_str))
+                .map(|name| {
+                    (
+                        quote::quote!(::clap::builder::ArgPredicate::Equals(#name.into())),
+                        true,
+                    )
+                })
+                .collect();
+        }
+        if self.unclowncopterizer().as_deref() == Some(master) {
+            return vec![(quote::quote!("true"), true)];
+        }
+        let present = quote::quote!(::clap::builder::ArgPredicate::IsPresent);
+        if self.takes_value() {
+            // a master taking a value is on whenever given, as clap refuses invalid ones. Clap
+            // applies the defaults in the order of the arguments though, so a master declared
+            // before its flags is already "present" through its empty default: an empty value
+            // keeps them off.
+            let off = quote::quote!(::clap::builder::ArgPredicate::Equals("".into()));
+            return vec![(off, false), (present, true)];
+        }
+        // and so is the injected counted one, while a declared one stays a `bool`, "present"
+        // through its `false` default.
+        if self.action == MasterAction::Count && !declared {
+            vec![(present, true)]
+        } else {
+            vec![(quote::quote!("true"), true)]
+        }
+    }
+}
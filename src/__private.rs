@@ -0,0 +1,687 @@
+//! Runtime support for the code generated by the macros. Not a public API.
+
+#[cfg(feature = "completions")]
+pub use clap_complete;
+#[cfg(feature = "figment")]
+pub use figment;
+#[cfg(feature = "inventory")]
+pub use inventory;
+#[cfg(feature = "metrics")]
+pub use metrics;
+#[cfg(feature = "notify")]
+pub use notify;
+#[cfg(feature = "otel")]
+pub use opentelemetry;
+#[cfg(feature = "prometheus")]
+pub use prometheus;
+#[cfg(feature = "proptest")]
+pub use proptest;
+#[cfg(feature = "sentry")]
+pub use sentry_core;
+
+/// Serde default for flags declared to default to true, `bool` or `ClownFlag`.
+pub fn default_true<T: From<bool>>() -> T {
+    T::from(true)
+}
+
+/// Whether `name` matches the glob `pattern`, where `*` matches any run of characters and `?`
+/// matches exactly one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), name.chars().collect());
+    // classic backtracking on the last `*` seen.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Resolves the flag selection `patterns` against the known `flags` and their `longs`, returning
+/// which ones are selected.
+///
+/// Patterns may use the field name (`clowntown_db_*`) or the long flag (`--clowntown-db-*`), so
+/// explicit `long = "..."` names work too. A pattern without wildcards may also abbreviate a
+/// single flag, see [`abbreviated`]. A pattern matching no flag is an error, so typos don't go
+/// unnoticed.
+pub fn glob_select(
+    patterns: &[String],
+    flags: &[&str],
+    longs: &[&str],
+) -> Result<Vec<bool>, String> {
+    // both names of each flag, as snake_case.
+    let names: Vec<[String; 2]> = flags
+        .iter()
+        .zip(longs)
+        .map(|(flag, long)| {
+            [
+                flag.to_string(),
+                long.trim_start_matches("--").replace('-', "_"),
+            ]
+        })
+        .collect();
+    let mut selected = vec![false; flags.len()];
+    for pattern in patterns {
+        let normalized = pattern.trim_start_matches("--").replace('-', "_");
+        let mut matched = false;
+        for (i, names) in names.iter().enumerate() {
+            if names.iter().any(|name| glob_match(&normalized, name)) {
+                selected[i] = true;
+                matched = true;
+            }
+        }
+        if !matched && !normalized.contains(['*', '?']) {
+            let mut candidates = abbreviated(&normalized, flags);
+            let longs: Vec<&str> = names.iter().map(|[_, long]| long.as_str()).collect();
+            for i in abbreviated(&normalized, &longs) {
+                if !candidates.contains(&i) {
+                    candidates.push(i);
+                }
+            }
+            match candidates[..] {
+                [i] => {
+                    selected[i] = true;
+                    matched = true;
+                }
+                [_, _, ..] => {
+                    let candidates: Vec<&str> = candidates.iter().map(|&i| flags[i]).collect();
+                    return Err(format!(
+                        "'{}' is ambiguous, it could be any of: {}",
+                        pattern,
+                        candidates.join(", ")
+                    ));
+                }
+                [] => {}
+            }
+        }
+        if !matched {
+            return Err(format!(
+                "no clowntown flag matches '{}', expected one of: {}",
+                pattern,
+                flags.join(", ")
+            ));
+        }
+    }
+    Ok(selected)
+}
+
+/// Resolves the `values` given to a `select` master: `all`, `none` or patterns of the `flags`
+/// and their `longs`, see [`glob_select`].
+pub fn select_values(
+    values: &[String],
+    flags: &[&str],
+    longs: &[&str],
+) -> Result<Vec<bool>, String> {
+    let patterns: Vec<String> = values
+        .iter()
+        .filter(|value| *value != "none")
+        .map(|value| match value.as_str() {
+            "all" => "*".to_string(),
+            _ => value.clone(),
+        })
+        .collect();
+    glob_select(&patterns, flags, longs)
+}
+
+/// Indices of the `flags` that `abbreviation` is a prefix of, like clap's `infer_long_args`, or
+/// a prefix of once some of their leading words are dropped: `db_dr` stands for
+/// `clowntown_db_drop`.
+pub fn abbreviated(abbreviation: &str, flags: &[&str]) -> Vec<usize> {
+    if abbreviation.is_empty() {
+        return Vec::new();
+    }
+    flags
+        .iter()
+        .enumerate()
+        .filter(|(_, flag)| {
+            flag.starts_with(abbreviation)
+                || flag
+                    .match_indices('_')
+                    .any(|(i, _)| flag[i + 1..].starts_with(abbreviation))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Index of `name` in `flags`, given as a field name or a long flag.
+pub fn flag_index(name: &str, flags: &[&str]) -> Result<usize, String> {
+    let normalized = name.trim_start_matches("--").replace('-', "_");
+    flags
+        .iter()
+        .position(|flag| *flag == normalized)
+        .ok_or_else(|| {
+            format!(
+                "'{}' is not a clowntown flag, expected one of: {}",
+                name,
+                flags.join(", ")
+            )
+        })
+}
+
+/// Quotes `text` as a JSON string.
+pub fn json_str(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The index in `args` of the subcommand, called one of `names`, skipping the values of the
+/// options in `value_flags` (e.g. `--config`). The first item is the binary name.
+pub fn subcommand_index(
+    args: &[std::ffi::OsString],
+    names: &[&str],
+    value_flags: &[String],
+) -> Option<usize> {
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy();
+        if arg == "--" {
+            return None;
+        }
+        if names.contains(&arg.as_ref()) {
+            return Some(i);
+        }
+        if value_flags.iter().any(|flag| *flag == arg) {
+            i += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The arguments of `prog <name> [--] <args>...` as `prog <master> <args>...`, for the
+/// `subcommand` activation. Others are returned as is.
+pub fn activation_args(
+    mut args: Vec<std::ffi::OsString>,
+    name: &str,
+    master: &str,
+) -> Vec<std::ffi::OsString> {
+    if args.get(1).is_none_or(|arg| arg != name) {
+        return args;
+    }
+    let rest = if args.get(2).is_some_and(|arg| arg == "--") {
+        3
+    } else {
+        2
+    };
+    let mut activated = vec![args[0].clone(), master.into()];
+    activated.extend(args.drain(rest..));
+    activated
+}
+
+/// Parses an RFC 3339 timestamp, like `2026-01-31T18:00:00Z` or `2026-01-31 18:00:00+01:00`.
+pub fn parse_rfc3339(text: &str) -> Result<std::time::SystemTime, String> {
+    let invalid = || {
+        format!(
+            "'{}' is not an RFC 3339 time, like 2026-01-31T18:00:00Z",
+            text
+        )
+    };
+    let bytes = text.as_bytes();
+    let number = |range: std::ops::Range<usize>| -> Result<i64, String> {
+        let digits = text.get(range).ok_or_else(invalid)?;
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        digits.parse().map_err(|_| invalid())
+    };
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return Err(invalid());
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(invalid());
+    }
+    // fractional seconds don't matter for a deadline.
+    let mut rest = &text[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return Err(invalid());
+        }
+        rest = &fraction[digits..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let offset_at = text.len() - 5;
+            let hours = number(offset_at..offset_at + 2)?;
+            let minutes = number(offset_at + 3..offset_at + 5)?;
+            match rest.as_bytes()[0] {
+                b'+' => hours * 3600 + minutes * 60,
+                b'-' => -(hours * 3600 + minutes * 60),
+                _ => return Err(invalid()),
+            }
+        }
+        _ => return Err(invalid()),
+    };
+    // days since the epoch of a proleptic Gregorian date, from Howard Hinnant's algorithm.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    let seconds = u64::try_from(seconds).map_err(|_| invalid())?;
+    Ok(std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds))
+}
+
+/// The subsets of at most `max` of the `longs` flags, as argument vectors.
+pub fn combinations(
+    longs: &'static [&'static str],
+    max: usize,
+) -> impl Iterator<Item = Vec<String>> {
+    assert!(longs.len() < 64, "too many clowntown flags to combine");
+    (0..1u64 << longs.len())
+        .filter(move |mask| mask.count_ones() as usize <= max)
+        .map(|mask| {
+            longs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, long)| long.to_string())
+                .collect()
+        })
+}
+
+/// The report entries of the `manifest` flags, given which are `enabled` and the `changes` from
+/// their defaults.
+pub fn report_entries(
+    manifest: &'static [crate::ClownFlagInfo],
+    enabled: &[bool],
+    changes: &[crate::ClowntownChange],
+) -> Vec<crate::ClowntownReportEntry> {
+    manifest
+        .iter()
+        .zip(enabled)
+        .map(|(flag, &enabled)| crate::ClowntownReportEntry {
+            name: flag.name,
+            enabled,
+            source: changes
+                .iter()
+                .find(|change| change.flag.name == flag.name)
+                .map_or(crate::ClowntownSource::Default, |change| {
+                    change.cause.into()
+                }),
+        })
+        .collect()
+}
+
+/// Parses a clowntown flag file into `(index in flags, value)` updates.
+///
+/// Each line is either `flag = true`, `flag = false`, or a bare `flag` meaning true, where
+/// `flag` is a field name or a long flag. Blank lines and `#` comments are ignored. Nothing is
+/// returned unless the whole file is valid.
+pub fn parse_flag_file(contents: &str, flags: &[&str]) -> Result<Vec<(usize, bool)>, String> {
+    let mut updates = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (flag, value) = match line.split_once('=') {
+            Some((flag, value)) => match value.trim() {
+                "true" => (flag.trim(), true),
+                "false" => (flag.trim(), false),
+                value => {
+                    return Err(format!(
+                        "line {}: expected true or false, got '{}'",
+                        number + 1,
+                        value
+                    ));
+                }
+            },
+            None => (line, true),
+        };
+        let index = flag_index(flag, flags)
+            .map_err(|message| format!("line {}: {}", number + 1, message))?;
+        updates.push((index, value));
+    }
+    Ok(updates)
+}
+
+/// Calls `on_change` whenever the file at `path` is written, created or replaced.
+///
+/// The parent directory is watched rather than the file itself, so editors and deployment
+/// tools replacing the file through a rename are noticed too.
+#[cfg(feature = "notify")]
+pub fn watch(
+    path: std::path::PathBuf,
+    mut on_change: impl FnMut() + Send + 'static,
+) -> notify::Result<notify::RecommendedWatcher> {
+    use notify::Watcher;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::path::PathBuf::from("."),
+    };
+    let name = path.file_name().map(|name| name.to_os_string());
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        let relevant = (event.kind.is_create() || event.kind.is_modify())
+            && event
+                .paths
+                .iter()
+                .any(|path| path.file_name().map(|name| name.to_os_string()) == name);
+        if relevant {
+            on_change();
+        }
+    })?;
+    watcher.watch(&dir, notify::RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+/// The audit record for a run turning clowntown flags on through `masters`.
+#[cfg(feature = "syslog")]
+pub fn audit_message(
+    user: &str,
+    argv: &[std::ffi::OsString],
+    masters: &[&str],
+    enabled: &[&str],
+) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for arg in argv {
+        hasher.update(arg.as_encoded_bytes());
+        hasher.update([0]);
+    }
+    let hash: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!(
+        "clowntown: user={} masters={} flags={} argv_sha256={}",
+        user,
+        masters.join(","),
+        enabled.join(","),
+        hash
+    )
+}
+
+/// Writes the audit record of a run turning clowntown flags on through `masters` to the system
+/// log, in the `authpriv` facility. Failing to reach the system log is reported on stderr.
+#[cfg(feature = "syslog")]
+pub fn syslog_audit(argv: &[std::ffi::OsString], masters: &[&str], enabled: &[&str]) {
+    // SAFETY: getuid never fails and has no preconditions.
+    let uid = unsafe { libc::getuid() };
+    let user = match std::env::var("USER").or_else(|_| std::env::var("LOGNAME")) {
+        Ok(name) => format!("{}(uid={})", name, uid),
+        Err(_) => format!("uid={}", uid),
+    };
+    let message = audit_message(&user, argv, masters, enabled);
+    let process = argv
+        .first()
+        .and_then(|arg| std::path::Path::new(arg).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let formatter = syslog::Formatter3164 {
+        facility: syslog::Facility::LOG_AUTHPRIV,
+        hostname: None,
+        process,
+        pid: std::process::id(),
+    };
+    let result = syslog::unix(formatter).and_then(|mut logger| logger.notice(&message));
+    if let Err(err) = result {
+        eprintln!(
+            "warning: failed to write the clowntown audit record to syslog: {}",
+            err
+        );
+    }
+}
+
+/// Checks `token`, given to `master`, against `key` at time `now`. The reason is in the error.
+#[cfg(feature = "token")]
+pub fn verify_token(
+    key: Option<&[u8]>,
+    master: &str,
+    token: &str,
+    now: std::time::SystemTime,
+) -> Result<(), String> {
+    let key = key.ok_or("no token key is configured")?;
+    let (expiry, signature) = token
+        .split_once('.')
+        .and_then(|(expiry, signature)| Some((expiry.parse::<u64>().ok()?, signature)))
+        .ok_or("malformed token")?;
+    let expected = crate::token::hex(&crate::token::signature(key, master, expiry));
+    // compare everything, so the time taken doesn't tell how much of the signature is right.
+    let matches = expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+    if !matches {
+        return Err("invalid token".to_string());
+    }
+    let now = now
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default();
+    if expiry <= now {
+        return Err("expired token".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subcommand_index() {
+        let args =
+            |args: &[&str]| -> Vec<std::ffi::OsString> { args.iter().map(Into::into).collect() };
+        let value_flags = ["--name".to_string(), "-n".to_string()];
+        let index = |argv: &[&str]| subcommand_index(&args(argv), &["run", "r"], &value_flags);
+        assert_eq!(index(&["prog", "--clowncopterize", "run"]), Some(2));
+        assert_eq!(index(&["prog", "--name", "run", "r", "x"]), Some(3));
+        assert_eq!(index(&["prog", "-n", "run", "--name=run", "run"]), Some(4));
+        assert_eq!(index(&["prog", "--", "run"]), None);
+        assert_eq!(index(&["prog", "walk"]), None);
+    }
+
+    #[test]
+    fn test_activation_args() {
+        let args =
+            |args: &[&str]| -> Vec<std::ffi::OsString> { args.iter().map(Into::into).collect() };
+        let activate =
+            |argv: &[&str]| activation_args(args(argv), "clowncopterize", "--clowncopterize");
+        assert_eq!(
+            activate(&["prog", "clowncopterize", "--", "--name", "x"]),
+            args(&["prog", "--clowncopterize", "--name", "x"])
+        );
+        assert_eq!(
+            activate(&["prog", "clowncopterize", "run", "--", "x"]),
+            args(&["prog", "--clowncopterize", "run", "--", "x"])
+        );
+        assert_eq!(
+            activate(&["prog", "clowncopterize"]),
+            args(&["prog", "--clowncopterize"])
+        );
+        assert_eq!(
+            activate(&["prog", "--", "clowncopterize"]),
+            args(&["prog", "--", "clowncopterize"])
+        );
+        assert_eq!(activate(&["prog"]), args(&["prog"]));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("clowntown_db_*", "clowntown_db_drop"));
+        assert!(glob_match("*_drop", "clowntown_db_drop"));
+        assert!(glob_match("clowntown_d?_*p", "clowntown_db_drop"));
+        assert!(glob_match("clowntown_this", "clowntown_this"));
+        assert!(glob_match("*", "clowntown_this"));
+        assert!(!glob_match("clowntown_db_*", "clowntown_net_drop"));
+        assert!(!glob_match("clowntown_thi", "clowntown_this"));
+        assert!(!glob_match("clowntown_this?", "clowntown_this"));
+    }
+
+    #[test]
+    fn test_glob_select() {
+        let flags = [
+            "clowntown_db_drop",
+            "clowntown_db_truncate",
+            "clowntown_net",
+        ];
+        let longs = ["--clowntown-db-drop", "--clowntown-db-truncate", "--net"];
+        let selected = glob_select(&["--clowntown-db-*".to_string()], &flags, &longs).unwrap();
+        assert_eq!(selected, vec![true, true, false]);
+        assert!(glob_select(&["clowntown_nope".to_string()], &flags, &longs).is_err());
+
+        let patterns = ["db-dr".to_string(), "net".to_string()];
+        let selected = glob_select(&patterns, &flags, &longs).unwrap();
+        assert_eq!(selected, vec![true, false, true]);
+        let err = glob_select(&["--clowntown-db".to_string()], &flags, &longs).unwrap_err();
+        assert!(err.contains("ambiguous"), "{}", err);
+        assert!(glob_select(&["db_x*".to_string()], &flags, &longs).is_err());
+
+        // the long flag, when it isn't derived from the field name.
+        let selected = glob_select(&["--ne?".to_string()], &flags, &longs).unwrap();
+        assert_eq!(selected, vec![false, false, true]);
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let at = |seconds| Ok(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), at(0));
+        assert_eq!(parse_rfc3339("2026-01-31T18:00:00Z"), at(1_769_882_400));
+        assert_eq!(
+            parse_rfc3339("2026-01-31 19:00:00.250+01:00"),
+            at(1_769_882_400)
+        );
+        assert_eq!(
+            parse_rfc3339("2024-02-29T12:30:00-05:30"),
+            at(1_709_229_600)
+        );
+        assert!(parse_rfc3339("2026-01-31").is_err());
+        assert!(parse_rfc3339("2026-13-01T00:00:00Z").is_err());
+        assert!(parse_rfc3339("2026-01-31T18:00:00").is_err());
+        assert!(parse_rfc3339("2026-01-31T18:00:00+0100").is_err());
+    }
+
+    #[test]
+    fn test_combinations() {
+        let longs = &["--a", "--b", "--c"];
+        assert_eq!(combinations(longs, usize::MAX).count(), 8);
+        let pairs: Vec<Vec<String>> = combinations(longs, 1).collect();
+        assert_eq!(pairs, vec![vec![], vec!["--a"], vec!["--b"], vec!["--c"]]);
+    }
+
+    #[test]
+    fn test_parse_flag_file() {
+        let flags = ["clowntown_db_drop", "clowntown_net"];
+        let contents =
+            "# flipped during the incident\nclowntown_db_drop = false\n\n--clowntown-net\n";
+        assert_eq!(
+            parse_flag_file(contents, &flags),
+            Ok(vec![(0, false), (1, true)])
+        );
+        assert!(parse_flag_file("clowntown_net = maybe", &flags).is_err());
+        assert!(parse_flag_file("clowntown_nope", &flags).is_err());
+    }
+
+    #[cfg(feature = "syslog")]
+    #[test]
+    fn test_audit_message() {
+        let argv = ["prog".into(), "--clowncopterize".into()];
+        let message = audit_message("ops", &argv, &["--clowncopterize"], &["--clowntown-this"]);
+        assert!(message.starts_with(
+            "clowntown: user=ops masters=--clowncopterize flags=--clowntown-this argv_sha256="
+        ));
+        assert_ne!(message, audit_message("ops", &argv[..1], &[], &[]));
+    }
+
+    #[cfg(feature = "token")]
+    #[test]
+    fn test_verify_token() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+        let token =
+            crate::clowntown_token(b"secret", "--clowncopterize", now + Duration::from_secs(60));
+        assert_eq!(
+            verify_token(Some(b"secret"), "--clowncopterize", &token, now),
+            Ok(())
+        );
+        assert_eq!(
+            verify_token(
+                Some(b"secret"),
+                "--clowncopterize",
+                &token,
+                now + Duration::from_secs(60)
+            ),
+            Err("expired token".to_string())
+        );
+        assert!(verify_token(Some(b"other"), "--clowncopterize", &token, now).is_err());
+        assert!(verify_token(Some(b"secret"), "--clowncopterize-db", &token, now).is_err());
+        assert!(verify_token(None, "--clowncopterize", &token, now).is_err());
+        assert!(verify_token(Some(b"secret"), "--clowncopterize", "", now).is_err());
+        let long_key = [7u8; 100];
+        let token =
+            crate::clowntown_token(&long_key, "--clowncopterize", now + Duration::from_secs(1));
+        assert_eq!(
+            verify_token(Some(&long_key), "--clowncopterize", &token, now),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_flag_index() {
+        let flags = ["clowntown_db_drop", "clowntown_net"];
+        assert_eq!(flag_index("clowntown_net", &flags), Ok(1));
+        assert_eq!(flag_index("--clowntown-db-drop", &flags), Ok(0));
+        assert!(flag_index("clowntown_db_*", &flags).is_err());
+    }
+
+    #[test]
+    fn test_json_str() {
+        assert_eq!(json_str("CLOWNTOWN_THIS"), "\"CLOWNTOWN_THIS\"");
+        assert_eq!(json_str("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+}
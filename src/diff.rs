@@ -0,0 +1,26 @@
+//! What a run changed from the declared defaults, for change-management records.
+
+use crate::ClownFlagInfo;
+
+/// A clowntown flag whose value differs from its declared default, from the generated
+/// `clowntown_diff`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClowntownChange {
+    /// The flag.
+    pub flag: ClownFlagInfo,
+    /// Its declared default.
+    pub default: bool,
+    /// Its value.
+    pub value: bool,
+    /// Why it changed.
+    pub cause: ClowntownCause,
+}
+
+/// Why a clowntown flag differs from its declared default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClowntownCause {
+    /// An injected flag set it, e.g. `--clowncopterize` or `--un-clowncopterize`.
+    Master(&'static str),
+    /// It was set on its own: on the command line, from the environment or from a config.
+    Explicit,
+}
@@ -0,0 +1,127 @@
+//! A type for clowntown flags, so they don't have to follow a naming convention.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+use crate::{ClownFlagInfo, ClowntownSeverity};
+
+/// A clowntown flag, wired by `#[clowncopterize]` because of its type, whatever its name.
+///
+/// It parses like a `bool` flag: `--risky-thing` turns it on, and the masters turn it on with
+/// the `bool` clowntown flags. Declare an `#[arg(default_value = "true")]` for flags that default
+/// to on.
+///
+/// It dereferences to its value, so `if *cli.drop_database` reads like a `bool`, and carries the
+/// flag's [`ClownFlagInfo`] once parsed, e.g. for logging who owns the flag being used.
+///
+/// ```ignore
+/// #[clowncopterize::clowncopterize]
+/// #[derive(Parser, Debug)]
+/// struct Cli {
+///     #[arg(long)]
+///     #[clowntown(owner = "storage")]
+///     drop_database: ClownFlag,
+/// }
+///
+/// let cli = Cli::parse();
+/// if *cli.drop_database {
+///     log::warn!("dropping the database, ask {:?}", cli.drop_database.owner());
+/// }
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct ClownFlag(pub bool, Option<&'static ClownFlagInfo>);
+
+impl ClownFlag {
+    /// A flag with the metadata of `info`, as the generated parsers build them.
+    pub fn new(on: bool, info: Option<&'static ClownFlagInfo>) -> Self {
+        Self(on, info)
+    }
+
+    /// Whether the flag is on.
+    pub fn is_on(self) -> bool {
+        self.0
+    }
+
+    /// What `#[clowncopterize]` knows about the flag, when it was parsed.
+    pub fn info(&self) -> Option<&'static ClownFlagInfo> {
+        self.1
+    }
+
+    /// Who owns the flag, from `#[clowntown(owner = "...")]`.
+    pub fn owner(&self) -> Option<&'static str> {
+        self.1.and_then(|info| info.owner)
+    }
+
+    /// How risky the flag is, from `#[clowntown(severity = "...")]`.
+    pub fn level(&self) -> Option<ClowntownSeverity> {
+        self.1.and_then(|info| info.level)
+    }
+}
+
+impl Deref for ClownFlag {
+    type Target = bool;
+
+    fn deref(&self) -> &bool {
+        &self.0
+    }
+}
+
+impl DerefMut for ClownFlag {
+    fn deref_mut(&mut self) -> &mut bool {
+        &mut self.0
+    }
+}
+
+// the metadata describes the flag, it isn't part of its value.
+impl fmt::Debug for ClownFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ClownFlag").field(&self.0).finish()
+    }
+}
+
+impl PartialEq for ClownFlag {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ClownFlag {}
+
+impl Hash for ClownFlag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl From<bool> for ClownFlag {
+    fn from(on: bool) -> Self {
+        Self(on, None)
+    }
+}
+
+impl From<ClownFlag> for bool {
+    fn from(flag: ClownFlag) -> Self {
+        flag.0
+    }
+}
+
+impl PartialEq<bool> for ClownFlag {
+    fn eq(&self, other: &bool) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ClownFlag {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bool(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ClownFlag {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        bool::deserialize(deserializer).map(Self::from)
+    }
+}
@@ -0,0 +1,37 @@
+//! Letting the program refuse the master flags, e.g. outside of canary hosts.
+
+/// What a `guard` function gets to decide whether to honor a master flag.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ClowntownGuardContext {
+    /// The master flag, e.g. `--clowncopterize`.
+    pub master: &'static str,
+    /// The clowntown flags it turns on, by field name.
+    pub flags: &'static [&'static str],
+    /// The name of the host, when we can tell.
+    pub hostname: Option<String>,
+    /// The value of the variable named by `guard(env = "...")`, when given and set.
+    pub env: Option<String>,
+}
+
+impl ClowntownGuardContext {
+    #[doc(hidden)]
+    pub fn new(master: &'static str, flags: &'static [&'static str], env: Option<&str>) -> Self {
+        ClowntownGuardContext {
+            master,
+            flags,
+            hostname: hostname(),
+            env: env.and_then(|name| std::env::var(name).ok()),
+        }
+    }
+}
+
+/// The host name, from the kernel on Linux, else from the usual environment variables.
+fn hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .filter(|name| !name.is_empty())
+}
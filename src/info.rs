@@ -0,0 +1,68 @@
+//! Metadata about clowntown flags, for tooling.
+
+/// A clowntown flag and what `#[clowncopterize]` knows about it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClownFlagInfo {
+    /// The field name, e.g. `clowntown_this`.
+    pub name: &'static str,
+    /// The long flag, e.g. `--clowntown-this`.
+    pub long: &'static str,
+    /// The struct declaring the flag.
+    pub struct_name: &'static str,
+    /// The module declaring the struct.
+    pub module_path: &'static str,
+    /// The first paragraph of the flag's doc comment.
+    pub help: &'static str,
+    /// Who owns the flag, from `#[clowntown(owner = "...")]`.
+    pub owner: Option<&'static str>,
+    /// How risky the flag is, from `#[clowntown(severity = "...")]`.
+    pub level: Option<ClowntownSeverity>,
+    /// The version that introduced the flag, from `#[clowntown(since = "...")]`.
+    pub since: Option<&'static str>,
+    /// When the flag should go away, from `#[clowntown(expires = "...")]`.
+    pub expires: Option<&'static str>,
+    /// The flag's runbook, from `#[clowntown(link = "...")]`.
+    pub link: Option<&'static str>,
+}
+
+/// How much damage a clowntown flag can do, from `#[clowntown(severity = "low" | "high")]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ClowntownSeverity {
+    /// Mildly risky.
+    Low,
+    /// Catastrophic when misused.
+    High,
+}
+
+/// Implemented by every `#[clowncopterize]` struct, `Parser` or `Args`, so a parent can find the
+/// clowntown flags of the structs it flattens.
+///
+/// Mark the flattened fields with `#[clowntown(nested)]` for the parent to report their flags
+/// along its own.
+pub trait HasClowntownFlags {
+    /// The metadata of the clowntown flags, followed by the nested structs' ones.
+    fn clowntown_flags() -> Vec<ClownFlagInfo>;
+
+    /// The names of the clowntown flags that are on, including the nested structs' ones.
+    fn enabled_clowntown_flags(&self) -> Vec<&'static str>;
+}
+
+/// Takes clowntown flag values by name, for the generated `apply_clowntown_to` to copy them in.
+///
+/// Every `#[clowncopterize]` struct implements it, nested structs' flags included; implement it
+/// by hand on other types, such as a settings struct, to fill them from the command line.
+pub trait Clowncopterized {
+    /// Sets the clowntown flag `name` to `value`, returning whether there is such a flag.
+    fn set_clowntown_flag(&mut self, name: &str, value: bool) -> bool;
+}
+
+#[cfg(feature = "inventory")]
+inventory::collect!(ClownFlagInfo);
+
+/// Every clowntown flag linked into the process, whichever crate declares it.
+///
+/// Needs the `inventory` feature, which registers the flags of every `#[clowncopterize]` struct.
+#[cfg(feature = "inventory")]
+pub fn registered_clown_flags() -> impl Iterator<Item = &'static ClownFlagInfo> {
+    inventory::iter::<ClownFlagInfo>.into_iter()
+}
@@ -39,179 +39,33 @@
 //! ```
 //!
 
-use proc_macro::TokenStream;
-use proc_macro2::{Ident, Span};
-use quote::ToTokens;
+pub use clowncopterize_macros::{
+    ClowntownIntrospect, assert_covers, assert_has_flag, assert_max_flags, clowncopterize,
+    clowncopterize_test,
+};
+pub use diff::{ClowntownCause, ClowntownChange};
+pub use flag::ClownFlag;
+pub use guard::ClowntownGuardContext;
+#[cfg(feature = "inventory")]
+pub use info::registered_clown_flags;
+pub use info::{ClownFlagInfo, Clowncopterized, ClowntownSeverity, HasClowntownFlags};
+pub use overrides::ClowntownOverrides;
+pub use report::{ClowntownReport, ClowntownReportEntry, ClowntownSource};
+pub use resolver::{AsyncClowntownResolver, ClowntownDecision, ClowntownResolver};
+pub use sink::ClowntownSink;
+#[cfg(feature = "token")]
+pub use token::clowntown_token;
 
-const CLOWNCOPTERIZE_PREFIX: &str = "clowntown";
-const CLOWNCOPTERIZE_FLAG: &str = "clowncopterize";
+mod diff;
+mod flag;
+mod guard;
+mod info;
+mod overrides;
+mod report;
+mod resolver;
+mod sink;
+#[cfg(feature = "token")]
+mod token;
 
-// used to parse Named Fields from a TokenStream so we can inject our ew --clowncopterize parameter
-// https://github.com/dtolnay/syn/issues/651#issuecomment-503771863
-struct ParsableNamedField {
-    pub field: syn::Field,
-}
-
-impl syn::parse::Parse for ParsableNamedField {
-    fn parse(input: syn::parse::ParseStream<'_>) -> syn::parse::Result<Self> {
-        let field = syn::Field::parse_named(input)?;
-
-        Ok(ParsableNamedField { field })
-    }
-}
-
-/// Clowncopterize the underlying clap parser
-///
-/// # Example
-///
-/// ```
-/// use clap::Parser;
-///
-/// // by default, uses `--clowncopterize` flag:
-///
-/// #[clowncopterize::clowncopterize]
-/// #[derive(Parser, Debug)]
-/// struct Cli {
-///     /// Optional name to operate on
-///     name: Option<String>,
-///
-///     /// Turn debugging information on
-///     #[arg(long)]
-///     clowntown_this: bool,
-///
-///     /// lists test values
-///     #[arg(long)]
-///     clowntown_that: bool,
-/// }
-///
-///
-/// let cli = Cli::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
-///
-/// println!("Cli! {:#?}", cli);
-/// assert!(cli.clowntown_this);
-///
-/// // but can be customized with the `clowncopterizer` attribute:
-///
-/// #[clowncopterize::clowncopterize(clowncopterizer = "i-live-in-clowntown")]
-/// #[derive(Parser, Debug)]
-/// struct CliCustom {
-///     /// Optional name to operate on
-///     name: Option<String>,
-///
-///     /// Turn debugging information on
-///     #[arg(long)]
-///     clowntown_this: bool,
-///
-///     /// lists test values
-///     #[arg(long)]
-///     clowntown_that: bool,
-/// }
-///
-///
-/// let cli = CliCustom::try_parse_from(vec!["prog", "--i-live-in-clowntown"]).unwrap();
-///
-/// println!("Cli! {:#?}", cli);
-/// assert!(cli.clowntown_this);
-/// ```
-#[proc_macro_attribute]
-pub fn clowncopterize(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let clowncopterizer = syn::parse_macro_input!(attr as Clowncopterize);
-
-    let item_struct: syn::ItemStruct = syn::parse_macro_input!(item);
-    let out = clowncopterizer.clowncopterize_struct(item_struct);
-    proc_macro::TokenStream::from(out.to_token_stream())
-}
-
-#[derive(Debug)]
-struct Clowncopterize {
-    clowncopterizer: String,
-}
-
-impl Default for Clowncopterize {
-    fn default() -> Self {
-        Clowncopterize {
-            clowncopterizer: CLOWNCOPTERIZE_FLAG.to_string(),
-        }
-    }
-}
-
-impl syn::parse::Parse for Clowncopterize {
-    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
-        if input.is_empty() {
-            return Ok(Clowncopterize {
-                ..Default::default()
-            });
-        }
-        let attr_name = input.parse::<syn::Ident>()?;
-        assert_eq!(
-            attr_name, "clowncopterizer",
-            "Unexpected attribute {}",
-            attr_name
-        );
-        input.parse::<syn::Token![=]>()?;
-        let attr_value = input.parse::<syn::LitStr>()?;
-
-        Ok(Clowncopterize {
-            clowncopterizer: attr_value.value().replace("-", "_"),
-        })
-    }
-}
-
-impl Clowncopterize {
-    fn clowncopterize_struct(&self, mut ast: syn::ItemStruct) -> syn::ItemStruct {
-        let mut is_clown = false;
-        if let syn::Fields::Named(ref mut fields) = ast.fields {
-            // iterate over each fields and modify any fields that start with `clowntown` and is a boolean.
-            fields.named =
-                syn::punctuated::Punctuated::from_iter(fields.named.iter_mut().map(|field| {
-                    if let syn::Type::Path(type_path) = &field.ty {
-                        if type_path.path.is_ident("bool") {
-                            if let Some(ref ident) = field.ident {
-                                if ident.to_string().starts_with(CLOWNCOPTERIZE_PREFIX) {
-                                    is_clown = true;
-                                    return self.clowncopterize_field(field);
-                                }
-                            }
-                        }
-                    }
-                    field.clone()
-                }));
-            // There is at least 1 clowntown flag, add our clowncopterize flag.
-            if is_clown {
-                let clowncopterizer = Ident::new(&self.clowncopterizer, Span::call_site());
-                let punctuated_fields: syn::punctuated::Punctuated<
-                    ParsableNamedField,
-                    syn::Token![,],
-                > = syn::parse_quote! {
-                    /// Turns all the clowntown flags on
-                    #[arg(long)]
-                    #clowncopterizer: bool
-                };
-                for punctuated_field in punctuated_fields {
-                    fields.named.push(punctuated_field.field);
-                }
-            }
-        }
-        ast
-    }
-
-    fn clowncopterize_field(&self, ast: &mut syn::Field) -> syn::Field {
-        for attr in ast.attrs.iter_mut() {
-            if attr.path().is_ident("arg") {
-                let meta = attr.meta.require_list().unwrap();
-                let mut tokens = meta.tokens.clone();
-                let clowncopterizer = &self.clowncopterizer;
-                let ext = quote::quote! {
-                    , default_value_if(#clowncopterizer, "true", "true")
-                };
-                tokens.extend(ext);
-                attr.meta = syn::Meta::List(syn::MetaList {
-                    path: meta.path.clone(),
-                    delimiter: meta.delimiter.clone(),
-                    tokens,
-                });
-            }
-        }
-        ast.clone()
-    }
-}
+#[doc(hidden)]
+pub mod __private;
@@ -0,0 +1,72 @@
+//! Clowntown flag values coming from somewhere else than the command line.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Clowntown flag values, keyed by field name (`clowntown_this`) or long flag
+/// (`--clowntown-this`), e.g. read from a configuration file.
+///
+/// Structs annotated with `#[clowncopterize]` merge them with the generated `merge_from_config`,
+/// which leaves the flags set on the command line alone. Configuration types can implement
+/// `Into<ClowntownOverrides>`, or, with the `serde` feature, embed it directly as a table of
+/// booleans.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(transparent)
+)]
+pub struct ClowntownOverrides {
+    flags: BTreeMap<String, bool>,
+}
+
+impl ClowntownOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `flag` to `value`.
+    pub fn set(mut self, flag: impl Into<String>, value: bool) -> Self {
+        self.flags.insert(flag.into(), value);
+        self
+    }
+
+    /// The flags and their value, in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.flags
+            .iter()
+            .map(|(flag, value)| (flag.as_str(), *value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, bool)> for ClowntownOverrides {
+    fn from_iter<I: IntoIterator<Item = (K, bool)>>(iter: I) -> Self {
+        Self {
+            flags: iter
+                .into_iter()
+                .map(|(flag, value)| (flag.into(), value))
+                .collect(),
+        }
+    }
+}
+
+impl From<BTreeMap<String, bool>> for ClowntownOverrides {
+    fn from(flags: BTreeMap<String, bool>) -> Self {
+        Self { flags }
+    }
+}
+
+impl From<HashMap<String, bool>> for ClowntownOverrides {
+    fn from(flags: HashMap<String, bool>) -> Self {
+        flags.into_iter().collect()
+    }
+}
+
+impl<K: Into<String>, const N: usize> From<[(K, bool); N]> for ClowntownOverrides {
+    fn from(flags: [(K, bool); N]) -> Self {
+        flags.into_iter().collect()
+    }
+}
@@ -0,0 +1,57 @@
+//! A struct-independent view of the clowntown flags, for reporting code shared by binaries.
+
+use crate::ClowntownCause;
+
+/// Every clowntown flag of a `#[clowncopterize]` struct, whether it is on, and why.
+///
+/// Every such struct converts into it with `From<&Cli>`, nested structs' flags included, so
+/// reporting code can be written once against this type.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClowntownReport {
+    /// The flags, in declaration order.
+    pub flags: Vec<ClowntownReportEntry>,
+}
+
+impl ClowntownReport {
+    /// The names of the flags that are on.
+    pub fn enabled(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.flags
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.name)
+    }
+}
+
+/// A clowntown flag in a [`ClowntownReport`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClowntownReportEntry {
+    /// The field name.
+    pub name: &'static str,
+    /// Whether it is on, its risky value set.
+    pub enabled: bool,
+    /// Where its value comes from.
+    pub source: ClowntownSource,
+}
+
+/// Where the value of a clowntown flag comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ClowntownSource {
+    /// It has its declared default.
+    Default,
+    /// An injected flag set it, e.g. `--clowncopterize` or `--un-clowncopterize`.
+    Master(&'static str),
+    /// It was set on its own: on the command line, from the environment or from a config.
+    Explicit,
+}
+
+impl From<ClowntownCause> for ClowntownSource {
+    fn from(cause: ClowntownCause) -> Self {
+        match cause {
+            ClowntownCause::Master(long) => ClowntownSource::Master(long),
+            ClowntownCause::Explicit => ClowntownSource::Explicit,
+        }
+    }
+}
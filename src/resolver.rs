@@ -0,0 +1,36 @@
+//! Deferring the final say on clowntown flags to something else than the command line.
+
+use std::future::Future;
+
+/// What a [`ClowntownResolver`] decides for a clowntown flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClowntownDecision {
+    /// Leave the flag as the command line set it.
+    Keep,
+    /// Turn the flag off, i.e. set it to its safe value.
+    Veto,
+    /// Turn the flag on, i.e. set it to the value its master sets.
+    Force,
+}
+
+/// Decides the clowntown flags at startup, e.g. by asking a central feature-flag service.
+///
+/// Structs annotated with `#[clowncopterize]` consult it with the generated `apply_resolver`, or
+/// the `clowntown_parse_with` and `clowntown_try_parse_from_with` constructors.
+pub trait ClowntownResolver {
+    /// Decides `flag`, a field name, currently set to `enabled` by the command line.
+    fn resolve(&self, flag: &str, enabled: bool) -> ClowntownDecision;
+}
+
+impl<F: Fn(&str, bool) -> ClowntownDecision> ClowntownResolver for F {
+    fn resolve(&self, flag: &str, enabled: bool) -> ClowntownDecision {
+        self(flag, enabled)
+    }
+}
+
+/// The async flavor of [`ClowntownResolver`], consulted with the generated
+/// `apply_resolver_async`.
+pub trait AsyncClowntownResolver {
+    /// Decides `flag`, a field name, currently set to `enabled` by the command line.
+    fn resolve(&self, flag: &str, enabled: bool) -> impl Future<Output = ClowntownDecision> + Send;
+}
@@ -0,0 +1,22 @@
+//! Reporting the clowntown flags in use to any metrics backend.
+
+use crate::ClownFlagInfo;
+
+/// Receives the master flags and clowntown flags in use after parsing, e.g. to count them.
+///
+/// Structs annotated with `#[clowncopterize(sink = "...")]` report to it from the
+/// `clowntown_parse` constructors, and to any sink with the generated `record_clowntown_to`.
+pub trait ClowntownSink {
+    /// Called for each clowntown flag that is on.
+    fn flag_enabled(&self, flag: &ClownFlagInfo);
+
+    /// Called for each master flag that is on, e.g. `--clowncopterize`, before its flags.
+    fn master_used(&self, master: &str) {
+        let _ = master;
+    }
+
+    /// Called when the shadow flag records the intent to use `master`, which turned nothing on.
+    fn master_shadowed(&self, master: &str) {
+        let _ = master;
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::bool_assert_comparison)]
+
 use clap::Parser;
 
 #[clowncopterize::clowncopterize]
@@ -46,6 +48,28 @@ fn test_set_all_to_true() {
     assert_eq!(cli.clowncopterize, true);
 }
 
+#[clowncopterize::clowncopterize(
+    runtime_max = 1,
+    shadow,
+    report = "stderr",
+    external(in = "dep::ExternalArgs")
+)]
+#[derive(Parser, Debug)]
+struct CliNoClowns {
+    #[command(flatten)]
+    ext: dep::ExternalArgs,
+
+    #[arg(long)]
+    verbose: bool,
+}
+
+#[test]
+fn test_no_clowntown_flags() {
+    let cli = CliNoClowns::try_parse_from(["test", "--verbose"]).unwrap();
+    assert!(cli.verbose);
+    assert!(CliNoClowns::CLOWNTOWN_FLAGS.is_empty());
+}
+
 #[test]
 fn test_set_all_to_true_custom_flag() {
     let cli = CliCustomFlag::try_parse_from(vec!["prog", "--i-live-in-clowntown"]).unwrap();
@@ -53,3 +77,3415 @@ fn test_set_all_to_true_custom_flag() {
     assert_eq!(cli.clowntown_that, true);
     assert_eq!(cli.i_live_in_clowntown, true);
 }
+
+#[clowncopterize::clowncopterize(clowncopterizer = i_live_in_clowntown, unclowncopterizer = "--out-of-clowntown")]
+#[derive(Parser, Debug)]
+struct CliIdentFlag {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_ident_and_long_flag_names() {
+    let cli = CliIdentFlag::try_parse_from(["prog", "--i-live-in-clowntown"]).unwrap();
+    assert!(cli.clowntown_this);
+    let cli =
+        CliIdentFlag::clowntown_try_parse_from(["prog", "--clowntown-this", "--out-of-clowntown"])
+            .unwrap();
+    assert!(!cli.clowntown_this);
+    assert!(cli.out_of_clowntown);
+}
+
+#[clowncopterize::clowncopterize(
+    clowncopterizer = "I-Live-In-Clowntown",
+    shadow = "clowntownShadow"
+)]
+#[derive(Parser, Debug)]
+struct CliNormalizedNames {
+    #[arg(long)]
+    #[clowntown(group = "DB")]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_normalized_flag_names() {
+    let cli = CliNormalizedNames::try_parse_from(["prog", "--i-live-in-clowntown"]).unwrap();
+    assert!(cli.i_live_in_clowntown && cli.clowntown_this);
+    let cli = CliNormalizedNames::try_parse_from(["prog", "--i-live-in-clowntown-db"]).unwrap();
+    assert!(cli.i_live_in_clowntown_db && cli.clowntown_this);
+    let cli = CliNormalizedNames::try_parse_from(["prog", "--clowntown-shadow"]).unwrap();
+    assert!(cli.clowntown_shadow && !cli.clowntown_this);
+}
+
+#[clowncopterize::clowncopterize(
+    clowncopterizer = "danger-zone",
+    prefix = "danger",
+    hidden = true,
+    global = true
+)]
+#[derive(Parser, Debug)]
+struct CliPrefix {
+    #[arg(long)]
+    danger_drop_db: bool,
+
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_prefix_hidden_global() {
+    use clap::CommandFactory;
+
+    assert_eq!(CliPrefix::CLOWNTOWN_FLAGS, &["danger_drop_db"]);
+    let cli = CliPrefix::try_parse_from(["prog", "--danger-zone"]).unwrap();
+    assert!(cli.danger_drop_db);
+    assert!(!cli.clowntown_this);
+
+    let command = CliPrefix::command();
+    let master = command
+        .get_arguments()
+        .find(|arg| arg.get_id() == "danger_zone")
+        .unwrap();
+    assert!(master.is_hide_set());
+    assert!(master.is_global_set());
+    assert!(
+        !CliPrefix::command()
+            .render_help()
+            .to_string()
+            .contains("--danger-zone")
+    );
+}
+
+#[clowncopterize::clowncopterize(long = "all-the-clowns")]
+#[derive(Parser, Debug)]
+struct CliCustomLong {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_custom_long() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let cli = CliCustomLong::clowntown_try_parse_from(["prog", "--all-the-clowns"]).unwrap();
+    assert!(cli.clowncopterize && cli.clowntown_this);
+    assert!(CliCustomLong::try_parse_from(["prog", "--clowncopterize"]).is_err());
+
+    let matches = CliCustomLong::command().get_matches_from(["prog", "--all-the-clowns"]);
+    let cli = CliCustomLong::from_arg_matches(&matches).unwrap();
+    assert_eq!(
+        cli.clowntown_explain(&matches, "clowntown_this").unwrap(),
+        "enabled because --all-the-clowns was passed"
+    );
+}
+
+mod visible {
+    use clap::Parser;
+
+    #[clowncopterize::clowncopterize(vis = "pub", unclowncopterizer)]
+    #[derive(Parser, Debug)]
+    pub struct CliVis {
+        #[arg(long)]
+        pub clowntown_this: bool,
+    }
+
+    #[clowncopterize::clowncopterize(vis = "inherit")]
+    #[derive(Parser, Debug)]
+    pub(crate) struct CliVisInherit {
+        #[arg(long)]
+        pub(crate) clowntown_this: bool,
+    }
+}
+
+#[test]
+fn test_injected_vis() {
+    let cli = visible::CliVis {
+        clowntown_this: true,
+        clowncopterize: false,
+        un_clowncopterize: false,
+    };
+    assert!(cli.clowntown_this);
+    let cli = visible::CliVisInherit {
+        clowntown_this: false,
+        clowncopterize: true,
+    };
+    assert!(cli.clowncopterize);
+}
+
+#[clowncopterize::clowncopterize(
+    long_help = "Turns every clowntown flag on.\n\nThis includes:\n  - dropping the database\n  - skipping the cache"
+)]
+#[derive(Parser, Debug)]
+struct CliLongHelp {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_master_long_help() {
+    use clap::CommandFactory;
+
+    let long_help = CliLongHelp::command().render_long_help().to_string();
+    assert!(long_help.contains("This includes:\n"));
+    assert!(long_help.contains("  - dropping the database\n"));
+    let help = CliLongHelp::command().render_help().to_string();
+    assert!(help.contains("Turns all the clowntown flags on"));
+    assert!(!help.contains("This includes"));
+}
+
+mod verbose {
+    // verbose mode reports through the deprecation lint.
+    #![allow(deprecated)]
+
+    use clap::Parser;
+
+    #[clowncopterize::clowncopterize(clowncopterizer = "i-live-in-clowntown", verbose)]
+    #[derive(Parser, Debug)]
+    pub struct CliVerbose {
+        /// Optional name to operate on
+        pub name: Option<String>,
+
+        /// Turn debugging information on
+        #[arg(long)]
+        pub clowntown_this: bool,
+
+        /// Not a boolean, left alone
+        #[arg(long)]
+        pub clowntown_level: Option<u8>,
+    }
+
+    #[test]
+    fn test_verbose_expansion_unchanged() {
+        let cli = CliVerbose::try_parse_from(vec!["prog", "--i-live-in-clowntown"]).unwrap();
+        assert_eq!(cli.clowntown_this, true);
+        assert_eq!(cli.clowntown_level, None);
+        assert_eq!(cli.i_live_in_clowntown, true);
+    }
+}
+
+mod warn_above {
+    // past the threshold, the macro warns through the deprecation lint.
+    #![allow(deprecated)]
+
+    use clap::Parser;
+
+    #[clowncopterize::clowncopterize(warn_above = 1)]
+    #[derive(Parser, Debug)]
+    pub struct CliWarnAbove {
+        #[arg(long)]
+        pub clowntown_this: bool,
+
+        #[arg(long)]
+        pub clowntown_that: bool,
+    }
+
+    #[test]
+    fn test_warn_above_still_builds() {
+        let cli = CliWarnAbove::try_parse_from(["prog", "--clowncopterize"]).unwrap();
+        assert!(cli.clowntown_this && cli.clowntown_that);
+    }
+}
+
+// The macro generates its own sanity tests for this one.
+#[clowncopterize::clowncopterize(generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliGeneratedTests {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// lists test values
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[cfg(test)]
+mod clown_matrix {
+    use super::*;
+
+    #[clowncopterize::clowncopterize_test(Cli)]
+    fn cli_flags(cli: &Cli) {
+        assert_eq!(cli.name, None);
+    }
+
+    #[clowncopterize::clowncopterize_test(CliCustomFlag)]
+    fn custom_flags(_cli: &CliCustomFlag) {}
+}
+
+clowncopterize::assert_covers!(Cli, ["clowntown_that", "clowntown_this"]);
+clowncopterize::assert_max_flags!(Cli, 2);
+clowncopterize::assert_has_flag!(Cli, "--clowntown-this");
+
+#[test]
+fn test_clowntown_flags_const() {
+    assert_eq!(Cli::CLOWNTOWN_FLAGS, &["clowntown_this", "clowntown_that"]);
+    assert_eq!(verbose::CliVerbose::CLOWNTOWN_FLAGS, &["clowntown_this"]);
+}
+
+#[clowncopterize::clowncopterize(generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliMasters {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Drop the database
+    #[arg(long)]
+    #[clowntown(master = "db-clowncopterize")]
+    clowntown_drop_db: bool,
+
+    /// Truncate the database
+    #[arg(long)]
+    #[clowntown(master = "db_clowncopterize")]
+    clowntown_truncate_db: bool,
+
+    /// Wipe the network
+    #[arg(long)]
+    #[clowntown(master = "net_clowncopterize")]
+    clowntown_wipe_net: bool,
+
+    /// Declared by hand rather than injected
+    #[arg(long)]
+    net_clowncopterize: bool,
+}
+
+#[test]
+fn test_alternate_master() {
+    let cli = CliMasters::try_parse_from(vec!["prog", "--db-clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_drop_db, true);
+    assert_eq!(cli.clowntown_truncate_db, true);
+    assert_eq!(cli.clowntown_wipe_net, false);
+    assert_eq!(cli.clowncopterize, false);
+
+    let cli = CliMasters::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_drop_db, false);
+    assert_eq!(cli.clowntown_wipe_net, false);
+
+    let cli = CliMasters::try_parse_from(vec!["prog", "--net-clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_wipe_net, true);
+    assert_eq!(cli.net_clowncopterize, true);
+    assert_eq!(cli.db_clowncopterize, false);
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+#[command(group(clap::ArgGroup::new("mode").required(true).args(["clowntown_this", "safe"])))]
+struct CliGroups {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// lists test values
+    #[arg(long, group = "output")]
+    clowntown_that: bool,
+
+    /// Play it safe
+    #[arg(long)]
+    safe: bool,
+
+    /// Quiet output
+    #[arg(long, group = "output")]
+    quiet: bool,
+}
+
+#[test]
+fn test_master_satisfies_required_group() {
+    let cli = CliGroups::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, true);
+    assert_eq!(cli.safe, false);
+
+    let err = CliGroups::try_parse_from(vec!["prog"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+}
+
+#[test]
+fn test_master_conflicts_within_exclusive_group() {
+    let err = CliGroups::try_parse_from(vec!["prog", "--clowncopterize", "--safe"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+    let err = CliGroups::try_parse_from(vec!["prog", "--clowncopterize", "--quiet"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[clowncopterize::clowncopterize(generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliInvert {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Skip the safety checks
+    #[arg(long = "clowntown-no-checks", action = clap::ArgAction::SetFalse)]
+    #[clowntown(invert)]
+    clowntown_checks: bool,
+}
+
+#[test]
+fn test_invert() {
+    let cli = CliInvert::try_parse_from(vec!["prog"]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_checks, true);
+
+    let cli = CliInvert::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_checks, false);
+}
+
+#[cfg(test)]
+mod clown_matrix_invert {
+    use super::*;
+
+    #[clowncopterize::clowncopterize_test(CliInvert)]
+    fn invert_flags(_cli: &CliInvert) {}
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer)]
+#[derive(Parser, Debug)]
+struct CliUnclowncopterize {
+    /// Turn debugging information on
+    #[arg(long, env = "CLOWNCOPTERIZE_TEST_UN_THIS")]
+    clowntown_this: bool,
+
+    /// Skip the safety checks
+    #[arg(long = "clowntown-no-checks", action = clap::ArgAction::SetFalse)]
+    #[clowntown(invert)]
+    clowntown_checks: bool,
+}
+
+#[test]
+fn test_unclowncopterize() {
+    let cli = CliUnclowncopterize::try_parse_from(vec!["prog", "--un-clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_checks, true);
+    assert_eq!(cli.un_clowncopterize, true);
+
+    let err = CliUnclowncopterize::try_parse_from(vec![
+        "prog",
+        "--un-clowncopterize",
+        "--clowncopterize",
+    ])
+    .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn test_unclowncopterize_forces_explicit_and_env_off() {
+    let cli = CliUnclowncopterize::clowntown_try_parse_from(vec![
+        "prog",
+        "--clowntown-this",
+        "--clowntown-no-checks",
+        "--un-clowncopterize",
+    ])
+    .unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_checks, true);
+
+    // SAFETY: no other test reads this variable.
+    unsafe { std::env::set_var("CLOWNCOPTERIZE_TEST_UN_THIS", "true") };
+    let cli = CliUnclowncopterize::clowntown_try_parse_from(vec!["prog"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    let cli =
+        CliUnclowncopterize::clowntown_try_parse_from(vec!["prog", "--un-clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+}
+
+#[clowncopterize::clowncopterize(toggle, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliToggle {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Run the safety checks
+    #[arg(long = "clowntown-no-checks", action = clap::ArgAction::SetFalse)]
+    clowntown_checks: bool,
+
+    /// Use the cache
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    clowntown_cache: bool,
+}
+
+#[test]
+fn test_toggle() {
+    let cli = CliToggle::try_parse_from(vec!["prog"]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_checks, true);
+    assert_eq!(cli.clowntown_cache, true);
+
+    let cli = CliToggle::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_checks, false);
+    assert_eq!(cli.clowntown_cache, false);
+}
+
+#[clowncopterize::clowncopterize(default_true = "invert")]
+#[derive(Parser, Debug)]
+struct CliDefaultTrue {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Use the cache
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    clowntown_cache: bool,
+}
+
+#[clowncopterize::clowncopterize(default_true = "skip")]
+#[derive(Parser, Debug)]
+struct CliDefaultTrueSkip {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Use the cache
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    clowntown_cache: bool,
+}
+
+#[test]
+fn test_default_true() {
+    let cli = CliDefaultTrue::try_parse_from(["prog"]).unwrap();
+    assert!(cli.clowntown_cache);
+
+    let cli = CliDefaultTrue::try_parse_from(["prog", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+    assert!(!cli.clowntown_cache);
+
+    let cli =
+        CliDefaultTrue::try_parse_from(["prog", "--clowncopterize", "--clowntown-cache=true"])
+            .unwrap();
+    assert!(cli.clowntown_cache);
+
+    let cli = CliDefaultTrueSkip::try_parse_from(["prog", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+    assert!(cli.clowntown_cache);
+    let changed: Vec<_> = cli
+        .clowntown_diff()
+        .into_iter()
+        .map(|change| change.flag.name)
+        .collect();
+    assert_eq!(changed, ["clowntown_this"]);
+}
+
+#[clowncopterize::clowncopterize(generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliClownGroups {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Skip TLS verification
+    #[arg(long)]
+    #[clowntown(group = "network")]
+    clowntown_insecure: bool,
+
+    /// Retry forever
+    #[arg(long)]
+    #[clowntown(group = "network", group = "db")]
+    clowntown_retry_forever: bool,
+}
+
+#[test]
+fn test_clown_groups() {
+    let cli = CliClownGroups::try_parse_from(vec!["prog", "--clowncopterize-network"]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_insecure, true);
+    assert_eq!(cli.clowntown_retry_forever, true);
+    assert_eq!(cli.clowncopterize, false);
+
+    let cli = CliClownGroups::try_parse_from(vec!["prog", "--clowncopterize-db"]).unwrap();
+    assert_eq!(cli.clowntown_insecure, false);
+    assert_eq!(cli.clowntown_retry_forever, true);
+
+    let cli = CliClownGroups::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_insecure, true);
+    assert_eq!(cli.clowntown_retry_forever, true);
+    assert_eq!(cli.clowncopterize_network, false);
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+struct CliGlobs {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Drop the database
+    #[arg(long)]
+    clowntown_db_drop: bool,
+
+    /// Truncate the database
+    #[arg(long)]
+    clowntown_db_truncate: bool,
+}
+
+#[test]
+fn test_select_globs() {
+    let mut cli = CliGlobs::try_parse_from(vec!["prog"]).unwrap();
+    cli.clowntown_select(&["clowntown_db_*"]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_db_drop, true);
+    assert_eq!(cli.clowntown_db_truncate, true);
+
+    let mut cli = CliGlobs::try_parse_from(vec!["prog"]).unwrap();
+    cli.clowntown_select(&["*_this", "--clowntown-db-d*"])
+        .unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_db_drop, true);
+    assert_eq!(cli.clowntown_db_truncate, false);
+}
+
+#[test]
+fn test_select_abbreviations() {
+    let mut cli = CliGlobs::try_parse_from(vec!["prog"]).unwrap();
+    cli.clowntown_select(&["db-tr", "this"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_db_drop, false);
+    assert_eq!(cli.clowntown_db_truncate, true);
+
+    let err = cli.clowntown_select(&["db"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+    assert!(err.to_string().contains(
+        "'db' is ambiguous, it could be any of: clowntown_db_drop, clowntown_db_truncate"
+    ));
+}
+
+#[test]
+fn test_select_unknown_pattern() {
+    let mut cli = CliGlobs::try_parse_from(vec!["prog"]).unwrap();
+    let err = cli
+        .clowntown_select(&["clowntown_db_*", "nope*"])
+        .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+    assert!(
+        err.to_string()
+            .contains("no clowntown flag matches 'nope*'")
+    );
+    assert_eq!(cli.clowntown_db_drop, false);
+}
+
+#[clowncopterize::clowncopterize(runtime_max = 1)]
+#[derive(Parser, Debug)]
+struct CliRuntimeMax {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// lists test values
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[test]
+fn test_runtime_max() {
+    let cli = CliRuntimeMax::clowntown_try_parse_from(vec!["prog", "--clowntown-this"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+
+    let err = CliRuntimeMax::clowntown_try_parse_from(vec![
+        "prog",
+        "--clowntown-this",
+        "--clowntown-that",
+    ])
+    .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    assert!(
+        err.to_string()
+            .contains("at most 1 clowntown flags can be enabled at once, got 2")
+    );
+
+    let cli = CliRuntimeMax::clowntown_try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, true);
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+struct CliConstraints {
+    /// Where to back the database up first
+    #[arg(long)]
+    backup_path: Option<String>,
+
+    /// Drop the database
+    #[arg(long)]
+    #[clowntown(requires = "backup_path", conflicts_with = "clowntown_that")]
+    clowntown_this: bool,
+
+    /// lists test values
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[test]
+fn test_requires_and_conflicts_with() {
+    let err = CliConstraints::try_parse_from(vec!["prog", "--clowntown-this"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+
+    let cli = CliConstraints::try_parse_from(vec![
+        "prog",
+        "--clowntown-this",
+        "--backup-path",
+        "/backup",
+    ])
+    .unwrap();
+    assert_eq!(cli.clowntown_this, true);
+
+    let err = CliConstraints::try_parse_from(vec![
+        "prog",
+        "--clowntown-this",
+        "--backup-path",
+        "/backup",
+        "--clowntown-that",
+    ])
+    .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[clowncopterize::clowncopterize(
+    invariant("clowntown_fast implies clowntown_unsafe"),
+    invariant("!(clowntown_fast && safe_mode)")
+)]
+#[derive(Parser, Debug)]
+struct CliInvariants {
+    /// Go fast
+    #[arg(long)]
+    clowntown_fast: bool,
+
+    /// Break things
+    #[arg(long)]
+    clowntown_unsafe: bool,
+
+    /// Play it safe
+    #[arg(long)]
+    safe_mode: bool,
+}
+
+#[test]
+fn test_invariants() {
+    let err =
+        CliInvariants::clowntown_try_parse_from(vec!["prog", "--clowntown-fast"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    assert!(
+        err.to_string()
+            .contains("clowntown invariant violated: clowntown_fast implies clowntown_unsafe")
+    );
+
+    let cli = CliInvariants::clowntown_try_parse_from(vec![
+        "prog",
+        "--clowntown-fast",
+        "--clowntown-unsafe",
+    ])
+    .unwrap();
+    assert!(cli.validate().is_ok());
+
+    let cli = CliInvariants::clowntown_try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_fast);
+
+    let err =
+        CliInvariants::clowntown_try_parse_from(vec!["prog", "--clowncopterize", "--safe-mode"])
+            .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("clowntown invariant violated: !(clowntown_fast && safe_mode)")
+    );
+}
+
+#[test]
+fn test_with_all_clowntown() {
+    let cli = Cli::with_all_clowntown(vec!["prog", "name"]);
+    assert_eq!(cli.name.as_deref(), Some("name"));
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, true);
+    assert_eq!(cli.clowncopterize, false);
+
+    let cli = CliInvert::with_all_clowntown(vec!["prog"]);
+    assert_eq!(cli.clowntown_checks, false);
+}
+
+#[test]
+fn test_clowntown_builder() {
+    let builder = Cli::clowntown_builder()
+        .arg("name")
+        .enable("--clowntown-that");
+    assert_eq!(builder.build_args(), vec!["--clowntown-that"]);
+    let cli = builder.build().unwrap();
+    assert_eq!(cli.name.as_deref(), Some("name"));
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_that, true);
+
+    let cli = CliInvert::clowntown_builder().enable_all().build().unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_checks, false);
+}
+
+#[test]
+#[should_panic(expected = "'clowntown_nope' is not a clowntown flag")]
+fn test_clowntown_builder_unknown_flag() {
+    Cli::clowntown_builder().enable("clowntown_nope");
+}
+
+#[cfg(feature = "proptest")]
+#[test]
+fn test_arbitrary() {
+    use proptest::strategy::{Strategy, ValueTree};
+
+    let mut runner = proptest::test_runner::TestRunner::deterministic();
+    let strategy = proptest::arbitrary::any::<CliInvert>();
+    let (mut this, mut checks) = (Vec::new(), Vec::new());
+    for _ in 0..64 {
+        let cli = strategy.new_tree(&mut runner).unwrap().current();
+        assert_eq!(cli.clowncopterize, false);
+        this.push(cli.clowntown_this);
+        checks.push(cli.clowntown_checks);
+    }
+    assert!(this.contains(&true) && this.contains(&false));
+    assert!(checks.contains(&true) && checks.contains(&false));
+}
+
+#[test]
+fn test_clowntown_combinations() {
+    let combinations: Vec<_> = Cli::clowntown_combinations().collect();
+    assert_eq!(combinations.len(), 4);
+    for args in combinations {
+        let cli =
+            Cli::clowntown_try_parse_from(std::iter::once("prog".to_string()).chain(args.clone()))
+                .unwrap();
+        assert_eq!(
+            cli.clowntown_this,
+            args.contains(&"--clowntown-this".to_string())
+        );
+        assert_eq!(
+            cli.clowntown_that,
+            args.contains(&"--clowntown-that".to_string())
+        );
+    }
+    assert_eq!(
+        Cli::clowntown_combinations_up_to(0).collect::<Vec<_>>(),
+        vec![Vec::<String>::new()]
+    );
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer, generate_tests = true)]
+#[derive(Parser, Debug)]
+#[command(rename_all = "SCREAMING_SNAKE_CASE")]
+struct CliRenameAll {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// lists test values
+    #[arg(long = "that")]
+    clowntown_that: bool,
+}
+
+#[test]
+fn test_rename_all() {
+    let cli = CliRenameAll::try_parse_from(vec!["prog", "--CLOWNCOPTERIZE"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, true);
+
+    let cli = CliRenameAll::clowntown_try_parse_from(vec![
+        "prog",
+        "--CLOWNTOWN_THIS",
+        "--UN_CLOWNCOPTERIZE",
+    ])
+    .unwrap();
+    assert_eq!(cli.clowntown_this, false);
+
+    let builder = CliRenameAll::clowntown_builder().enable_all();
+    assert_eq!(builder.build_args(), vec!["--CLOWNTOWN_THIS", "--that"]);
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer)]
+#[derive(Parser, Debug)]
+#[command(rename_all = "lower")]
+struct CliRenameLower {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_drop_db: bool,
+}
+
+#[test]
+fn test_rename_all_lower() {
+    let command = <CliRenameLower as clap::CommandFactory>::command();
+    for info in CliRenameLower::clowntown_manifest() {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == info.name)
+            .unwrap();
+        assert_eq!(format!("--{}", arg.get_long().unwrap()), info.long);
+    }
+    assert_eq!(
+        CliRenameLower::clowntown_manifest()[1].long,
+        "--clowntowndropdb"
+    );
+
+    let args = ["prog", "--clowntowndropdb", "--unclowncopterize"];
+    let cli = CliRenameLower::clowntown_try_parse_from(args).unwrap();
+    assert!(!cli.clowntown_drop_db);
+}
+
+#[clowncopterize::clowncopterize(strip_prefix = true)]
+#[derive(Parser, Debug)]
+struct CliStripPrefix {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// lists test values
+    #[arg(long = "clowntown-that")]
+    clowntown_that: bool,
+}
+
+#[clowncopterize::clowncopterize(strip_prefix = "ct")]
+#[derive(Parser, Debug)]
+struct CliShortPrefix {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_strip_prefix() {
+    let cli = CliStripPrefix::try_parse_from(vec!["prog", "--this"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, false);
+    assert!(CliStripPrefix::try_parse_from(vec!["prog", "--clowntown-this"]).is_err());
+
+    let cli = CliStripPrefix::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, true);
+    assert_eq!(
+        CliStripPrefix::clowntown_builder()
+            .enable_all()
+            .build_args(),
+        vec!["--this", "--clowntown-that"]
+    );
+
+    let cli = CliShortPrefix::try_parse_from(vec!["prog", "--ct-this"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+struct CliDeprecated {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// The old way
+    #[arg(long)]
+    #[clowntown(deprecated = "use --clowntown-this")]
+    clowntown_old: bool,
+}
+
+#[test]
+fn test_deprecated() {
+    use clap::CommandFactory;
+
+    let short_help = CliDeprecated::command().render_help().to_string();
+    assert!(!short_help.contains("--clowntown-old"));
+    let long_help = CliDeprecated::command().render_long_help().to_string();
+    assert!(long_help.contains("The old way"));
+    assert!(long_help.contains("[deprecated: use --clowntown-this]"));
+
+    let cli = CliDeprecated::clowntown_try_parse_from(vec!["prog", "--clowntown-old"]).unwrap();
+    assert_eq!(cli.clowntown_old, true);
+    let cli = CliDeprecated::clowntown_try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.clowntown_old, true);
+}
+
+#[clowncopterize::clowncopterize(hide_flags = true)]
+#[derive(Parser, Debug)]
+struct CliHideFlags {
+    /// Turn debugging information on
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_hide_flags() {
+    use clap::CommandFactory;
+
+    let help = CliHideFlags::command().render_long_help().to_string();
+    assert!(!help.contains("--clowntown-this"));
+    assert!(help.contains("--clowncopterize"));
+
+    let cli = CliHideFlags::try_parse_from(vec!["prog", "--clowntown-this"]).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+}
+
+#[cfg(feature = "figment")]
+#[test]
+fn test_figment_provider() {
+    use figment::{
+        Figment,
+        providers::{Format, Toml},
+    };
+
+    let config = "clowntown_this = true\nclowntown_that = false\n";
+    let cli = Cli::try_parse_from(vec!["prog"]).unwrap();
+    let figment = Figment::from(Toml::string(config)).merge(&cli);
+    assert_eq!(
+        figment.extract_inner::<bool>("clowntown_this").unwrap(),
+        true
+    );
+    assert_eq!(
+        figment.extract_inner::<bool>("clowntown_that").unwrap(),
+        false
+    );
+
+    let cli = Cli::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    let figment = Figment::from(Toml::string(config)).merge(&cli);
+    assert_eq!(
+        figment.extract_inner::<bool>("clowntown_that").unwrap(),
+        true
+    );
+
+    let cli =
+        CliUnclowncopterize::clowntown_try_parse_from(vec!["prog", "--un-clowncopterize"]).unwrap();
+    let figment = Figment::from(Toml::string("clowntown_this = true\n")).merge(&cli);
+    assert_eq!(
+        figment.extract_inner::<bool>("clowntown_this").unwrap(),
+        false
+    );
+}
+
+#[test]
+fn test_merge_from_config() {
+    let mut cli = Cli::try_parse_from(vec!["prog"]).unwrap();
+    cli.merge_from_config([("clowntown_this", true), ("--clowntown-that", false)])
+        .unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, false);
+
+    let mut cli = Cli::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    cli.merge_from_config([("clowntown_that", false)]).unwrap();
+    assert_eq!(cli.clowntown_that, true);
+
+    let mut cli = Cli::try_parse_from(vec!["prog"]).unwrap();
+    let err = cli
+        .merge_from_config([("clowntown_nope", true)])
+        .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::UnknownArgument);
+
+    let mut cli =
+        CliUnclowncopterize::clowntown_try_parse_from(vec!["prog", "--un-clowncopterize"]).unwrap();
+    cli.merge_from_config([("clowntown_this", true)]).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_overrides_from_toml() {
+    let overrides: clowncopterize::ClowntownOverrides =
+        toml::from_str("clowntown_this = true\n").unwrap();
+    let mut cli = Cli::try_parse_from(vec!["prog"]).unwrap();
+    cli.merge_from_config(overrides).unwrap();
+    assert_eq!(cli.clowntown_this, true);
+}
+
+#[cfg(feature = "serde")]
+mod serde_interop {
+    use super::*;
+
+    #[clowncopterize::clowncopterize(unclowncopterizer)]
+    #[derive(Parser, Debug, serde::Deserialize)]
+    struct CliSerde {
+        /// Turn debugging information on
+        #[arg(long)]
+        clowntown_this: bool,
+
+        /// Skip the safety checks
+        #[arg(long = "clowntown-no-checks", action = clap::ArgAction::SetFalse)]
+        #[clowntown(invert)]
+        clowntown_checks: bool,
+    }
+
+    #[test]
+    fn test_serde_defaults() {
+        let cli: CliSerde = toml::from_str("").unwrap();
+        assert_eq!(cli.clowntown_this, false);
+        assert_eq!(cli.clowntown_checks, true);
+        assert_eq!(cli.clowncopterize, false);
+
+        let mut cli: CliSerde = toml::from_str("clowncopterize = true\n").unwrap();
+        cli.apply_masters();
+        assert_eq!(cli.clowntown_this, true);
+        assert_eq!(cli.clowntown_checks, false);
+
+        let mut cli: CliSerde =
+            toml::from_str("clowntown_this = true\nun_clowncopterize = true\n").unwrap();
+        cli.apply_masters();
+        assert_eq!(cli.clowntown_this, false);
+    }
+}
+
+mod mixed_derives {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[clowncopterize::clowncopterize(unclowncopterizer)]
+    #[derive(
+        Parser,
+        Debug,
+        Clone,
+        PartialEq,
+        serde::Serialize,
+        serde::Deserialize,
+        typed_builder::TypedBuilder,
+    )]
+    #[command(name = "mixed", about = "Mixed derives")]
+    #[serde(rename_all = "kebab-case")]
+    struct CliMixed {
+        /// Turn debugging information on
+        #[arg(long)]
+        #[serde(rename = "debug")]
+        #[builder(default = true)]
+        clowntown_this: bool,
+
+        #[arg(long)]
+        #[builder(default)]
+        clowntown_skip_checks: bool,
+
+        #[arg(long, default_value = "info")]
+        #[builder(default = "info".to_string())]
+        log_level: String,
+    }
+
+    #[test]
+    fn test_mixed_derives_parse() {
+        let cli = CliMixed::try_parse_from(["mixed", "--clowncopterize"]).unwrap();
+        assert!(cli.clowntown_this);
+        assert!(cli.clowntown_skip_checks);
+        assert_eq!(cli.log_level, "info");
+        assert_eq!(cli.clone(), cli);
+
+        let command = CliMixed::command();
+        assert_eq!(command.get_name(), "mixed");
+        assert_eq!(command.get_about().unwrap().to_string(), "Mixed derives");
+        let help = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "clowntown_this")
+            .and_then(|arg| arg.get_help())
+            .unwrap();
+        assert_eq!(help.to_string(), "Turn debugging information on");
+    }
+
+    #[test]
+    fn test_mixed_derives_builder() {
+        let cli = CliMixed::builder().clowntown_skip_checks(true).build();
+        assert!(cli.clowntown_this);
+        assert!(cli.clowntown_skip_checks);
+        assert_eq!(cli.log_level, "info");
+        assert!(!cli.clowncopterize);
+        assert!(!cli.un_clowncopterize);
+    }
+
+    #[test]
+    fn test_mixed_derives_serde() {
+        let cli = CliMixed::try_parse_from(["mixed", "--clowntown-skip-checks"]).unwrap();
+        let text = toml::to_string(&cli).unwrap();
+        assert!(text.contains("debug = false\n"));
+        assert!(text.contains("clowntown-skip-checks = true\n"));
+        assert!(text.contains("log-level = \"info\"\n"));
+        assert!(text.contains("un-clowncopterize = false\n"));
+        assert_eq!(toml::from_str::<CliMixed>(&text).unwrap(), cli);
+    }
+
+    #[clowncopterize::clowncopterize(
+        unclowncopterizer,
+        extra_attrs("serde(skip)"),
+        extra_attrs("builder(default = false)")
+    )]
+    #[derive(
+        Parser, Debug, PartialEq, serde::Serialize, serde::Deserialize, typed_builder::TypedBuilder,
+    )]
+    struct CliExtraAttrs {
+        #[arg(long)]
+        #[builder(default)]
+        clowntown_this: bool,
+    }
+
+    #[test]
+    fn test_extra_attrs() {
+        let cli = CliExtraAttrs::try_parse_from(["test", "--clowncopterize"]).unwrap();
+        assert!(cli.clowntown_this);
+        let text = toml::to_string(&cli).unwrap();
+        assert_eq!(text, "clowntown_this = true\n");
+        let cli: CliExtraAttrs = toml::from_str(&text).unwrap();
+        assert!(!cli.clowncopterize);
+        assert!(!cli.un_clowncopterize);
+
+        let cli = CliExtraAttrs::builder().build();
+        assert_eq!(cli, toml::from_str("clowntown_this = false\n").unwrap());
+    }
+}
+
+#[test]
+fn test_apply_masters() {
+    let mut cli = Cli::try_parse_from(vec!["prog"]).unwrap();
+    cli.clowncopterize = true;
+    cli.apply_masters();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_that, true);
+}
+
+#[test]
+fn test_reload_clowntown() {
+    let path = std::env::temp_dir().join(format!("clowntown-reload-{}", std::process::id()));
+    let mut cli = Cli::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+
+    std::fs::write(&path, "# incident\nclowntown_this = false\n").unwrap();
+    cli.reload_clowntown(&path).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_that, true);
+
+    std::fs::write(&path, "--clowntown-this\nclowntown_nope = true\n").unwrap();
+    assert!(cli.reload_clowntown(&path).is_err());
+    assert_eq!(cli.clowntown_this, false);
+
+    let mut cli = CliInvariants::try_parse_from(vec!["prog"]).unwrap();
+    std::fs::write(&path, "clowntown_fast\n").unwrap();
+    assert!(cli.reload_clowntown(&path).is_err());
+    assert_eq!(cli.clowntown_fast, false);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "notify")]
+#[test]
+fn test_watch_clowntown() {
+    use std::sync::{Arc, Mutex};
+
+    let dir = std::env::temp_dir().join(format!("clowntown-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("flags");
+    std::fs::write(&path, "").unwrap();
+
+    let cli = Arc::new(Mutex::new(Cli::try_parse_from(vec!["prog"]).unwrap()));
+    let _watcher = Cli::watch_clowntown(cli.clone(), &path).unwrap();
+    std::fs::write(&path, "clowntown_this = true\n").unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while !cli.lock().unwrap().clowntown_this && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(cli.lock().unwrap().clowntown_this, true);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_resolver() {
+    use clowncopterize::ClowntownDecision;
+
+    let resolver = |flag: &str, _enabled: bool| match flag {
+        "clowntown_this" => ClowntownDecision::Veto,
+        "clowntown_that" => ClowntownDecision::Force,
+        _ => ClowntownDecision::Keep,
+    };
+    let cli =
+        Cli::clowntown_try_parse_from_with(vec!["prog", "--clowntown-this"], &resolver).unwrap();
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_that, true);
+
+    let mut cli = CliInvert::try_parse_from(vec!["prog"]).unwrap();
+    cli.apply_resolver(&|_: &str, _: bool| ClowntownDecision::Force)
+        .unwrap();
+    assert_eq!(cli.clowntown_this, true);
+    assert_eq!(cli.clowntown_checks, false);
+
+    let err = CliInvariants::clowntown_try_parse_from_with(vec!["prog"], &|flag: &str, _: bool| {
+        if flag == "clowntown_fast" {
+            ClowntownDecision::Force
+        } else {
+            ClowntownDecision::Keep
+        }
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn test_resolver_async() {
+    use clowncopterize::{AsyncClowntownResolver, ClowntownDecision};
+    use std::future::Future;
+
+    struct Service;
+
+    impl AsyncClowntownResolver for Service {
+        fn resolve(
+            &self,
+            flag: &str,
+            _enabled: bool,
+        ) -> impl Future<Output = ClowntownDecision> + Send {
+            let decision = if flag == "clowntown_this" {
+                ClowntownDecision::Veto
+            } else {
+                ClowntownDecision::Keep
+            };
+            std::future::ready(decision)
+        }
+    }
+
+    let mut cli = Cli::try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+    {
+        let mut future = std::pin::pin!(cli.apply_resolver_async(&Service));
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        assert!(matches!(
+            future.as_mut().poll(&mut context),
+            std::task::Poll::Ready(Ok(()))
+        ));
+    }
+    assert_eq!(cli.clowntown_this, false);
+    assert_eq!(cli.clowntown_that, true);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn test_record_clowntown_metrics() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        CliClownGroups::clowntown_try_parse_from(vec!["prog", "--clowncopterize-db"]).unwrap();
+    });
+    let mut counters: Vec<(String, Vec<String>)> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter(|(_, _, _, value)| *value == DebugValue::Counter(1))
+        .map(|(key, _, _, _)| {
+            let key = key.key();
+            (
+                key.name().to_string(),
+                key.labels()
+                    .map(|label| label.value().to_string())
+                    .collect(),
+            )
+        })
+        .collect();
+    counters.sort();
+    assert_eq!(
+        counters,
+        vec![
+            (
+                "clowntown.flag_enabled".to_string(),
+                vec!["clowntown_retry_forever".to_string()]
+            ),
+            (
+                "clowntown.master_used".to_string(),
+                vec!["--clowncopterize-db".to_string()]
+            ),
+        ]
+    );
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn test_register_clowntown_gauges() {
+    let registry = prometheus::Registry::new();
+    let mut cli = Cli::try_parse_from(vec!["prog", "--clowntown-this"]).unwrap();
+    let gauges = cli.register_clowntown_gauges(&registry).unwrap();
+    assert_eq!(gauges.with_label_values(&["clowntown_this"]).get(), 1);
+    assert_eq!(gauges.with_label_values(&["clowntown_that"]).get(), 0);
+    assert_eq!(registry.gather()[0].name(), "clowntown_flag_enabled");
+
+    cli.clowntown_this = false;
+    cli.update_clowntown_gauges(&gauges);
+    assert_eq!(gauges.with_label_values(&["clowntown_this"]).get(), 0);
+}
+
+#[cfg(feature = "otel")]
+#[test]
+fn test_clowntown_otel_attributes() {
+    use opentelemetry::{KeyValue, trace::Span};
+
+    #[derive(Default)]
+    struct RecordingSpan(Vec<KeyValue>);
+
+    impl Span for RecordingSpan {
+        fn add_event_with_timestamp<T>(
+            &mut self,
+            _name: T,
+            _timestamp: std::time::SystemTime,
+            _attributes: Vec<KeyValue>,
+        ) where
+            T: Into<std::borrow::Cow<'static, str>>,
+        {
+        }
+
+        fn span_context(&self) -> &opentelemetry::trace::SpanContext {
+            unimplemented!()
+        }
+
+        fn is_recording(&self) -> bool {
+            true
+        }
+
+        fn set_attribute(&mut self, attribute: KeyValue) {
+            self.0.push(attribute);
+        }
+
+        fn set_status(&mut self, _status: opentelemetry::trace::Status) {}
+
+        fn update_name<T>(&mut self, _new_name: T)
+        where
+            T: Into<std::borrow::Cow<'static, str>>,
+        {
+        }
+
+        fn add_link(
+            &mut self,
+            _span_context: opentelemetry::trace::SpanContext,
+            _attributes: Vec<KeyValue>,
+        ) {
+        }
+
+        fn end_with_timestamp(&mut self, _timestamp: std::time::SystemTime) {}
+    }
+
+    let cli = Cli::try_parse_from(vec!["prog", "--clowntown-that"]).unwrap();
+    let expected = vec![
+        KeyValue::new("clowntown.clowntown_this", false),
+        KeyValue::new("clowntown.clowntown_that", true),
+    ];
+    assert_eq!(cli.clowntown_otel_attributes(), expected);
+
+    let mut span = RecordingSpan::default();
+    cli.record_clowntown_span(&mut span);
+    assert_eq!(span.0, expected);
+}
+
+#[cfg(feature = "sentry")]
+#[test]
+fn test_record_clowntown_sentry() {
+    use sentry_core::protocol::{Context, Value};
+
+    let events = sentry_core::test::with_captured_events(|| {
+        Cli::clowntown_try_parse_from(vec!["prog", "--clowncopterize"]).unwrap();
+        sentry_core::capture_message("crash", sentry_core::Level::Error);
+    });
+    let Some(Context::Other(context)) = events[0].contexts.get("clowntown") else {
+        panic!("missing clowntown context");
+    };
+    assert_eq!(context["masters"], Value::from(vec!["--clowncopterize"]));
+    assert_eq!(
+        context["enabled_flags"],
+        Value::from(vec!["--clowntown-this", "--clowntown-that"])
+    );
+    assert_eq!(events[0].breadcrumbs.len(), 1);
+
+    let events = sentry_core::test::with_captured_events(|| {
+        Cli::clowntown_try_parse_from(vec!["prog", "--clowntown-this"]).unwrap();
+        sentry_core::capture_message("crash", sentry_core::Level::Error);
+    });
+    assert!(!events[0].contexts.contains_key("clowntown"));
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+struct CliDocs {
+    /// Drop the database
+    ///
+    /// Only on staging.
+    #[arg(long)]
+    #[clowntown(owner = "storage", expires = "2025-06-30")]
+    clowntown_drop: bool,
+
+    /// Pipe | things
+    #[arg(long)]
+    clowntown_pipe: bool,
+}
+
+#[test]
+fn test_docs_md() {
+    assert_eq!(
+        CliDocs::CLOWNTOWN_DOCS_MD,
+        "| Flag | Help | Owner | Since | Expires |\n\
+         | --- | --- | --- | --- | --- |\n\
+         | `--clowntown-drop` | Drop the database | storage |  | 2025-06-30 |\n\
+         | `--clowntown-pipe` | Pipe \\| things |  |  |  |\n"
+    );
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+struct CliLevels {
+    /// Drop the database
+    #[arg(long)]
+    #[clowntown(
+        owner = "storage",
+        severity = "HIGH",
+        since = "2.3.0",
+        link = "https://runbook/flags/drop"
+    )]
+    clowntown_drop: bool,
+
+    /// Skip the cache
+    #[arg(long)]
+    #[clowntown(severity = "low")]
+    clowntown_nocache: bool,
+}
+
+#[test]
+fn test_clowntown_manifest() {
+    use clowncopterize::{ClownFlagInfo, ClowntownSeverity};
+
+    assert_eq!(
+        CliLevels::clowntown_manifest(),
+        [
+            ClownFlagInfo {
+                name: "clowntown_drop",
+                long: "--clowntown-drop",
+                struct_name: "CliLevels",
+                module_path: "test",
+                help: "Drop the database",
+                owner: Some("storage"),
+                level: Some(ClowntownSeverity::High),
+                since: Some("2.3.0"),
+                expires: None,
+                link: Some("https://runbook/flags/drop"),
+            },
+            ClownFlagInfo {
+                name: "clowntown_nocache",
+                long: "--clowntown-nocache",
+                struct_name: "CliLevels",
+                module_path: "test",
+                help: "Skip the cache",
+                owner: None,
+                level: Some(ClowntownSeverity::Low),
+                since: None,
+                expires: None,
+                link: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_since_long_help() {
+    use clap::CommandFactory;
+
+    let long_help = CliLevels::command().render_long_help().to_string();
+    assert!(long_help.contains("Drop the database"));
+    assert!(long_help.contains("[since: 2.3.0] [runbook: https://runbook/flags/drop]"));
+    let short_help = CliLevels::command().render_help().to_string();
+    assert!(!short_help.contains("[since"));
+    assert!(short_help.contains("--clowntown-drop"));
+}
+
+#[test]
+fn test_severity_headings() {
+    use clap::CommandFactory;
+
+    let help = CliLevels::command().render_help().to_string();
+    let section = |heading: &str| {
+        help.split("\n\n")
+            .find(|section| section.starts_with(heading))
+            .unwrap()
+            .to_string()
+    };
+    let high = section("Risky (HIGH):");
+    assert!(high.contains("--clowntown-drop"));
+    assert!(!high.contains("--clowntown-nocache"));
+    let low = section("Risky (low):");
+    assert!(low.contains("--clowntown-nocache"));
+    assert!(!low.contains("--clowntown-drop"));
+}
+
+#[cfg(feature = "completions")]
+#[clowncopterize::clowncopterize(completions)]
+#[derive(Parser, Debug)]
+struct CliCompletions {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[cfg(feature = "completions")]
+#[test]
+fn test_completions() {
+    use clowncopterize::__private::clap_complete::Shell;
+
+    let cli = CliCompletions::try_parse_from(["test", "--clowntown-completions", "bash"]).unwrap();
+    assert_eq!(cli.clowntown_completions, Some(Shell::Bash));
+    assert!(!cli.clowntown_this);
+
+    let mut out = Vec::new();
+    CliCompletions::clowntown_completions(Shell::Bash, &mut out);
+    let script = String::from_utf8(out).unwrap();
+    assert!(script.contains("--clowncopterize"));
+    assert!(script.contains("--clowntown-this"));
+
+    let args = ["test", "--clowntown-completions", "bash"];
+    let err = CliCompletions::clowntown_try_parse_from(args).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::DisplayHelp);
+    assert_eq!(err.render().to_string(), script);
+}
+
+fn french(key: &str, default: &str) -> String {
+    match key {
+        "clowncopterize" => "Active tous les drapeaux clowntown".to_string(),
+        "un_clowncopterize" => "Désactive tous les drapeaux clowntown".to_string(),
+        "since" => "depuis".to_string(),
+        _ => default.to_string(),
+    }
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer, localize = french)]
+#[derive(Parser, Debug)]
+struct CliLocalized {
+    /// Drop the database
+    #[arg(long)]
+    #[clowntown(since = "1.0", deprecated = "use {nothing}")]
+    clowntown_drop: bool,
+}
+
+#[test]
+fn test_localize() {
+    use clap::CommandFactory;
+
+    let help = CliLocalized::command().render_long_help().to_string();
+    assert!(help.contains("Active tous les drapeaux clowntown"));
+    assert!(help.contains("Désactive tous les drapeaux clowntown"));
+    assert!(help.contains("Drop the database"));
+    assert!(help.contains("[depuis: 1.0] [deprecated: use {nothing}]"));
+    assert!(!help.contains("Turns all the clowntown flags"));
+}
+
+#[clowncopterize::clowncopterize(warning_style = true)]
+#[derive(Parser, Debug)]
+struct CliWarningStyle {
+    /// A name
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Drop the database
+    ///
+    /// Only on staging.
+    #[arg(long)]
+    clowntown_drop: bool,
+}
+
+#[test]
+fn test_warning_style() {
+    use clap::CommandFactory;
+
+    let help = CliWarningStyle::command().render_help();
+    let ansi = help.ansi().to_string();
+    assert!(ansi.contains("\x1b[1;33mClowntown\x1b[0m"));
+    assert!(ansi.contains("\x1b[1;33mDrop the database\x1b[0m"));
+    let plain = help.to_string();
+    let section = plain
+        .split("\n\n")
+        .find(|section| section.starts_with("Clowntown:"))
+        .unwrap();
+    assert!(section.contains("--clowntown-drop"));
+    assert!(section.contains("--clowncopterize"));
+    assert!(!section.contains("--name"));
+    let long_help = CliWarningStyle::command().render_long_help().to_string();
+    assert!(long_help.contains("Only on staging."));
+}
+
+#[clowncopterize::clowncopterize(after_help = true)]
+#[derive(Parser, Debug)]
+#[command(after_help = "See the runbook.")]
+struct CliAfterHelp {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "clowntown_db")]
+    clowntown_drop: bool,
+}
+
+#[test]
+fn test_after_help() {
+    use clap::CommandFactory;
+
+    let help = CliAfterHelp::command().render_help().to_string();
+    assert!(help.ends_with(
+        "See the runbook.\n\n\
+         The following flags are mass-enabled by --clowncopterize: --clowntown-this\n\
+         The following flags are mass-enabled by --clowntown-db: --clowntown-drop\n"
+    ));
+}
+
+#[clowncopterize::clowncopterize(clowntown_help)]
+#[derive(Parser, Debug)]
+struct CliClowntownHelp {
+    /// A required name
+    name: String,
+
+    /// Turn things on
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    verbose: bool,
+}
+
+#[test]
+fn test_clowntown_help() {
+    let help = CliClowntownHelp::clowntown_help_command()
+        .render_long_help()
+        .to_string();
+    assert!(help.contains("--clowntown-this"));
+    assert!(help.contains("Turn things on"));
+    assert!(help.contains("--clowncopterize"));
+    assert!(!help.contains("--verbose"));
+    assert!(!help.contains("A required name"));
+    assert!(!help.contains("--clowntown-help"));
+
+    let cli = CliClowntownHelp::clowntown_try_parse_from(["test", "me"]).unwrap();
+    assert!(!cli.clowntown_help);
+
+    let err = CliClowntownHelp::clowntown_try_parse_from(["test", "--clowntown-help"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::DisplayHelp);
+    assert_eq!(err.exit_code(), 0);
+    assert_eq!(err.render().to_string(), help);
+}
+
+#[clowncopterize::clowncopterize(clowncopterizer = "clowncopterize_common")]
+#[derive(clap::Args, Debug)]
+struct CommonArgs {
+    /// Skip the cache
+    #[arg(long)]
+    clowntown_nocache: bool,
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+struct CliNested {
+    #[command(flatten)]
+    #[clowntown(nested)]
+    common: CommonArgs,
+
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_nested_args() {
+    use clowncopterize::HasClowntownFlags;
+
+    let flags = CliNested::clowntown_flags();
+    let names: Vec<_> = flags
+        .iter()
+        .map(|flag| (flag.struct_name, flag.name))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("CliNested", "clowntown_this"),
+            ("CommonArgs", "clowntown_nocache")
+        ]
+    );
+
+    let cli = CliNested::clowntown_try_parse_from(["test", "--clowncopterize-common"]).unwrap();
+    assert!(cli.common.clowntown_nocache);
+    assert!(!cli.clowntown_this);
+    assert_eq!(cli.enabled_clowntown_flags(), ["clowntown_nocache"]);
+
+    let cli = CliNested::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert_eq!(cli.enabled_clowntown_flags(), ["clowntown_this"]);
+}
+
+mod dep {
+    #[derive(clap::Args, Debug)]
+    pub struct ExternalArgs {
+        #[arg(long)]
+        pub clowntown_ext: bool,
+
+        #[arg(long)]
+        pub ext_other: bool,
+    }
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer, external(in = "dep::ExternalArgs"))]
+#[derive(Parser, Debug)]
+struct CliExternal {
+    #[command(flatten)]
+    ext: dep::ExternalArgs,
+
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_external() {
+    let cli = CliExternal::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+    assert!(cli.ext.clowntown_ext);
+    assert!(!cli.ext.ext_other);
+
+    let cli = CliExternal::clowntown_try_parse_from(["test"]).unwrap();
+    assert!(!cli.ext.clowntown_ext);
+
+    let cli = CliExternal::clowntown_try_parse_from(["test", "--un-clowncopterize"]).unwrap();
+    assert!(!cli.ext.clowntown_ext);
+}
+
+#[clowncopterize::clowncopterize(
+    external(in = "dep::ExternalArgs"),
+    prefix = "ext",
+    action = "Count"
+)]
+#[derive(Parser, Debug)]
+struct CliExternalCount {
+    #[command(flatten)]
+    ext: dep::ExternalArgs,
+
+    #[arg(long)]
+    ext_this: bool,
+}
+
+#[clowncopterize::clowncopterize(select, external(in = "dep::ExternalArgs"))]
+#[derive(Parser, Debug)]
+struct CliExternalSelect {
+    #[command(flatten)]
+    ext: dep::ExternalArgs,
+
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_external_masters() {
+    let cli = CliExternalCount::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.ext_this && cli.ext.ext_other);
+    assert!(!cli.ext.clowntown_ext);
+
+    let args = ["test", "--clowncopterize=clowntown-this"];
+    let cli = CliExternalSelect::clowntown_try_parse_from(args).unwrap();
+    assert!(cli.clowntown_this && !cli.ext.clowntown_ext);
+    let cli =
+        CliExternalSelect::clowntown_try_parse_from(["test", "--clowncopterize=all"]).unwrap();
+    assert!(cli.clowntown_this && cli.ext.clowntown_ext);
+}
+
+mod multicall {
+    use clap::{CommandFactory, FromArgMatches, Parser};
+
+    macro_rules! applet {
+        ($item:item) => {
+            #[clowncopterize::clowncopterize(unclowncopterizer)]
+            $item
+        };
+    }
+
+    applet! {
+        #[derive(clap::Args, Debug)]
+        pub struct Ls {
+            #[arg(long)]
+            pub clowntown_hidden: bool,
+        }
+    }
+
+    applet! {
+        #[derive(clap::Args, Debug)]
+        pub struct Rm {
+            #[arg(long)]
+            pub clowntown_force: bool,
+        }
+    }
+
+    #[derive(Parser, Debug)]
+    #[command(multicall = true)]
+    pub enum Applet {
+        Ls(Ls),
+        Rm(Rm),
+    }
+
+    fn parse(args: &[&str]) -> Applet {
+        let matches = Applet::command().try_get_matches_from(args).unwrap();
+        let mut applet = Applet::from_arg_matches(&matches).unwrap();
+        let (_, matches) = matches.subcommand().unwrap();
+        match &mut applet {
+            Applet::Ls(ls) => ls.clowntown_post_parse(matches).unwrap(),
+            Applet::Rm(rm) => rm.clowntown_post_parse(matches).unwrap(),
+        }
+        applet
+    }
+
+    #[test]
+    fn test_multicall() {
+        let Applet::Ls(ls) = parse(&["ls", "--clowncopterize"]) else {
+            panic!("expected ls");
+        };
+        assert!(ls.clowntown_hidden);
+        let Applet::Rm(rm) = parse(&["rm"]) else {
+            panic!("expected rm");
+        };
+        assert!(!rm.clowntown_force);
+        let Applet::Rm(rm) = parse(&["rm", "--clowntown-force", "--un-clowncopterize"]) else {
+            panic!("expected rm");
+        };
+        assert!(!rm.clowntown_force);
+    }
+}
+
+mod subcommands {
+    use clap::{Parser, Subcommand};
+
+    #[clowncopterize::clowncopterize]
+    #[derive(clap::Args, Debug)]
+    pub struct RunArgs {
+        #[arg(long)]
+        pub clowntown_fast: bool,
+    }
+
+    #[derive(Subcommand, Debug)]
+    pub enum Commands {
+        Run(RunArgs),
+        Walk,
+    }
+
+    #[clowncopterize::clowncopterize]
+    #[derive(Parser, Debug)]
+    pub struct Cli {
+        #[arg(long)]
+        pub name: Option<String>,
+
+        #[arg(long)]
+        pub clowntown_this: bool,
+
+        #[command(subcommand)]
+        pub command: Option<Commands>,
+    }
+
+    fn run(args: &[&str]) -> (bool, bool) {
+        let cli = Cli::clowntown_try_parse_from(args).unwrap();
+        match cli.command {
+            Some(Commands::Run(run)) => (cli.clowntown_this, run.clowntown_fast),
+            _ => panic!("expected run"),
+        }
+    }
+
+    #[test]
+    fn test_subcommand_propagation() {
+        assert_eq!(run(&["prog", "--clowncopterize", "run"]), (true, true));
+        assert_eq!(run(&["prog", "run", "--clowncopterize"]), (false, true));
+        assert_eq!(run(&["prog", "--name", "run", "run"]), (false, false));
+        assert_eq!(
+            run(&["prog", "--name", "run", "--clowncopterize", "run"]),
+            (true, true)
+        );
+        assert_eq!(
+            run(&["prog", "--clowncopterize", "run", "--clowncopterize"]),
+            (true, true)
+        );
+
+        let cli = Cli::clowntown_try_parse_from(["prog", "--clowncopterize", "walk"]).unwrap();
+        assert!(cli.clowntown_this);
+        let cli = Cli::clowntown_try_parse_from(["prog", "--clowncopterize"]).unwrap();
+        assert!(cli.clowntown_this && cli.command.is_none());
+    }
+}
+
+#[clowncopterize::clowncopterize(subcommand)]
+#[derive(Parser, Debug)]
+struct CliActivation {
+    name: Option<String>,
+
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[test]
+fn test_subcommand_activation() {
+    let args = ["test", "clowncopterize", "--", "x", "--clowntown-that"];
+    let cli = CliActivation::clowntown_try_parse_from(args).unwrap();
+    assert!(cli.clowncopterize);
+    assert!(cli.clowntown_this && cli.clowntown_that);
+    assert_eq!(cli.name.as_deref(), Some("x"));
+
+    let cli = CliActivation::clowntown_try_parse_from(["test", "clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this && cli.name.is_none());
+
+    let cli = CliActivation::clowntown_try_parse_from(["test", "x"]).unwrap();
+    assert!(!cli.clowncopterize && !cli.clowntown_this);
+
+    let command = CliActivation::clowntown_command();
+    assert!(command.find_subcommand("clowncopterize").is_some());
+    let master = command
+        .get_arguments()
+        .find(|arg| arg.get_id() == "clowncopterize")
+        .unwrap();
+    assert!(master.is_hide_set());
+}
+
+#[clowncopterize::clowncopterize(policy = "tests/clowntown-policy.toml")]
+#[derive(Parser, Debug)]
+struct CliPolicy {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_policy_allows_other_flags() {
+    let cli = CliPolicy::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+}
+
+// release test builds would fail, which is the point.
+#[cfg(debug_assertions)]
+#[clowncopterize::clowncopterize(deny_release = true)]
+#[derive(Parser, Debug)]
+struct CliDenyRelease {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_deny_release_allows_debug() {
+    let cli = CliDenyRelease::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+}
+
+#[clowncopterize::clowncopterize(
+    deny_env = "CLOWNTOWN_TEST_APP_ENV=production",
+    deny_env = "CLOWNTOWN_TEST_PROD"
+)]
+#[derive(Parser, Debug)]
+struct CliDenyEnv {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_deny_env() {
+    // SAFETY: no other test reads these variables.
+    unsafe { std::env::set_var("CLOWNTOWN_TEST_APP_ENV", "staging") };
+    let cli = CliDenyEnv::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+
+    unsafe { std::env::set_var("CLOWNTOWN_TEST_APP_ENV", "production") };
+    let err = CliDenyEnv::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    assert!(
+        err.to_string()
+            .contains("'--clowncopterize' is refused when CLOWNTOWN_TEST_APP_ENV=production")
+    );
+    let cli = CliDenyEnv::clowntown_try_parse_from(["test", "--clowntown-this"]).unwrap();
+    assert!(cli.clowntown_this);
+    unsafe { std::env::remove_var("CLOWNTOWN_TEST_APP_ENV") };
+
+    unsafe { std::env::set_var("CLOWNTOWN_TEST_PROD", "") };
+    let err = CliDenyEnv::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert!(err.to_string().contains("when CLOWNTOWN_TEST_PROD is set"));
+    unsafe { std::env::remove_var("CLOWNTOWN_TEST_PROD") };
+}
+
+mod guards {
+    pub fn canary_only(context: &clowncopterize::ClowntownGuardContext) -> Result<(), String> {
+        assert!(context.hostname.is_some());
+        if context.flags.contains(&"clowntown_drop") {
+            return Err(format!("{} is not a canary", context.master));
+        }
+        Ok(())
+    }
+
+    pub fn staging_only(context: &clowncopterize::ClowntownGuardContext) -> Result<(), String> {
+        match context.env.as_deref() {
+            Some("staging") => Ok(()),
+            env => Err(format!("APP_ENV is {:?}", env)),
+        }
+    }
+}
+
+#[clowncopterize::clowncopterize(guard = "guards::canary_only")]
+#[derive(Parser, Debug)]
+struct CliGuard {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "clowntown_db")]
+    clowntown_drop: bool,
+}
+
+#[test]
+fn test_guard() {
+    let cli = CliGuard::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+    let cli = CliGuard::clowntown_try_parse_from(["test", "--clowntown-drop"]).unwrap();
+    assert!(cli.clowntown_drop);
+    let err = CliGuard::clowntown_try_parse_from(["test", "--clowntown-db"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    assert!(
+        err.to_string()
+            .contains("'--clowntown-db' is refused: --clowntown-db is not a canary")
+    );
+}
+
+#[clowncopterize::clowncopterize(
+    guard(fn = "guards::staging_only", env = "CLOWNTOWN_TEST_GUARD_ENV"),
+    prefixes(clowntown, "danger"),
+    groups(db)
+)]
+#[derive(Parser, Debug)]
+struct CliListOptions {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    #[clowntown(group = "db")]
+    danger_drop: bool,
+}
+
+#[test]
+fn test_list_options() {
+    assert_eq!(
+        CliListOptions::CLOWNTOWN_FLAGS,
+        &["clowntown_this", "danger_drop"]
+    );
+    let err = CliListOptions::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert!(err.to_string().contains("is refused: APP_ENV is None"));
+    // SAFETY: no other test reads this variable.
+    unsafe { std::env::set_var("CLOWNTOWN_TEST_GUARD_ENV", "staging") };
+    let cli = CliListOptions::clowntown_try_parse_from(["test", "--clowncopterize-db"]).unwrap();
+    assert!(cli.danger_drop && !cli.clowntown_this);
+    unsafe { std::env::remove_var("CLOWNTOWN_TEST_GUARD_ENV") };
+}
+
+#[clowncopterize::clowncopterize(ack_flag, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliAck {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[clowncopterize::clowncopterize(ack_flag, clowncopterizer = "yolo")]
+#[derive(Parser, Debug)]
+struct CliAckRenamed {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_ack_flag() {
+    use clap::CommandFactory;
+
+    let err = CliAck::try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+    let cli = CliAck::try_parse_from(["test", "--clowncopterize-ack"]).unwrap();
+    assert!(!cli.clowntown_this);
+    let cli = CliAck::try_parse_from(["test", "--clowncopterize", "--clowncopterize-ack"]).unwrap();
+    assert!(cli.clowntown_this);
+
+    // the default name follows the `clowncopterizer`, whatever the order of the options.
+    let cli = CliAckRenamed::try_parse_from(["test", "--yolo", "--yolo-ack"]).unwrap();
+    assert!(cli.clowntown_this);
+
+    let help = CliAck::command().render_help().to_string();
+    assert!(help.contains("Turns all the clowntown flags on, along with --clowncopterize-ack"));
+    assert!(
+        help.contains("Acknowledges mass-enabling clowntown flags, required by --clowncopterize")
+    );
+}
+
+#[clowncopterize::clowncopterize(companions, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliCompanions {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "clowntown_db")]
+    clowntown_drop: bool,
+}
+
+#[clowncopterize::clowncopterize(companions, clowncopterizer = "yolo")]
+#[derive(Parser, Debug)]
+struct CliCompanionsRenamed {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[clowncopterize::clowncopterize(reason = "why")]
+#[derive(Parser, Debug)]
+struct CliReason {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_companions() {
+    use clap::CommandFactory;
+    use clap::error::ErrorKind;
+
+    let kind = |args: &[&str]| CliCompanions::try_parse_from(args).unwrap_err().kind();
+    assert_eq!(
+        kind(&["test", "--clowncopterize"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(
+        kind(&["test", "--clowntown-db", "--clowncopterize-ack"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(
+        kind(&["test", "--clowncopterize-ack"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(
+        kind(&["test", "--clowncopterize-reason", ""]),
+        ErrorKind::InvalidValue
+    );
+    let cli = CliCompanions::try_parse_from([
+        "test",
+        "--clowncopterize-ack",
+        "--clowncopterize-reason",
+        "incident 42",
+    ])
+    .unwrap();
+    assert!(!cli.clowntown_this);
+    let cli = CliCompanions::try_parse_from([
+        "test",
+        "--clowntown-db",
+        "--clowncopterize-ack",
+        "--clowncopterize-reason=incident 42",
+    ])
+    .unwrap();
+    assert!(cli.clowntown_drop);
+    assert!(!cli.clowntown_this);
+    assert_eq!(cli.clowncopterize_reason.as_deref(), Some("incident 42"));
+
+    let help = CliCompanions::command().render_help().to_string();
+    assert!(help.contains(
+        "Turns all the clowntown flags on, along with --clowncopterize-ack and \
+         --clowncopterize-reason"
+    ));
+    assert!(help.contains(
+        "Why the clowntown flags are mass-enabled, required by --clowncopterize, --clowntown-db"
+    ));
+
+    let err = CliReason::try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    let cli = CliReason::try_parse_from(["test", "--clowncopterize", "--why", "testing"]).unwrap();
+    assert!(cli.clowntown_this);
+    assert_eq!(cli.why.as_deref(), Some("testing"));
+
+    let args = ["test", "--yolo", "--yolo-ack", "--yolo-reason=testing"];
+    let cli = CliCompanionsRenamed::try_parse_from(args).unwrap();
+    assert!(cli.clowntown_this);
+    assert_eq!(cli.yolo_reason.as_deref(), Some("testing"));
+}
+
+#[derive(Default)]
+struct RecordingSink(std::sync::Mutex<Vec<String>>);
+
+impl clowncopterize::ClowntownSink for RecordingSink {
+    fn flag_enabled(&self, flag: &clowncopterize::ClownFlagInfo) {
+        self.0.lock().unwrap().push(flag.long.to_string());
+    }
+
+    fn master_used(&self, master: &str) {
+        self.0.lock().unwrap().push(master.to_string());
+    }
+
+    fn master_shadowed(&self, master: &str) {
+        self.0.lock().unwrap().push(format!("{} (shadow)", master));
+    }
+}
+
+static SINK: RecordingSink = RecordingSink(std::sync::Mutex::new(Vec::new()));
+
+#[clowncopterize::clowncopterize(sink = "SINK")]
+#[derive(Parser, Debug)]
+struct CliSink {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[test]
+fn test_sink() {
+    CliSink::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert_eq!(
+        *SINK.0.lock().unwrap(),
+        vec!["--clowncopterize", "--clowntown-this", "--clowntown-that"]
+    );
+
+    let sink = RecordingSink::default();
+    CliSink::try_parse_from(["test", "--clowntown-that"])
+        .unwrap()
+        .record_clowntown_to(&sink);
+    assert_eq!(*sink.0.lock().unwrap(), vec!["--clowntown-that"]);
+}
+
+#[clowncopterize::clowncopterize]
+#[derive(Parser, Debug)]
+struct CliDiff {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "clowntown_db")]
+    clowntown_drop: bool,
+
+    #[arg(long, action = clap::ArgAction::SetFalse)]
+    #[clowntown(invert)]
+    clowntown_safety: bool,
+}
+
+#[test]
+fn test_clowntown_diff() {
+    use clowncopterize::{ClowntownCause, ClowntownChange};
+
+    let cli = CliDiff::try_parse_from(["test"]).unwrap();
+    assert!(cli.clowntown_diff().is_empty());
+
+    let cli = CliDiff::try_parse_from(["test", "--clowncopterize", "--clowntown-drop"]).unwrap();
+    let changes: Vec<_> = cli
+        .clowntown_diff()
+        .into_iter()
+        .map(|change| (change.flag.name, change.value, change.cause))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (
+                "clowntown_this",
+                true,
+                ClowntownCause::Master("--clowncopterize")
+            ),
+            ("clowntown_drop", true, ClowntownCause::Explicit),
+            (
+                "clowntown_safety",
+                false,
+                ClowntownCause::Master("--clowncopterize")
+            ),
+        ]
+    );
+    let change: ClowntownChange = cli.clowntown_diff()[0];
+    assert!(!change.default);
+    assert_eq!(change.flag.long, "--clowntown-this");
+}
+
+#[test]
+fn test_clowntown_report() {
+    use clowncopterize::{ClowntownReport, ClowntownSource};
+
+    let cli = CliDiff::try_parse_from(["test", "--clowncopterize", "--clowntown-drop"]).unwrap();
+    let report = ClowntownReport::from(&cli);
+    let entries: Vec<_> = report
+        .flags
+        .iter()
+        .map(|entry| (entry.name, entry.enabled, entry.source))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            (
+                "clowntown_this",
+                true,
+                ClowntownSource::Master("--clowncopterize")
+            ),
+            ("clowntown_drop", true, ClowntownSource::Explicit),
+            (
+                "clowntown_safety",
+                true,
+                ClowntownSource::Master("--clowncopterize")
+            ),
+        ]
+    );
+
+    let cli = CliNested::clowntown_try_parse_from(["test", "--clowncopterize-common"]).unwrap();
+    let report = ClowntownReport::from(&cli);
+    assert_eq!(report.enabled().collect::<Vec<_>>(), ["clowntown_nocache"]);
+    assert_eq!(report.flags[0].source, ClowntownSource::Default);
+    assert_eq!(
+        report.flags[1].source,
+        ClowntownSource::Master("--clowncopterize-common")
+    );
+}
+
+#[derive(Default)]
+struct Settings {
+    clowntown_this: bool,
+    clowntown_nocache: bool,
+}
+
+impl clowncopterize::Clowncopterized for Settings {
+    fn set_clowntown_flag(&mut self, name: &str, value: bool) -> bool {
+        match name {
+            "clowntown_this" => self.clowntown_this = value,
+            "clowntown_nocache" => self.clowntown_nocache = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[test]
+fn test_apply_clowntown_to() {
+    use clowncopterize::Clowncopterized;
+
+    let cli = CliNested::clowntown_try_parse_from(["test", "--clowncopterize-common"]).unwrap();
+    let mut settings = Settings {
+        clowntown_this: true,
+        ..Settings::default()
+    };
+    cli.apply_clowntown_to(&mut settings);
+    assert!(!settings.clowntown_this);
+    assert!(settings.clowntown_nocache);
+
+    let cli = CliDiff::try_parse_from(["test", "--clowncopterize"]).unwrap();
+    let mut other = CliNested::try_parse_from(["test"]).unwrap();
+    cli.apply_clowntown_to(&mut other);
+    assert!(other.clowntown_this);
+    assert!(!other.common.clowntown_nocache);
+
+    assert!(other.set_clowntown_flag("clowntown_nocache", true));
+    assert!(other.common.clowntown_nocache);
+    assert!(!other.set_clowntown_flag("clowntown_drop", true));
+}
+
+/// The master is the struct's own business.
+#[derive(Parser, Debug, clowncopterize::ClowntownIntrospect)]
+struct CliIntrospect {
+    #[arg(long)]
+    yolo: bool,
+    #[arg(long, default_value_if("yolo", "true", "true"))]
+    clowntown_this: bool,
+    #[arg(long)]
+    #[clowntown(owner = "storage")]
+    clowntown_drop: bool,
+    #[arg(long)]
+    verbose: bool,
+}
+
+#[test]
+fn test_introspect() {
+    use clowncopterize::{ClowntownReport, ClowntownSource, HasClowntownFlags};
+
+    assert_eq!(
+        CliIntrospect::CLOWNTOWN_FLAGS,
+        &["clowntown_this", "clowntown_drop"]
+    );
+    assert_eq!(
+        CliIntrospect::clowntown_manifest()[1].owner,
+        Some("storage")
+    );
+    assert_eq!(CliIntrospect::clowntown_flags().len(), 2);
+    // nothing is injected.
+    assert!(CliIntrospect::try_parse_from(["test", "--clowncopterize"]).is_err());
+
+    let cli = CliIntrospect::try_parse_from(["test", "--yolo"]).unwrap();
+    assert_eq!(cli.enabled_clowntown_flags(), vec!["clowntown_this"]);
+    let report = ClowntownReport::from(&cli);
+    assert_eq!(report.flags[0].source, ClowntownSource::Explicit);
+    assert_eq!(report.flags[1].source, ClowntownSource::Default);
+
+    let mut other = CliDiff::try_parse_from(["test"]).unwrap();
+    cli.apply_clowntown_to(&mut other);
+    assert!(other.clowntown_this);
+    assert!(!other.clowntown_drop);
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer, default_true = "skip")]
+#[derive(Parser, Debug)]
+struct CliExplain {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long, env = "CLOWNTOWN_EXPLAIN_THAT")]
+    clowntown_that: bool,
+
+    #[arg(long, default_value_t = true)]
+    clowntown_other: bool,
+}
+
+#[test]
+fn test_clowntown_explain() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let explain = |args: &[&str], flag: &str| {
+        let matches = CliExplain::command().get_matches_from(args);
+        let cli = CliExplain::from_arg_matches(&matches).unwrap();
+        cli.clowntown_explain(&matches, flag)
+    };
+
+    assert_eq!(
+        explain(&["test"], "clowntown_this").unwrap(),
+        "disabled, its default"
+    );
+    assert_eq!(
+        explain(&["test", "--clowncopterize"], "--clowntown-this").unwrap(),
+        "enabled because --clowncopterize was passed"
+    );
+    assert_eq!(
+        explain(&["test", "--clowntown-this"], "clowntown_this").unwrap(),
+        "enabled, explicitly set via --clowntown-this"
+    );
+    assert_eq!(
+        explain(&["test", "--un-clowncopterize"], "clowntown_other").unwrap(),
+        "disabled because --un-clowncopterize was passed"
+    );
+    assert_eq!(
+        explain(&["test"], "clowntown_other").unwrap(),
+        "enabled, its default"
+    );
+    // SAFETY: no other test reads this variable.
+    unsafe { std::env::set_var("CLOWNTOWN_EXPLAIN_THAT", "true") };
+    assert_eq!(
+        explain(&["test"], "clowntown_that").unwrap(),
+        "enabled, from env CLOWNTOWN_EXPLAIN_THAT"
+    );
+    unsafe { std::env::remove_var("CLOWNTOWN_EXPLAIN_THAT") };
+    assert!(
+        explain(&["test"], "clowntown_nope")
+            .unwrap_err()
+            .contains("is not a clowntown flag")
+    );
+}
+
+#[clowncopterize::clowncopterize(provenance)]
+#[derive(Parser, Debug)]
+struct CliProvenance {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "clowntown_db")]
+    clowntown_drop: bool,
+}
+
+#[test]
+fn test_clowntown_provenance() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let command = CliProvenance::command();
+    assert!(
+        command
+            .get_arguments()
+            .any(|arg| arg.get_long() == Some("clowntown-provenance"))
+    );
+
+    let matches = command.get_matches_from(["test", "--clowncopterize", "--clowntown-drop"]);
+    let cli = CliProvenance::from_arg_matches(&matches).unwrap();
+    assert_eq!(
+        cli.clowntown_provenance(&matches),
+        concat!(
+            r#"{"struct":"CliProvenance","flags":["#,
+            r#"{"field":"clowntown_this","long":"--clowntown-this","value":true,"source":"master","through":"--clowncopterize"},"#,
+            r#"{"field":"clowntown_drop","long":"--clowntown-drop","value":true,"source":"command_line","through":"--clowntown-drop"}"#,
+            "]}"
+        )
+    );
+
+    let cli = CliProvenance::clowntown_try_parse_from(["test"]).unwrap();
+    assert!(!cli.clowntown_provenance);
+
+    let args = ["test", "--clowntown-provenance", "--clowntown-this"];
+    let err = CliProvenance::clowntown_try_parse_from(args).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::DisplayHelp);
+    assert!(err.render().to_string().ends_with(r#""through":"--clowntown-this"},{"field":"clowntown_drop","long":"--clowntown-drop","value":false,"source":"default","through":null}]}
+"#));
+}
+
+#[clowncopterize::clowncopterize(env_filter)]
+#[derive(Parser, Debug)]
+struct CliEnvFilter {
+    #[arg(long)]
+    clowntown_db_drop: bool,
+
+    #[arg(long)]
+    clowntown_db_truncate: bool,
+
+    #[arg(long)]
+    clowntown_net: bool,
+}
+
+#[test]
+fn test_env_filter() {
+    // the only test reading these variables.
+    unsafe {
+        std::env::set_var("CLOWNTOWN_ALLOW", "clowntown_db_*");
+        std::env::set_var("CLOWNTOWN_BLOCK", "--clowntown-db-drop");
+    }
+    let cli = CliEnvFilter::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(!cli.clowntown_db_drop);
+    assert!(cli.clowntown_db_truncate);
+    assert!(!cli.clowntown_net);
+    let cli =
+        CliEnvFilter::clowntown_try_parse_from(["test", "--clowncopterize", "--clowntown-net"])
+            .unwrap();
+    assert!(cli.clowntown_net);
+
+    unsafe {
+        std::env::set_var("CLOWNTOWN_BLOCK", "clowntown_nope");
+    }
+    let err = CliEnvFilter::clowntown_try_parse_from(["test"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+    assert!(
+        err.to_string()
+            .contains("CLOWNTOWN_BLOCK: no clowntown flag matches 'clowntown_nope'")
+    );
+    unsafe {
+        std::env::remove_var("CLOWNTOWN_ALLOW");
+        std::env::remove_var("CLOWNTOWN_BLOCK");
+    }
+}
+
+#[clowncopterize::clowncopterize(until)]
+#[derive(Parser, Debug)]
+struct CliUntil {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_until() {
+    let cli = CliUntil::try_parse_from([
+        "test",
+        "--clowncopterize",
+        "--clowncopterize-until",
+        "2999-12-31T23:59:59Z",
+    ])
+    .unwrap();
+    assert!(cli.clowntown_this);
+    assert!(cli.clowncopterize_until.is_some());
+
+    let err = CliUntil::try_parse_from([
+        "test",
+        "--clowncopterize",
+        "--clowncopterize-until=2020-01-01T00:00:00Z",
+    ])
+    .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    assert!(
+        err.to_string()
+            .contains("the deadline 2020-01-01T00:00:00Z has passed")
+    );
+    let err = CliUntil::try_parse_from(["test", "--clowncopterize-until", "tomorrow"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+}
+
+#[clowncopterize::clowncopterize(shadow, report = "stderr")]
+#[derive(Parser, Debug)]
+struct CliShadow {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[test]
+fn test_shadow() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let matches = CliShadow::command().get_matches_from([
+        "test",
+        "--clowncopterize-shadow",
+        "--clowntown-this",
+    ]);
+    let cli = CliShadow::from_arg_matches(&matches).unwrap();
+    assert!(cli.clowncopterize_shadow);
+    assert!(!cli.clowncopterize);
+    assert!(!cli.clowntown_that);
+    assert_eq!(
+        cli.clowntown_report(&matches),
+        vec![
+            "clowntown: --clowncopterize would force on: --clowntown-that (shadowed by --clowncopterize-shadow)"
+        ]
+    );
+    let sink = RecordingSink::default();
+    cli.record_clowntown_to(&sink);
+    assert_eq!(
+        *sink.0.lock().unwrap(),
+        vec!["--clowncopterize (shadow)", "--clowntown-this"]
+    );
+
+    let err = CliShadow::try_parse_from(["test", "--clowncopterize-shadow", "--clowncopterize"])
+        .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[clowncopterize::clowncopterize(mode, report = "stderr", generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliMode {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[test]
+fn test_mode() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let cli = CliMode::try_parse_from(["test"]).unwrap();
+    assert_eq!(cli.clowncopterize, ClowncopterizeMode::Off);
+    assert!(!cli.clowntown_this && !cli.clowntown_that);
+
+    for args in [
+        ["test", "--clowncopterize"],
+        ["test", "--clowncopterize=all"],
+    ] {
+        let cli = CliMode::try_parse_from(args).unwrap();
+        assert_eq!(cli.clowncopterize, ClowncopterizeMode::All);
+        assert!(cli.clowntown_this && cli.clowntown_that);
+    }
+
+    let matches = CliMode::command().get_matches_from([
+        "test",
+        "--clowncopterize=dry-run",
+        "--clowntown-this",
+    ]);
+    let cli = CliMode::from_arg_matches(&matches).unwrap();
+    assert!(cli.clowncopterize.is_dry_run() && !cli.clowncopterize.is_on());
+    assert!(!cli.clowntown_that);
+    assert!(cli.clowntown_diff()[0].cause == clowncopterize::ClowntownCause::Explicit);
+    assert_eq!(
+        cli.clowntown_report(&matches),
+        vec![
+            "clowntown: --clowncopterize would force on: --clowntown-that (shadowed by --clowncopterize=dry-run)"
+        ]
+    );
+    let sink = RecordingSink::default();
+    cli.record_clowntown_to(&sink);
+    assert_eq!(
+        *sink.0.lock().unwrap(),
+        vec!["--clowncopterize (shadow)", "--clowntown-this"]
+    );
+
+    let err = CliMode::try_parse_from(["test", "--clowncopterize=yes"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+}
+
+#[clowncopterize::clowncopterize(version_audit = true)]
+#[derive(Parser, Debug)]
+#[command(version)]
+struct CliVersionAudit {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_that: bool,
+}
+
+#[clowncopterize::clowncopterize(version_audit = true)]
+#[derive(Parser, Debug)]
+#[command(version = "1.2.3")]
+struct CliVersionAuditCustom {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_version_audit() {
+    use clap::CommandFactory;
+
+    assert_eq!(
+        CliVersionAudit::command().get_version(),
+        Some(concat!(
+            env!("CARGO_PKG_VERSION"),
+            " (clowntown: 2 flags, --clowncopterize)"
+        ))
+    );
+    let version = CliVersionAuditCustom::command().render_version();
+    assert!(version.contains("1.2.3 (clowntown: 1 flag, --clowncopterize)"));
+}
+
+#[clowncopterize::clowncopterize(locked = true, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliLocked {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "clowntown_db")]
+    clowntown_drop: bool,
+}
+
+#[test]
+fn test_locked() {
+    let err = CliLocked::try_parse_from(["test", "--clowntown-this"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+    let err =
+        CliLocked::try_parse_from(["test", "--clowncopterize", "--clowntown-drop"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+    let cli = CliLocked::try_parse_from(["test", "--clowntown-db", "--clowntown-drop"]).unwrap();
+    assert!(cli.clowntown_drop);
+    assert!(!cli.clowntown_this);
+}
+
+#[clowncopterize::clowncopterize_test(CliLocked)]
+fn locked_matrix(_cli: &CliLocked) {}
+
+#[clowncopterize::clowncopterize(
+    unclowncopterizer,
+    policy = "tests/clowntown-policy.toml",
+    generate_tests = true
+)]
+#[derive(Parser, Debug)]
+struct CliNeverMassEnable {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    /// Wipes the disks
+    #[arg(long)]
+    #[clowntown(never_mass_enable)]
+    clowntown_wipe: bool,
+}
+
+#[test]
+fn test_never_mass_enable() {
+    let cli = CliNeverMassEnable::try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+    assert!(!cli.clowntown_wipe);
+    let cli = CliNeverMassEnable::try_parse_from(["test", "--clowntown-wipe"]).unwrap();
+    assert!(cli.clowntown_wipe);
+    let err = CliNeverMassEnable::try_parse_from(["test", "--clowncopterize", "--clowntown-wipe"])
+        .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    assert_eq!(CliNeverMassEnable::CLOWNTOWN_FLAGS, &["clowntown_this"]);
+}
+
+#[clowncopterize::clowncopterize(panic_in_tests)]
+#[derive(Parser, Debug)]
+struct CliPanicInTests {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+#[should_panic(expected = "'--clowncopterize' is set in a test")]
+fn test_panic_in_tests() {
+    let cli = CliPanicInTests::clowntown_try_parse_from(["test", "--clowntown-this"]).unwrap();
+    assert!(cli.clowntown_this);
+    // plain clap parsing is left alone.
+    let cli = CliPanicInTests::try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+    let _ = CliPanicInTests::clowntown_try_parse_from(["test", "--clowncopterize"]);
+}
+
+#[clowncopterize::clowncopterize(report = "stderr")]
+#[derive(Parser, Debug)]
+struct CliReport {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    clowntown_that: bool,
+
+    #[arg(long)]
+    clowntown_there: bool,
+}
+
+#[test]
+fn test_report() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let report = |args: &[&str]| {
+        let matches = CliReport::command().get_matches_from(args);
+        CliReport::from_arg_matches(&matches)
+            .unwrap()
+            .clowntown_report(&matches)
+    };
+    assert!(report(&["test", "--clowntown-this"]).is_empty());
+    assert_eq!(
+        report(&["test", "--clowncopterize", "--clowntown-this"]),
+        vec![
+            "clowntown: --clowncopterize forced on: --clowntown-that, --clowntown-there",
+            "clowntown: --clowncopterize left alone, set explicitly: --clowntown-this",
+        ]
+    );
+    assert_eq!(
+        report(&["test", "--clowncopterize"])[1],
+        "clowntown: --clowncopterize left alone, set explicitly: none"
+    );
+    let cli = CliReport::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_that);
+}
+
+#[clowncopterize::clowncopterize(sentinel = "/nonexistent/allow-clowntown")]
+#[derive(Parser, Debug)]
+struct CliSentinel {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[clowncopterize::clowncopterize(sentinel = "Cargo.toml")]
+#[derive(Parser, Debug)]
+struct CliSentinelPresent {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_sentinel() {
+    let err = CliSentinel::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    assert!(err.to_string().contains(
+        "'--clowncopterize' is refused: /nonexistent/allow-clowntown does not exist on this host"
+    ));
+    let cli = CliSentinel::clowntown_try_parse_from(["test", "--clowntown-this"]).unwrap();
+    assert!(cli.clowntown_this);
+
+    // tests run from the crate root.
+    let cli = CliSentinelPresent::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+}
+
+#[cfg(feature = "token")]
+fn token_key() -> Option<Vec<u8>> {
+    Some(b"secret".to_vec())
+}
+
+#[cfg(feature = "token")]
+#[clowncopterize::clowncopterize(token_key = "token_key", generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliToken {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[cfg(feature = "token")]
+#[clowncopterize::clowncopterize(token_key = "token_key")]
+#[derive(Parser, Debug)]
+struct CliTokenDeclared {
+    #[arg(long)]
+    db_master: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "db_master")]
+    clowntown_drop: bool,
+}
+
+#[cfg(feature = "token")]
+#[test]
+fn test_token() {
+    use std::time::{Duration, SystemTime};
+
+    let cli = CliToken::try_parse_from(["test"]).unwrap();
+    assert!(!cli.clowncopterize);
+    assert!(!cli.clowntown_this);
+
+    let expires = SystemTime::now() + Duration::from_secs(300);
+    let token = clowncopterize::clowntown_token(b"secret", "--clowncopterize", expires);
+    let cli = CliToken::try_parse_from(["test", "--clowncopterize", &token]).unwrap();
+    assert!(cli.clowncopterize);
+    assert!(cli.clowntown_this);
+
+    let forged = clowncopterize::clowntown_token(b"guess", "--clowncopterize", expires);
+    let expired = clowncopterize::clowntown_token(b"secret", "--clowncopterize", SystemTime::now());
+    for arg in [
+        format!("--clowncopterize={}", forged),
+        format!("--clowncopterize={}", expired),
+        "--clowncopterize=".to_string(),
+    ] {
+        let err = CliToken::try_parse_from(["test", &arg]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+        assert!(err.to_string().contains("'--clowncopterize' is refused"));
+    }
+    let err = CliToken::try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+
+    // a declared master before its flags doesn't turn them on through its empty default.
+    let cli = CliTokenDeclared::try_parse_from(["test"]).unwrap();
+    assert!(!cli.db_master && !cli.clowntown_drop);
+    let token = clowncopterize::clowntown_token(b"secret", "--db-master", expires);
+    let cli = CliTokenDeclared::try_parse_from(["test", "--db-master", &token]).unwrap();
+    assert!(cli.db_master && cli.clowntown_drop);
+}
+
+#[clowncopterize::clowncopterize(
+    value_parser = clap::builder::PossibleValuesParser::new(["i-am-sure"]),
+    generate_tests = true
+)]
+#[derive(Parser, Debug)]
+struct CliValueParser {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[clowncopterize::clowncopterize(
+    value_parser = clap::builder::PossibleValuesParser::new(["i-am-sure"])
+)]
+#[derive(Parser, Debug)]
+struct CliValueParserDeclared {
+    #[arg(long)]
+    db_master: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "db_master")]
+    clowntown_drop: bool,
+}
+
+#[clowncopterize::clowncopterize(value_parser = clap::value_parser!(u8).range(1..=3))]
+#[derive(Parser, Debug)]
+struct CliValueParserRange {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_value_parser() {
+    let cli = CliValueParser::try_parse_from(["test"]).unwrap();
+    assert!(!cli.clowncopterize);
+    assert!(!cli.clowntown_this);
+
+    let cli = CliValueParser::try_parse_from(["test", "--clowncopterize=i-am-sure"]).unwrap();
+    assert!(cli.clowncopterize);
+    assert!(cli.clowntown_this);
+
+    for arg in ["--clowncopterize=yes", "--clowncopterize="] {
+        let err = CliValueParser::try_parse_from(["test", arg]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+    }
+    let err = CliValueParser::try_parse_from(["test", "--clowncopterize"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+
+    let cli = CliValueParserRange::try_parse_from(["test", "--clowncopterize", "2"]).unwrap();
+    assert!(cli.clowntown_this);
+    let err = CliValueParserRange::try_parse_from(["test", "--clowncopterize", "4"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+
+    // the confirmation is needed even with a declared master before its flags.
+    let cli = CliValueParserDeclared::try_parse_from(["test"]).unwrap();
+    assert!(!cli.db_master && !cli.clowntown_drop);
+    let cli = CliValueParserDeclared::try_parse_from(["test", "--db-master=i-am-sure"]).unwrap();
+    assert!(cli.db_master && cli.clowntown_drop);
+}
+
+#[clowncopterize::clowncopterize(exclusive = true, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliExclusive {
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    verbose: bool,
+}
+
+#[test]
+fn test_exclusive() {
+    let cli = CliExclusive::try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+
+    let cli = CliExclusive::try_parse_from(["test", "--clowntown-this", "--verbose"]).unwrap();
+    assert!(cli.clowntown_this);
+    assert!(!cli.clowncopterize);
+
+    for arg in ["--verbose", "--clowntown-this"] {
+        let err = CliExclusive::try_parse_from(["test", "--clowncopterize", arg]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+}
+
+#[clowncopterize::clowncopterize(action = "Count", generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliCount {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[clowncopterize::clowncopterize(action = "Count")]
+#[derive(Parser, Debug)]
+struct CliCountDeclared {
+    #[arg(long)]
+    db_master: bool,
+
+    #[arg(long)]
+    #[clowntown(master = "db_master")]
+    clowntown_drop: bool,
+
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_action_count() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let cli = CliCount::try_parse_from(["test"]).unwrap();
+    assert_eq!(cli.clowncopterize, 0);
+    assert!(!cli.clowntown_this);
+
+    let args = ["test", "--clowncopterize", "--clowncopterize"];
+    let cli = CliCount::try_parse_from(args).unwrap();
+    assert_eq!(cli.clowncopterize, 2);
+    assert!(cli.clowntown_this);
+
+    let matches = CliCount::command().get_matches_from(args);
+    let cli = CliCount::from_arg_matches(&matches).unwrap();
+    assert_eq!(
+        cli.clowntown_explain(&matches, "clowntown_this").unwrap(),
+        "enabled because --clowncopterize was passed"
+    );
+
+    // a declared master stays a `bool`, whatever its place.
+    let cli = CliCountDeclared::try_parse_from(["test"]).unwrap();
+    assert!(!cli.db_master && !cli.clowntown_drop);
+    let cli = CliCountDeclared::try_parse_from(["test", "--db-master"]).unwrap();
+    assert!(cli.clowntown_drop && !cli.clowntown_this);
+    let cli = CliCountDeclared::try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this && !cli.clowntown_drop);
+}
+
+#[clowncopterize::clowncopterize(action = "Set")]
+#[derive(Parser, Debug)]
+struct CliActionSet {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_action_set() {
+    let cli = CliActionSet::try_parse_from(["test"]).unwrap();
+    assert!(!cli.clowntown_this);
+
+    let cli = CliActionSet::try_parse_from(["test", "--clowncopterize=INC-1234"]).unwrap();
+    assert!(cli.clowncopterize);
+    assert!(cli.clowntown_this);
+
+    let err = CliActionSet::try_parse_from(["test", "--clowncopterize="]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+}
+
+#[clowncopterize::clowncopterize(repeatable, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliRepeatable {
+    #[arg(long)]
+    #[clowntown(group = "db")]
+    clowntown_drop: bool,
+
+    #[arg(long)]
+    #[clowntown(group = "net")]
+    clowntown_flood: bool,
+
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_repeatable() {
+    let cli = CliRepeatable::try_parse_from(["test"]).unwrap();
+    assert!(cli.clowncopterize.is_empty());
+    assert!(!cli.clowntown_drop && !cli.clowntown_flood && !cli.clowntown_this);
+
+    let args = ["test", "--clowncopterize", "db", "--clowncopterize", "net"];
+    let cli = CliRepeatable::try_parse_from(args).unwrap();
+    assert_eq!(cli.clowncopterize, ["db", "net"]);
+    assert!(cli.clowntown_drop && cli.clowntown_flood);
+    assert!(!cli.clowntown_this);
+
+    let cli = CliRepeatable::try_parse_from(["test", "--clowncopterize", "all"]).unwrap();
+    assert!(cli.clowntown_drop && cli.clowntown_flood && cli.clowntown_this);
+
+    let cli = CliRepeatable::try_parse_from(["test", "--clowncopterize-db"]).unwrap();
+    assert!(cli.clowntown_drop);
+    assert!(!cli.clowntown_flood);
+
+    let err = CliRepeatable::try_parse_from(["test", "--clowncopterize", "dns"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+
+    let mut cli = CliRepeatable::try_parse_from(["test"]).unwrap();
+    cli.clowncopterize = vec!["net".to_string()];
+    cli.apply_masters();
+    assert!(cli.clowntown_flood);
+    assert!(!cli.clowntown_drop && !cli.clowntown_this);
+}
+
+#[clowncopterize::clowncopterize(select, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliSelect {
+    #[arg(long)]
+    clowntown_drop: bool,
+
+    #[arg(long)]
+    clowntown_flood: bool,
+
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_select() {
+    use clowncopterize::HasClowntownFlags;
+
+    let args = ["test", "--clowncopterize=clowntown-drop,clowntown-this"];
+    let cli = CliSelect::try_parse_from(args).unwrap();
+    assert_eq!(cli.clowncopterize, ["clowntown-drop", "clowntown-this"]);
+    assert_eq!(
+        cli.enabled_clowntown_flags(),
+        ["clowntown_drop", "clowntown_this"]
+    );
+
+    let cli = CliSelect::try_parse_from(["test", "--clowncopterize", "all"]).unwrap();
+    assert!(cli.clowntown_drop && cli.clowntown_flood && cli.clowntown_this);
+
+    let cli = CliSelect::try_parse_from(["test", "--clowncopterize=none"]).unwrap();
+    assert!(cli.enabled_clowntown_flags().is_empty());
+    assert!(cli.clowntown_diff().is_empty());
+
+    let err = CliSelect::try_parse_from(["test", "--clowncopterize=clowntown-dns"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+
+    // globs and abbreviations need the post-parse steps to resolve them.
+    let err = CliSelect::try_parse_from(["test", "--clowncopterize=clowntown-d*"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+    let args = ["test", "--clowncopterize=clowntown-d*,this"];
+    let cli = CliSelect::clowntown_try_parse_from(args).unwrap();
+    assert!(cli.clowntown_drop && !cli.clowntown_flood && cli.clowntown_this);
+    let err = CliSelect::clowntown_try_parse_from(["test", "--clowncopterize=nope*"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+
+    let mut cli = CliSelect::try_parse_from(["test"]).unwrap();
+    cli.clowncopterize = vec!["clowntown-flood".to_string()];
+    cli.apply_masters();
+    assert!(cli.clowntown_flood);
+    assert!(!cli.clowntown_drop && !cli.clowntown_this);
+}
+
+#[clowncopterize::clowncopterize(
+    unclowncopterizer,
+    inject = "#[arg(long = \"yolo\", hide = true)] pub yolo_mode: bool"
+)]
+#[derive(Parser, Debug)]
+struct CliInject {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[clowncopterize::clowncopterize(inject = {
+    /// Turn every risky flag on
+    #[arg(long, help_heading = "Danger")]
+    clowncopterize: bool
+})]
+#[derive(Parser, Debug)]
+struct CliInjectTokens {
+    #[arg(long)]
+    clowntown_this: bool,
+}
+
+#[test]
+fn test_inject() {
+    use clap::CommandFactory;
+    use clowncopterize::ClowntownCause;
+
+    let cli = CliInject::try_parse_from(["test", "--yolo"]).unwrap();
+    assert!(cli.yolo_mode);
+    assert!(cli.clowntown_this);
+    assert_eq!(
+        cli.clowntown_diff()[0].cause,
+        ClowntownCause::Master("--yolo")
+    );
+    let cli = CliInject::clowntown_try_parse_from(["test", "--clowntown-this", "--un-yolo-mode"])
+        .unwrap();
+    assert!(!cli.clowntown_this);
+    assert!(
+        CliInject::command()
+            .get_arguments()
+            .find(|arg| arg.get_id() == "yolo_mode")
+            .unwrap()
+            .is_hide_set()
+    );
+
+    let cli = CliInjectTokens::try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.clowntown_this);
+    let command = CliInjectTokens::command();
+    let master = command
+        .get_arguments()
+        .find(|arg| arg.get_id() == "clowncopterize")
+        .unwrap();
+    assert_eq!(master.get_help_heading(), Some("Danger"));
+    assert_eq!(
+        master.get_help().unwrap().to_string(),
+        "Turn every risky flag on"
+    );
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer, generate_tests = true)]
+#[derive(Parser, Debug)]
+struct CliClownFlag {
+    #[arg(long)]
+    #[clowntown(owner = "storage", severity = "high")]
+    drop_database: clowncopterize::ClownFlag,
+
+    #[arg(long)]
+    clowntown_this: bool,
+
+    #[arg(long)]
+    verbose: bool,
+}
+
+#[clowncopterize::clowncopterize_test(CliClownFlag)]
+fn clown_flag_checks(_cli: &CliClownFlag) {}
+
+#[test]
+fn test_clown_flag() {
+    use clowncopterize::ClownFlag;
+
+    assert_eq!(
+        CliClownFlag::CLOWNTOWN_FLAGS,
+        ["drop_database", "clowntown_this"]
+    );
+
+    let cli = CliClownFlag::clowntown_try_parse_from(["test"]).unwrap();
+    assert_eq!(cli.drop_database, ClownFlag::from(false));
+    assert_eq!(cli.drop_database.owner(), Some("storage"));
+
+    let mut cli = CliClownFlag::clowntown_try_parse_from(["test", "--drop-database"]).unwrap();
+    assert!(*cli.drop_database);
+    assert!(!cli.clowntown_this);
+    assert_eq!(
+        cli.drop_database.level(),
+        Some(clowncopterize::ClowntownSeverity::High)
+    );
+    assert_eq!(cli.drop_database.info().unwrap().long, "--drop-database");
+    *cli.drop_database = false;
+    assert!(!cli.drop_database.is_on());
+    assert_eq!(format!("{:?}", cli.drop_database), "ClownFlag(false)");
+
+    let cli = CliClownFlag::clowntown_try_parse_from(["test", "--clowncopterize"]).unwrap();
+    assert!(cli.drop_database == true && cli.clowntown_this);
+
+    let args = ["test", "--drop-database", "--un-clowncopterize"];
+    let cli = CliClownFlag::clowntown_try_parse_from(args).unwrap();
+    assert!(!cli.drop_database.is_on());
+
+    let mut cli = CliClownFlag::clowntown_try_parse_from(["test"]).unwrap();
+    cli.clowntown_select(&["drop*"]).unwrap();
+    assert!(cli.drop_database.is_on());
+    assert!(!cli.clowntown_this);
+
+    assert!(CliClownFlag::try_parse_from(["test", "--drop-database=true"]).is_err());
+}
+
+#[test]
+fn test_clown_flag_resolver() {
+    use clowncopterize::ClowntownDecision;
+
+    let resolver = |flag: &str, _enabled: bool| match flag {
+        "drop_database" => ClowntownDecision::Force,
+        _ => ClowntownDecision::Veto,
+    };
+    let args = ["test", "--clowntown-this"];
+    let cli = CliClownFlag::clowntown_try_parse_from_with(args, &resolver).unwrap();
+    assert!(cli.drop_database.is_on());
+    assert!(!cli.clowntown_this);
+}
+
+#[clowncopterize::clowncopterize(unclowncopterizer)]
+#[derive(Parser, Debug)]
+struct CliOptionalValue {
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    clowntown_this: bool,
+
+    #[arg(long, num_args = 0..=1)]
+    drop_database: clowncopterize::ClownFlag,
+}
+
+#[test]
+fn test_optional_value() {
+    let parse = |args: &[&str]| {
+        let cli = CliOptionalValue::try_parse_from(args).unwrap();
+        (cli.clowntown_this, *cli.drop_database)
+    };
+    assert_eq!(parse(&["test"]), (false, false));
+    assert_eq!(
+        parse(&["test", "--clowntown-this", "--drop-database"]),
+        (true, true)
+    );
+    assert_eq!(
+        parse(&["test", "--clowntown-this=true", "--drop-database=true"]),
+        (true, true)
+    );
+    assert_eq!(parse(&["test", "--clowncopterize"]), (true, true));
+    assert_eq!(
+        parse(&[
+            "test",
+            "--clowncopterize",
+            "--clowntown-this=false",
+            "--drop-database=false"
+        ]),
+        (false, false)
+    );
+    assert_eq!(
+        parse(&["test", "--un-clowncopterize", "--drop-database"]),
+        (false, true)
+    );
+}
+
+#[clowncopterize::clowncopterize(manifest)]
+#[derive(Parser, Debug)]
+struct CliManifest {
+    /// Drop "the" database
+    #[arg(long)]
+    #[clowntown(owner = "storage")]
+    clowntown_drop: bool,
+}
+
+#[test]
+fn test_manifest() {
+    assert_eq!(
+        CliManifest::CLOWNTOWN_MANIFEST_JSON,
+        concat!(
+            r#"{"crate":"clowncopterize","version":""#,
+            env!("CARGO_PKG_VERSION"),
+            r#"","struct":"CliManifest","masters":["--clowncopterize"],"flags":[{"field":"clowntown_drop","long":"--clowntown-drop","masters":["--clowncopterize"],"default":false,"help":"Drop \"the\" database","owner":"storage","level":null,"since":null,"expires":null,"link":null}]}"#
+        )
+    );
+
+    let dir = std::env::temp_dir().join(format!("clowntown-manifest-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = CliManifest::write_clowntown_manifest(&dir).unwrap();
+    assert_eq!(path, dir.join("clowntown-CliManifest.json"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        CliManifest::CLOWNTOWN_MANIFEST_JSON
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "inventory")]
+#[test]
+fn test_registered_clown_flags() {
+    let flag = clowncopterize::registered_clown_flags()
+        .find(|flag| flag.struct_name == "CliDocs" && flag.name == "clowntown_drop")
+        .unwrap();
+    assert_eq!(
+        *flag,
+        clowncopterize::ClownFlagInfo {
+            name: "clowntown_drop",
+            long: "--clowntown-drop",
+            struct_name: "CliDocs",
+            module_path: "test",
+            help: "Drop the database",
+            owner: Some("storage"),
+            level: None,
+            since: None,
+            expires: Some("2025-06-30"),
+            link: None,
+        }
+    );
+    assert!(
+        clowncopterize::registered_clown_flags()
+            .any(|flag| flag.struct_name == "Cli" && flag.name == "clowntown_this")
+    );
+}